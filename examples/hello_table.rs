@@ -1,8 +1,10 @@
 use std::fmt::Debug;
 
 use druid_table::{
-    column, AxisMeasurementType, CellCtx, CellRender, CellRenderExt, DataCompare, EditorFactory,
-    ShowHeadings, SortDirection, Table, TableAxis, TableBuilder, TextCell,
+    column, AxisMeasurementType, CellCtx, CellRender, CellRenderExt, CellStyle, ChoiceCell,
+    ColorScale, ColumnGroup, DataBarCell, DataCompare, EditorFactory, FormatRule,
+    HorizontalAlignment, ProgressBarCell, RowMoves, ShowHeadings, SortDirection, Table, TableAxis,
+    TableBuilder, TextCell, TextOverflow, VerticalAlignment,
 };
 
 use druid::im::{vector, Vector};
@@ -22,12 +24,23 @@ use std::f64::consts::PI;
 
 const WINDOW_TITLE: LocalizedString<HelloState> = LocalizedString::new("Hello Table!");
 
+#[derive(Clone, Copy, Data, PartialEq, Debug)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Arabic,
+    Devanagari,
+    Han,
+    Kana,
+}
+
 #[derive(Clone, Data, Lens, Debug)]
 struct HelloRow {
     lang: String,
     greeting: String,
     westernised: String,
     who_knows: f64,
+    script: Script,
 }
 
 impl HelloRow {
@@ -36,12 +49,14 @@ impl HelloRow {
         greeting: impl Into<String>,
         westernised: impl Into<String>,
         percent: f64,
+        script: Script,
     ) -> HelloRow {
         HelloRow {
             lang: lang.into(),
             greeting: greeting.into(),
             westernised: westernised.into(),
             who_knows: percent / 100.,
+            script,
         }
     }
 }
@@ -102,7 +117,7 @@ impl EditorFactory<f64> for PieCell {
 
 fn build_main_widget() -> impl Widget<HelloState> {
     // Need a wrapper widget to get selection/scroll events out of it
-    let row = || HelloRow::new("Japanese", "こんにちは", "Kon'nichiwa", 63.);
+    let row = || HelloRow::new("Japanese", "こんにちは", "Kon'nichiwa", 63., Script::Kana);
 
     let buttons = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
@@ -208,25 +223,109 @@ fn build_table(settings: Settings) -> impl Widget<Vector<HelloRow>> {
             },
         )
         .headings(settings.show_headings)
+        .copy_headers(true)
+        .filters(true)
+        .header_menus::<HelloState>()
+        .row_moves(RowMoves::vector())
+        .row_style(|row: &HelloRow| {
+            if row.who_knows < 0.3 {
+                Some(
+                    CellStyle::new()
+                        .text_color(Color::grey8(0x80))
+                        .strikethrough(),
+                )
+            } else {
+                None
+            }
+        })
         .border(settings.border_thickness)
-        .with_column("Language", TextCell::new().lens(HelloRow::lang))
-        .with_column(
-            "Greeting",
-            TextCell::new().font_size(17.).lens(HelloRow::greeting),
+        .with(
+            column(
+                "Language",
+                TextCell::new()
+                    .overflow(TextOverflow::Ellipsis)
+                    .lens(HelloRow::lang),
+            )
+            .validate(|r: &HelloRow| {
+                if r.lang.chars().all(|c| c.is_alphabetic() || c == ' ') {
+                    Ok(())
+                } else {
+                    Err("A language name can only have letters".into())
+                }
+            }),
         )
-        .with_column(
-            "Westernised",
-            TextCell::new().font_size(17.).lens(HelloRow::westernised),
+        .with(
+            column(
+                "Greeting",
+                TextCell::new().font_size(17.).lens(HelloRow::greeting),
+            )
+            .on_commit(|_, edited: &HelloRow| {
+                if edited.greeting.trim().is_empty() {
+                    Err("A greeting can't be blank".into())
+                } else {
+                    Ok(())
+                }
+            }),
+        )
+        .with(
+            column(
+                "Westernised",
+                TextCell::new().font_size(17.).lens(HelloRow::westernised),
+            )
+            .format(FormatRule::color_scale(
+                |r: &HelloRow| r.who_knows,
+                ColorScale::three(
+                    Color::rgb8(0xF8, 0x69, 0x6B),
+                    Color::rgb8(0xFF, 0xEB, 0x84),
+                    Color::rgb8(0x63, 0xBE, 0x7B),
+                ),
+            ))
+            .format(FormatRule::top_percent(
+                |r: &HelloRow| r.who_knows,
+                20.,
+                CellStyle::new().text_color(Color::rgb8(0x20, 0x20, 0xA0)),
+            )),
         )
         .with(
             column("Who knows?", PieCell {}.lens(HelloRow::who_knows))
-                .sort(SortDirection::Ascending),
+                .sort(SortDirection::Ascending)
+                .tooltip(|r: &HelloRow| {
+                    Some(format!(
+                        "{:.0}% of {} speakers know this",
+                        r.who_knows * 100.,
+                        r.lang
+                    ))
+                }),
+        )
+        .with(
+            column(
+                "Script",
+                ChoiceCell::new(vec![
+                    (Script::Latin, "Latin"),
+                    (Script::Cyrillic, "Cyrillic"),
+                    (Script::Arabic, "Arabic"),
+                    (Script::Devanagari, "Devanagari"),
+                    (Script::Han, "Han"),
+                    (Script::Kana, "Kana"),
+                ])
+                .lens(HelloRow::script),
+            )
+            .merge_repeats(),
         )
+        .with_column("Known bar", DataBarCell::new().lens(HelloRow::who_knows))
         .with_column(
-            "Greeting 2 with very long column name",
-            TextCell::new()
-                .font_name(KeyOrValue::Concrete("Courier New".into()))
-                .lens(HelloRow::greeting),
+            "Known progress",
+            ProgressBarCell::new().lens(HelloRow::who_knows),
+        )
+        .with(
+            column(
+                "Greeting 2 with very long column name",
+                TextCell::new()
+                    .font_name(KeyOrValue::Concrete("Courier New".into()))
+                    .lens(HelloRow::greeting),
+            )
+            .align(HorizontalAlignment::Center)
+            .vertical_align(VerticalAlignment::Middle),
         )
         .with_column(
             "Greeting 3",
@@ -236,7 +335,13 @@ fn build_table(settings: Settings) -> impl Widget<Vector<HelloRow>> {
         )
         .with_column("Greeting 4", TextCell::new().lens(HelloRow::greeting))
         .with_column("Greeting 5", TextCell::new().lens(HelloRow::greeting))
-        .with_column("Greeting 6", TextCell::new().lens(HelloRow::greeting));
+        .with_column("Greeting 6", TextCell::new().lens(HelloRow::greeting))
+        .column_group(ColumnGroup::new("Known").columns(5..=6))
+        .column_group(
+            ColumnGroup::new("More greetings")
+                .columns(7..=8)
+                .group(ColumnGroup::new("Plain").columns(9..=11)),
+        );
 
     let measures = table_builder.build_measures();
     let table = Table::new_in_scope(table_builder.build_args(), measures);
@@ -253,15 +358,15 @@ pub fn main() {
     // create the initial app state
     let initial_state = HelloState {
         items: vector![
-            HelloRow::new("English", "Hello", "Hello", 99.1),
-            HelloRow::new("Français", "Bonjour", "Bonjour", 95.0),
-            HelloRow::new("Espanol", "Hola", "Hola", 95.0),
-            HelloRow::new("Mandarin", "你好", "nǐ hǎo", 85.),
-            HelloRow::new("Hindi", "नमस्ते", "namaste", 74.),
-            HelloRow::new("Arabic", "مرحبا", "marhabaan", 24.),
-            HelloRow::new("Portuguese", "olá", "olá", 30.),
-            HelloRow::new("Russian", "Привет", "Privet", 42.),
-            HelloRow::new("Japanese", "こんにちは", "Kon'nichiwa", 63.),
+            HelloRow::new("English", "Hello", "Hello", 99.1, Script::Latin),
+            HelloRow::new("Français", "Bonjour", "Bonjour", 95.0, Script::Latin),
+            HelloRow::new("Espanol", "Hola", "Hola", 95.0, Script::Latin),
+            HelloRow::new("Mandarin", "你好", "nǐ hǎo", 85., Script::Han),
+            HelloRow::new("Hindi", "नमस्ते", "namaste", 74., Script::Devanagari),
+            HelloRow::new("Arabic", "مرحبا", "marhabaan", 24., Script::Arabic),
+            HelloRow::new("Portuguese", "olá", "olá", 30., Script::Latin),
+            HelloRow::new("Russian", "Привет", "Privet", 42., Script::Cyrillic),
+            HelloRow::new("Japanese", "こんにちは", "Kon'nichiwa", 63., Script::Kana),
         ],
         settings: Settings {
             show_headings: ShowHeadings::Both,
@@ -217,11 +217,14 @@ impl AxisMeasure {
     }
 
     fn last_vis_idx(&self) -> VisIdx {
-        let len = match &self.inner {
+        VisIdx(self.vis_len() - 1)
+    }
+
+    pub(crate) fn vis_len(&self) -> usize {
+        match &self.inner {
             Fixed(f) => f.len,
             Stored(s) => s.borrow().vis_pix_lengths.len(),
-        };
-        VisIdx(len - 1)
+        }
     }
 
     pub(crate) fn pixel_near_border(&self, pixel: f64) -> Option<VisIdx> {
@@ -278,6 +281,16 @@ impl AxisMeasure {
         }
     }
 
+    // Every item back to its default length
+    pub fn reset_lengths(&mut self) {
+        if match &mut self.inner {
+            Fixed(f) => f.reset_lengths(),
+            Stored(s) => s.borrow_mut().reset_lengths(),
+        } {
+            self.version += 1;
+        }
+    }
+
     pub(crate) fn far_pixel_from_vis(&self, idx: VisIdx) -> Option<f64> {
         self.first_pixel_from_vis(idx)
             .map(|p| self.pixels_length_for_vis(idx).map(|l| p + l))
@@ -297,6 +310,7 @@ trait AxisMeasureT: Debug {
 
     fn set_axis_properties(&mut self, border: f64, len: usize, remap: &Remap) -> bool;
     fn set_far_pixel_for_vis(&mut self, idx: VisIdx, pixel: f64) -> bool;
+    fn reset_lengths(&mut self) -> bool;
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -360,19 +374,23 @@ impl AxisMeasureT for FixedAxisMeasure {
         false
     }
 
-    fn set_axis_properties(&mut self, border: f64, len: usize, _remap: &Remap) -> bool {
+    fn set_axis_properties(&mut self, border: f64, len: usize, remap: &Remap) -> bool {
+        // Every item is the same, so the remap only matters for how many are shown
+        let len = remap.vis_len(len);
         let changed = border != self.border || len != self.len;
 
         self.border = border;
         self.len = len;
-        // We don't care about remap as every item is the same... I think.
-        // TODO: Maybe we should care about the length?
         changed
     }
 
     fn set_far_pixel_for_vis(&mut self, _idx: VisIdx, _pixel: f64) -> bool {
         false
     }
+
+    fn reset_lengths(&mut self) -> bool {
+        false
+    }
 }
 
 #[derive(Clone)]
@@ -516,6 +534,18 @@ impl AxisMeasureT for StoredAxisMeasure {
         }
         false
     }
+
+    fn reset_lengths(&mut self) -> bool {
+        let default_pixels = self.default_pixels;
+        if self.log_pix_lengths.iter().all(|l| *l == default_pixels) {
+            return false;
+        }
+        self.log_pix_lengths
+            .iter_mut()
+            .for_each(|l| *l = default_pixels);
+        self.build_maps();
+        true
+    }
 }
 
 #[cfg(not)]
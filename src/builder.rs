@@ -1,11 +1,15 @@
 use crate::columns::{
-    CellDelegate, CellRenderExt, HeaderCell, ProvidedColumns, TableColumn, TextCell,
+    CellDelegate, CellRenderExt, HeaderCell, ProvidedColumns, RowStyleFn, TableColumn, TextCell,
 };
 
 use crate::axis_measure::{AxisMeasure, AxisPair, LogIdx, TableAxis};
 use crate::config::TableConfig;
 use crate::data::{IndexedData, IndexedItems};
+use crate::formatting::CellStyle;
+use crate::groups::{ColumnGroup, ColumnGroups};
 use crate::headings::{HeadersFromIndices, SuppliedHeaders};
+use crate::menus::HeaderMenus;
+use crate::row_moves::RowMoves;
 use crate::table::TableArgs;
 use crate::{CellRender, HeaderBuild};
 use druid::{theme, Data, KeyOrValue};
@@ -25,12 +29,16 @@ impl Default for AxisMeasurementType {
 
 pub struct TableBuilder<RowData: Data, TableData: Data> {
     table_columns: Vec<TableColumn<RowData, Box<dyn CellDelegate<RowData>>>>,
+    row_style: Option<RowStyleFn<RowData>>,
     column_header_delegate: Box<dyn CellRender<String>>,
     row_header_delegate: Box<dyn CellRender<LogIdx>>,
     table_config: TableConfig,
     phantom_td: PhantomData<TableData>,
     show_headings: ShowHeadings,
     measurements: AxisPair<AxisMeasurementType>,
+    column_groups: ColumnGroups,
+    header_menus: Option<HeaderMenus>,
+    row_moves: Option<RowMoves<TableData>>,
 }
 
 impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>> Default
@@ -71,6 +79,7 @@ impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>>
     pub fn new() -> TableBuilder<RowData, TableData> {
         TableBuilder {
             table_columns: Vec::<TableColumn<RowData, Box<dyn CellDelegate<RowData>>>>::new(),
+            row_style: None,
             row_header_delegate: Box::new(
                 HeaderCell::new(TextCell::new().text_color(theme::LABEL_COLOR))
                     .on_result_of(|br: &LogIdx| br.0.to_string()),
@@ -85,6 +94,9 @@ impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>>
                 AxisMeasurementType::Individual,
                 AxisMeasurementType::Individual,
             ),
+            column_groups: ColumnGroups::new(),
+            header_menus: None,
+            row_moves: None,
         }
     }
 
@@ -93,6 +105,37 @@ impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>>
         self
     }
 
+    // Enter starts editing the focused cell, instead of moving down
+    pub fn enter_edits(mut self, enter_edits: bool) -> Self {
+        self.table_config.enter_edits = enter_edits;
+        self
+    }
+
+    // Copied cells start with a row of column headers
+    pub fn copy_headers(mut self, copy_headers: bool) -> Self {
+        self.table_config.copy_headers = copy_headers;
+        self
+    }
+
+    // A button on each column header to filter the rows by the values in the column
+    pub fn filters(mut self, filters: bool) -> Self {
+        self.table_config.filters = filters;
+        self
+    }
+
+    // Right clicking a header opens a menu to sort, hide, pin or size it. Menus are built for the
+    // data of the whole app, so its type is needed.
+    pub fn header_menus<AppData: Data>(mut self) -> Self {
+        self.header_menus = Some(HeaderMenus::new::<AppData>());
+        self
+    }
+
+    // Dragging row headers moves the rows in the data, eg with RowMoves::vector()
+    pub fn row_moves(mut self, row_moves: RowMoves<TableData>) -> Self {
+        self.row_moves = Some(row_moves);
+        self
+    }
+
     pub fn headings(mut self, show_headings: ShowHeadings) -> Self {
         self.show_headings = show_headings;
         self
@@ -121,6 +164,21 @@ impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>>
             .push(TableColumn::new(header, Box::new(cell_render)));
     }
 
+    // A band of header over some of the columns, eg "Q1" over "Jan", "Feb" and "Mar"
+    pub fn column_group(mut self, group: ColumnGroup) -> Self {
+        self.column_groups.add(group);
+        self
+    }
+
+    // Style whole rows from their data, eg to grey out cancelled orders
+    pub fn row_style(
+        mut self,
+        row_style: impl Fn(&RowData) -> Option<CellStyle> + 'static,
+    ) -> Self {
+        self.row_style = Some(Box::new(row_style));
+        self
+    }
+
     pub fn measuring_axis(mut self, axis: TableAxis, measure: AxisMeasurementType) -> Self {
         self.measurements[axis] = measure;
         self
@@ -159,11 +217,14 @@ impl<RowData: Data, TableData: IndexedData<Item = RowData, Idx = LogIdx>>
             )
         );
 
-        TableArgs::new(
-            ProvidedColumns::new(self.table_columns),
-            row_build,
-            col_build,
-            self.table_config,
-        )
+        let mut columns = ProvidedColumns::new(self.table_columns);
+        if let Some(row_style) = self.row_style {
+            columns = columns.row_style(row_style);
+        }
+
+        TableArgs::new(columns, row_build, col_build, self.table_config)
+            .column_groups(self.column_groups)
+            .header_menus(self.header_menus)
+            .row_moves(self.row_moves)
     }
 }
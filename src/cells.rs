@@ -1,18 +1,32 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
 
+use druid::kurbo::BezPath;
+use druid::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
 use druid::widget::prelude::*;
 use druid::{
-    Affine, BoxConstraints, Command, Data, Env, Event, EventCtx, KbKey, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Point, Rect, Selector, Size, UpdateCtx, Widget, WidgetPod,
+    commands, theme, Affine, Application, BoxConstraints, Color, Command, Cursor, Data, Env, Event,
+    EventCtx, KbKey, KeyEvent, LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point,
+    Rect, Selector, Size, Target, TimerToken, UpdateCtx, Vec2, Widget, WidgetPod,
 };
 
 use crate::axis_measure::{AxisMeasure, AxisPair, LogIdx, TableAxis, VisIdx, VisOffset};
 use crate::cells::Editing::Inactive;
-use crate::columns::{CellCtx, CellRender};
+use crate::clipboard::{copy_selection, paste_into_selection, PasteReport};
+use crate::columns::{CellCtx, CellRender, CommitResult};
 use crate::config::{ResolvedTableConfig, TableConfig};
-use crate::data::{IndexedData, Remapper};
+use crate::data::{IndexedData, Pin, Remapper, SortDirection};
+use crate::editors::{EDITOR_ERROR, EDITOR_ERROR_COLOR};
+use crate::fill::{fill_selection, fill_target, FillMode};
+use crate::filters::{distinct_values, FilterAction, FilterPopup};
+use crate::formatting::CellStyle;
+use crate::pinned::{column_event, ColumnRegion, Regions};
 use crate::render_ext::RenderContextExt;
-use crate::selection::{CellRect, SingleCell, TableSelection};
+use crate::selection::{CellDemap, CellRect, SingleCell, TableSelection};
+use crate::spans::{CellSpans, RepeatRuns};
 use crate::table::TableState;
 use crate::{EditorFactory, IndexedItems, Remap};
 use druid_bindings::{BindableAccess, bindable_self_body};
@@ -23,6 +37,31 @@ where
     TableData::Item: Data,
 {
     fn number_of_columns_in_data(&self, data: &TableData) -> usize;
+
+    // Styling for a whole row, applied beneath the style of each of its cells
+    fn row_style(&self, _data: &TableData::Item) -> Option<CellStyle> {
+        None
+    }
+
+    // The text of a column's header, used when copying with headers
+    fn column_header(&self, _col: LogIdx) -> Option<String> {
+        None
+    }
+
+    // Whether any cells are merged. The table only looks for spans if so.
+    fn merges_cells(&self) -> bool {
+        false
+    }
+
+    // Whether runs of the same value one above another in the column are merged
+    fn merges_repeats(&self, _col: LogIdx) -> bool {
+        false
+    }
+
+    // How many columns the cell covers, counting right in the order they are shown
+    fn column_span(&self, _col: LogIdx, _data: &TableData::Item) -> usize {
+        1
+    }
 }
 
 enum Editing<RowData> {
@@ -30,6 +69,12 @@ enum Editing<RowData> {
     Cell {
         single_cell: SingleCell,
         child: WidgetPod<RowData, Box<dyn Widget<RowData>>>,
+        // The editor works on a copy of the row, only written back when the edit is committed
+        value: RowData,
+        // Text in the editor that doesn't parse, as the editor reported it with EDITOR_ERROR
+        editor_error: Option<String>,
+        // Why the edited value can't be committed, shown under the editor
+        error: Option<String>,
     },
 }
 
@@ -41,6 +86,14 @@ impl<RowData: Data> Editing<RowData> {
         }
     }
 
+    // Editors with popups, like dropdown lists, capture the mouse to get clicks outside their cell
+    fn captures_mouse(&self) -> bool {
+        match self {
+            Editing::Cell { child, .. } => child.has_active(),
+            _ => false,
+        }
+    }
+
     fn is_editing(&self, cell: &SingleCell) -> bool {
         match self {
             Editing::Cell { single_cell, .. } => single_cell.vis.eq(&cell.vis),
@@ -48,40 +101,70 @@ impl<RowData: Data> Editing<RowData> {
         }
     }
 
-    fn handle_event<TableData: IndexedItems<Idx = LogIdx, Item = RowData>>(
+    fn handle_event(
         &mut self,
         ctx: &mut EventCtx,
         event: &Event,
-        data: &mut TableData,
         env: &Env,
+        validate: impl FnOnce(&CellCtx, &RowData) -> CommitResult,
     ) {
-        match self {
-            Editing::Cell {
-                ref single_cell,
-                ref mut child,
-            } => {
-                data.with_mut(single_cell.log.row, |row| child.event(ctx, event, row, env));
+        if let Editing::Cell {
+            single_cell,
+            child,
+            value,
+            editor_error,
+            error,
+        } = self
+        {
+            let changed = match event {
+                // Sent up by the editor, which may have an error even if the value didn't change
+                Event::Notification(note) if note.is(EDITOR_ERROR) => {
+                    *editor_error = note.get(EDITOR_ERROR).cloned().flatten();
+                    ctx.set_handled();
+                    true
+                }
+                _ => {
+                    let before = value.clone();
+                    child.event(ctx, event, value, env);
+                    let changed = !before.same(value);
+                    if changed {
+                        ctx.request_update();
+                    }
+                    changed
+                }
+            };
+            // A rejected commit stays shown until the edit changes
+            if changed {
+                let new_error = editor_error
+                    .clone()
+                    .or_else(|| validate(&CellCtx::Cell(single_cell), value).err());
+                if new_error != *error {
+                    *error = new_error;
+                    ctx.request_paint();
+                }
             }
-            _ => {}
         }
     }
 
-    fn start_editing<TableData: IndexedItems<Item = RowData>>(
+    fn start_editing<TableData: IndexedItems<Idx = LogIdx, Item = RowData>>(
         &mut self,
         ctx: &mut EventCtx,
-        data: &mut TableData,
+        data: &TableData,
         cell: &SingleCell,
         make_editor: impl FnMut(&CellCtx) -> Option<Box<dyn Widget<RowData>>>,
     ) {
-        self.stop_editing(data);
         let mut me = make_editor;
         let cell_ctx = CellCtx::Cell(&cell);
-        if let Some(editor) = me(&cell_ctx) {
+        let value = data.with(cell.log.row, |row| row.clone());
+        if let (Some(editor), Some(value)) = (me(&cell_ctx), value) {
             let pod = WidgetPod::new(editor);
 
             *self = Editing::Cell {
                 single_cell: cell.clone(),
                 child: pod,
+                value,
+                editor_error: None,
+                error: None,
             };
 
             ctx.children_changed();
@@ -90,19 +173,88 @@ impl<RowData: Data> Editing<RowData> {
         }
     }
 
-    fn stop_editing<TableData: IndexedItems<Item = RowData>>(&mut self, _data: &mut TableData) {
-        match self {
-            Editing::Cell { .. } => {
-                // Work out what to do with the previous pod if there is one.
-                // We could have lazy editors (that don't write back to data immediately) and send them a special command saying 'you are being shut down'.
-                // Would need to give them data for their row
+    // Writes the edited copy back to the row, unless it is invalid or the check rejects it.
+    // The reason is kept to show under the editor. Committing when nothing is being edited succeeds.
+    fn commit<TableData: IndexedItems<Idx = LogIdx, Item = RowData>>(
+        &mut self,
+        data: &mut TableData,
+        validate: impl FnOnce(&CellCtx, &RowData) -> CommitResult,
+        check: impl FnOnce(&CellCtx, &RowData, &RowData) -> CommitResult,
+    ) -> CommitResult {
+        if let Editing::Cell {
+            single_cell,
+            value,
+            editor_error,
+            error,
+            ..
+        } = self
+        {
+            let cell = CellCtx::Cell(single_cell);
+            let original = data.with(single_cell.log.row, |row| row.clone());
+            let checked = match editor_error {
+                Some(reason) => Err(reason.clone()),
+                None => validate(&cell, value).and_then(|_| match &original {
+                    Some(original) if !original.same(value) => check(&cell, original, value),
+                    _ => Ok(()),
+                }),
+            };
+            if let Err(reason) = checked {
+                *error = Some(reason.clone());
+                return Err(reason);
+            }
+            if original.map_or(false, |original| !original.same(value)) {
+                data.with_mut(single_cell.log.row, |row| *row = value.clone());
             }
-            Editing::Inactive => {}
         }
+        *self = Editing::Inactive;
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
         *self = Editing::Inactive
     }
 }
 
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+const TOOLTIP_MAX_WIDTH: f64 = 300.;
+const TOOLTIP_PADDING: f64 = 4.;
+const FILL_HANDLE_SIZE: f64 = 6.;
+// Auto-sizing a column only looks this far down it
+const AUTO_SIZE_ROWS: usize = 1000;
+const SCROLL_ANIMATION: Duration = Duration::from_millis(250);
+
+// A drag of the fill handle, from the selected cells to the ones they will fill
+struct FillDrag {
+    source: CellRect,
+    target: CellRect,
+    mode: FillMode,
+}
+
+// A scroll to a row or column that moves a little each frame
+struct ScrollAnimation {
+    from: f64,
+    to: f64,
+    elapsed: Duration,
+}
+
+impl ScrollAnimation {
+    // Eases out, so it slows down as it arrives
+    fn advance(&mut self, interval: Duration) -> (f64, bool) {
+        self.elapsed += interval;
+        let t = (self.elapsed.as_secs_f64() / SCROLL_ANIMATION.as_secs_f64()).min(1.);
+        let eased = 1. - (1. - t).powi(3);
+        (self.from + (self.to - self.from) * eased, t >= 1.)
+    }
+}
+
+// The cell under the mouse, whose tooltip shows once the mouse has rested there
+struct Hover {
+    cell: SingleCell,
+    pos: Point,
+    timer: TimerToken,
+    shown: bool,
+}
+
 pub struct Cells<TableData, CellDel>
 where
     TableData: IndexedData<Idx = LogIdx>,
@@ -114,6 +266,22 @@ where
     cell_delegate: CellDel,
     editing: Editing<TableData::Item>,
     dragging_selection: bool,
+    pressed_cell: Option<SingleCell>,
+    filling: Option<FillDrag>,
+    filter: Option<FilterPopup>,
+    hover: Option<Hover>,
+    scroll_animations: AxisPair<Option<ScrollAnimation>>,
+    // The columns these cells show, when some are pinned outside the horizontal scroll
+    region: ColumnRegion,
+    regions: Option<Regions>,
+    // The data from before the last paste and the data it made, so the paste can be undone in
+    // one go while nothing else has changed
+    undo_paste: Option<(TableData, TableData)>,
+    // Whether stored values pass validation, by logical row and column. Cleared when the data
+    // changes, as rows may have been edited, moved or removed.
+    validity: RefCell<HashMap<(LogIdx, LogIdx), bool>>,
+    // Merged runs down columns, found again when the data or its order changes
+    repeat_runs: RefCell<Option<(TableData, AxisPair<Remap>, Rc<RepeatRuns>)>>,
     phantom_td: PhantomData<TableData>,
 }
 
@@ -130,23 +298,747 @@ where
             cell_delegate: cells_delegate,
             editing: Inactive,
             dragging_selection: false,
+            pressed_cell: None,
+            filling: None,
+            filter: None,
+            hover: None,
+            scroll_animations: AxisPair::new(None, None),
+            region: ColumnRegion::Scrolling,
+            regions: None,
+            undo_paste: None,
+            validity: RefCell::new(HashMap::new()),
+            repeat_runs: RefCell::new(None),
             phantom_td: PhantomData::default(),
         }
     }
 
+    pub(crate) fn in_region(mut self, region: ColumnRegion, regions: Regions) -> Self {
+        self.region = region;
+        self.regions = Some(regions);
+        self
+    }
+
+    // The scrolling region does the work for the whole table, like remapping and the clipboard
+    fn is_main(&self) -> bool {
+        self.region == ColumnRegion::Scrolling
+    }
+
+    // The pixel along the columns at the left edge of what is in view
+    fn view_start(&self, data: &TableState<TableData>) -> f64 {
+        let (start, _) = data.region_pixels(self.region);
+        match self.region {
+            ColumnRegion::Scrolling => start + data.scroll_x,
+            _ => start,
+        }
+    }
+
+    fn vis_column(&self, data: &TableState<TableData>, col: LogIdx) -> Option<VisIdx> {
+        let remap = &data.remaps[TableAxis::Columns];
+        let last = remap.max_vis_idx(self.cell_delegate.number_of_columns_in_data(&data.data));
+        VisIdx::range_inc_iter(VisIdx(0), last).find(|vis| remap.get_log_idx(*vis) == Some(col))
+    }
+
+    fn region_of_log_column(
+        &self,
+        data: &TableState<TableData>,
+        col: LogIdx,
+    ) -> Option<(ColumnRegion, &Regions)> {
+        let vis = self.vis_column(data, col)?;
+        Some((data.region_of_column(vis), self.regions.as_ref()?))
+    }
+
+    // The keyboard goes to the region showing the focused cell
+    fn focus_region(&self, ctx: &mut EventCtx, data: &TableState<TableData>) {
+        let region = data
+            .selection
+            .vis_focus()
+            .map_or(self.region, |vis| data.region_of_column(vis.col));
+        match &self.regions {
+            Some(regions) if region != self.region => ctx.set_focus(regions.id(region)),
+            _ => ctx.request_focus(),
+        }
+    }
+
+    // Under the column's header, at the top of the cells in view
+    fn filter_origin(&self, data: &TableState<TableData>, col: LogIdx) -> Point {
+        let x = self
+            .vis_column(data, col)
+            .and_then(|vis| data.measures[TableAxis::Columns].first_pixel_from_vis(vis))
+            .unwrap_or(0.);
+        Point::new(x.max(self.view_start(data)), data.scroll_y)
+    }
+
+    fn open_filter(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut TableState<TableData>,
+        col: LogIdx,
+        env: &Env,
+    ) {
+        if !self.commit_editing(ctx, &mut data.data) {
+            return;
+        }
+        let filters = &data.remap_specs[TableAxis::Rows].filters;
+        let values = distinct_values(&self.cell_delegate, &data.data, filters, col);
+        let hidden = filters.get(&col).cloned().unwrap_or_default();
+        let mut filter = FilterPopup::new(col, values, hidden);
+        filter.layout(self.filter_origin(data, col), env);
+        self.filter = Some(filter);
+        // Being active means clicks outside the cells still close the filter
+        ctx.set_active(true);
+        ctx.request_focus();
+        ctx.request_paint();
+    }
+
+    // The open filter takes the mouse and keys until it is closed
+    fn filter_event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut TableState<TableData>,
+    ) {
+        let filter = match self.filter.as_mut() {
+            Some(filter) => filter,
+            None => return,
+        };
+        let col = filter.col;
+        let action = match filter.event(ctx, event) {
+            Some(action) => action,
+            None => return,
+        };
+        let spec = &mut data.remap_specs[TableAxis::Rows];
+        match action {
+            FilterAction::Apply(hidden) => spec.set_filter(col, hidden),
+            FilterAction::ClearAll => spec.clear_filters(),
+            FilterAction::Close => (),
+        }
+        self.filter = None;
+        ctx.set_active(false);
+        ctx.request_paint();
+    }
+
+    fn paint_filter(&mut self, ctx: &mut PaintCtx, data: &TableState<TableData>, env: &Env) {
+        let origin = match &self.filter {
+            Some(filter) => self.filter_origin(data, filter.col),
+            None => return,
+        };
+        if let Some(filter) = self.filter.as_mut() {
+            filter.layout(origin, env);
+            // Painted above the rest of the table, as it goes outside the cells in view
+            let filter = filter.clone();
+            let env = env.clone();
+            ctx.paint_with_z_index(1, move |ctx| filter.paint(ctx, &env));
+        }
+    }
+
+    // The commands for sorting, hiding, pinning and sizing headers, sent by their menus or the app.
+    // Returns whether the command was one of them.
+    fn layout_command(
+        &mut self,
+        ctx: &mut EventCtx,
+        cmd: &Command,
+        data: &mut TableState<TableData>,
+        env: &Env,
+    ) -> bool {
+        if let Some((col, direction)) = cmd.get(SORT_COLUMN) {
+            data.remap_specs[TableAxis::Rows].set_sort(*col, *direction);
+        } else if let Some((axis, log_idx)) = cmd.get(HIDE_HEADER) {
+            data.remap_specs[*axis].hidden.insert(*log_idx);
+        } else if let Some(axis) = cmd.get(SHOW_HIDDEN) {
+            data.remap_specs[*axis].hidden.clear();
+        } else if let Some((col, pin)) = cmd.get(PIN_COLUMN) {
+            data.remap_specs[TableAxis::Columns].pin(*col, *pin);
+        } else if let Some((axis, log_idx)) = cmd.get(AUTO_SIZE) {
+            self.auto_size(ctx, data, *axis, *log_idx, env);
+        } else if cmd.is(RESET_LAYOUT) {
+            for axis in [TableAxis::Rows, TableAxis::Columns].iter() {
+                data.remap_specs[*axis].reset_layout();
+                data.measures[*axis].reset_lengths();
+            }
+            ctx.request_layout();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    // Fits the row or column to the largest text in it, as the text cells lay it out
+    fn auto_size(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut TableState<TableData>,
+        axis: TableAxis,
+        log_idx: LogIdx,
+        env: &Env,
+    ) {
+        let padding = match &self.resolved_config {
+            Some(rtc) => rtc.cell_padding,
+            None => return,
+        };
+        let columns = self.cell_delegate.number_of_columns_in_data(&data.data);
+        let lens = AxisPair::new(data.data.idx_len(), columns);
+        let remap = &data.remaps[axis];
+        let vis = VisIdx::range_inc_iter(VisIdx(0), remap.max_vis_idx(lens[axis]))
+            .find(|vis| remap.get_log_idx(*vis) == Some(log_idx));
+        let vis = match vis {
+            Some(vis) if data.measures[axis].can_resize(vis) => vis,
+            _ => return,
+        };
+
+        let cross = axis.cross_axis();
+        let mut texts = Vec::new();
+        if axis == TableAxis::Columns {
+            texts.extend(self.cell_delegate.column_header(log_idx));
+        }
+        let cross_last = data.remaps[cross].max_vis_idx(lens[cross]);
+        for cross_vis in VisIdx::range_inc_iter(VisIdx(0), cross_last).take(AUTO_SIZE_ROWS) {
+            let vis_cell = AxisPair::new_for_axis(axis, vis, cross_vis);
+            if let Some(log) = data.remaps.get_log_cell(&vis_cell) {
+                let cell = SingleCell::new(vis_cell, log);
+                let cell_delegate = &self.cell_delegate;
+                texts.extend(
+                    data.data
+                        .with(log.row, |row| {
+                            cell_delegate.cell_text(&CellCtx::Cell(&cell), row)
+                        })
+                        .flatten(),
+                );
+            }
+        }
+
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let length = texts
+            .into_iter()
+            .filter_map(|text| {
+                ctx.text()
+                    .new_text_layout(text)
+                    .font(FontFamily::SYSTEM_UI, font_size)
+                    .build()
+                    .ok()
+            })
+            .map(|layout| axis.length_from_size(&layout.size()))
+            .fold(0., f64::max);
+        let measure = &mut data.measures[axis];
+        match measure.first_pixel_from_vis(vis) {
+            Some(first) if length > 0. => {
+                measure.set_far_pixel_for_vis(vis, first + length + 2. * padding);
+                ctx.request_layout();
+            }
+            _ => (),
+        }
+    }
+
+    fn spans<'a>(&'a self, data: &'a TableState<TableData>) -> CellSpans<'a, TableData, CellDel> {
+        let mut cached = self.repeat_runs.borrow_mut();
+        let runs = match &*cached {
+            Some((for_data, for_remaps, runs))
+                if for_data.same(&data.data) && for_remaps.same(&data.remaps) =>
+            {
+                runs.clone()
+            }
+            _ => {
+                let runs = Rc::new(RepeatRuns::find(
+                    &self.cell_delegate,
+                    &data.data,
+                    &data.remaps,
+                ));
+                *cached = Some((data.data.clone(), data.remaps.clone(), runs.clone()));
+                runs
+            }
+        };
+        CellSpans::with_runs(&self.cell_delegate, &data.data, &data.remaps, runs)
+    }
+
+    // A merged block of cells is found as its top left cell
     fn find_cell(&self, data: &TableState<TableData>, pos: &Point) -> Option<SingleCell> {
-        let (r, c) = (
+        let mut vis = AxisPair::new(
             data.measures[TableAxis::Rows].vis_idx_from_pixel(pos.y)?,
             data.measures[TableAxis::Columns].vis_idx_from_pixel(pos.x)?,
         );
-        let log_row = data.remaps[TableAxis::Rows].get_log_idx(r)?;
-        let log_col = data.remaps[TableAxis::Columns].get_log_idx(c)?;
-        Some(SingleCell::new(
-            AxisPair::new(r, c),
-            AxisPair::new(log_row, log_col),
+        if let Some(span) = self.spans(data).span_of(&vis) {
+            vis = AxisPair::new(span.start_row, span.start_col);
+        }
+        let log_row = data.remaps[TableAxis::Rows].get_log_idx(vis.row)?;
+        let log_col = data.remaps[TableAxis::Columns].get_log_idx(vis.col)?;
+        Some(SingleCell::new(vis, AxisPair::new(log_row, log_col)))
+    }
+
+    // Where a cell is painted, covering the whole block if it is merged
+    fn cell_pixel_rect(
+        &self,
+        data: &TableState<TableData>,
+        vis: &AxisPair<VisIdx>,
+    ) -> Option<Rect> {
+        self.spans(data)
+            .span_of(vis)
+            .unwrap_or_else(|| CellRect::point(vis.row, vis.col))
+            .to_pixel_rect(&data.measures)
+    }
+
+    // Offer a mouse event to the renderer of a cell, translated to where that cell is painted
+    fn cell_claims_event(
+        &self,
+        ctx: &mut EventCtx,
+        data: &TableState<TableData>,
+        cell: &SingleCell,
+        event: &Event,
+        env: &Env,
+    ) -> bool {
+        let padding = match &self.resolved_config {
+            Some(rtc) => rtc.cell_padding,
+            None => return false,
+        };
+        let origin = match self.cell_pixel_rect(data, &cell.vis) {
+            Some(rect) => rect.inset(-padding).origin().to_vec2(),
+            None => return false,
+        };
+        let translate = |me: &MouseEvent| {
+            let mut me = me.clone();
+            me.pos -= origin;
+            me
+        };
+        let cell_event = match event {
+            Event::MouseDown(me) => Event::MouseDown(translate(me)),
+            Event::MouseUp(me) => Event::MouseUp(translate(me)),
+            _ => return false,
+        };
+
+        let cell_delegate = &self.cell_delegate;
+        let cell_ctx = CellCtx::Cell(cell);
+        data.data
+            .with(cell.log.row, |row| {
+                cell_delegate.event(ctx, &cell_ctx, &cell_event, row, env)
+            })
+            .unwrap_or(false)
+    }
+
+    fn track_hover(&mut self, ctx: &mut EventCtx, data: &TableState<TableData>, pos: Point) {
+        let cell = self.find_cell(data, &pos);
+        if let (Some(hover), Some(cell)) = (&mut self.hover, &cell) {
+            if hover.cell == *cell {
+                // A shown tooltip stays where it is
+                if !hover.shown {
+                    hover.pos = pos;
+                }
+                return;
+            }
+        }
+        if self.hide_tooltip() {
+            ctx.request_paint();
+        }
+        self.hover = cell.map(|cell| Hover {
+            cell,
+            pos,
+            timer: ctx.request_timer(TOOLTIP_DELAY),
+            shown: false,
+        });
+    }
+
+    // Returns whether a tooltip was showing
+    fn hide_tooltip(&mut self) -> bool {
+        self.hover.take().map_or(false, |hover| hover.shown)
+    }
+
+    // Returns false if the edit was rejected, in which case the editor stays open
+    fn commit_editing(&mut self, ctx: &mut EventCtx, data: &mut TableData) -> bool {
+        let cell_delegate = &self.cell_delegate;
+        let committed = self.editing.commit(
+            data,
+            |cell, edited| cell_delegate.validate(cell, edited),
+            |cell, original, edited| cell_delegate.can_commit(cell, original, edited),
+        );
+        match committed {
+            Ok(()) => {
+                ctx.request_layout();
+                true
+            }
+            // The reason is kept with the edit and shown under the editor, which stays open
+            Err(_) => {
+                ctx.request_paint();
+                false
+            }
+        }
+    }
+
+    fn copy_to_clipboard(&self, data: &TableState<TableData>) {
+        let include_headers = self
+            .resolved_config
+            .as_ref()
+            .map_or(false, |rtc| rtc.copy_headers);
+        let copied = copy_selection(
+            &self.cell_delegate,
+            &data.data,
+            &data.remaps,
+            &data.selection,
+            include_headers,
+        );
+        if let Some(copied) = copied {
+            copied.put_on_clipboard();
+        }
+    }
+
+    fn paste_from_clipboard(&mut self, ctx: &mut EventCtx, data: &mut TableState<TableData>) {
+        let text = match Application::global().clipboard().get_string() {
+            Some(text) => text,
+            None => return,
+        };
+        let before = data.data.clone();
+        let report = paste_into_selection(
+            &self.cell_delegate,
+            &mut data.data,
+            &data.remaps,
+            &data.selection,
+            &text,
+        );
+        if report.pasted > 0 {
+            self.undo_paste = Some((before, data.data.clone()));
+        }
+        Self::report_failures(ctx, report);
+    }
+
+    fn undo_paste(&mut self, data: &mut TableState<TableData>) -> bool {
+        match self.undo_paste.take() {
+            Some((before, after)) if after.same(&data.data) => {
+                data.data = before;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn report_failures(ctx: &mut EventCtx, report: PasteReport) {
+        if !report.failures.is_empty() {
+            for failure in &report.failures {
+                log::warn!(
+                    "Could not write '{}' into {:?}: {}",
+                    failure.text,
+                    failure.cell,
+                    failure.reason
+                );
+            }
+            ctx.submit_command(Command::new(PASTE_FAILED, report, Target::Auto));
+        }
+    }
+
+    // The block of selected cells that has a fill handle, which is the current one when ranges
+    // were added with Ctrl
+    fn fill_source(selection: &TableSelection) -> Option<CellRect> {
+        match selection.current() {
+            TableSelection::SingleCell(sc) => Some(CellRect::point(sc.vis.row, sc.vis.col)),
+            TableSelection::CellRange(range) => Some(range.to_cell_rect()),
+            _ => None,
+        }
+    }
+
+    // A small square on the bottom right corner of the selected cells
+    fn fill_handle_rect(&self, data: &TableState<TableData>) -> Option<Rect> {
+        if self.editing.is_active() {
+            return None;
+        }
+        let selected = Self::fill_source(&data.selection)?.to_pixel_rect(&data.measures)?;
+        Some(Rect::from_center_size(
+            (selected.x1, selected.y1),
+            (FILL_HANDLE_SIZE, FILL_HANDLE_SIZE),
         ))
     }
 
+    fn over_fill_handle(&self, data: &TableState<TableData>, pos: Point) -> bool {
+        self.fill_handle_rect(data)
+            .map_or(false, |handle| handle.inflate(2., 2.).contains(pos))
+    }
+
+    // The cell under the pointer, or the nearest one when it has left the table
+    fn nearest_cell(data: &TableState<TableData>, pos: Point) -> Option<AxisPair<VisIdx>> {
+        let vis_at = |axis: TableAxis, pixel: f64| {
+            let measure = &data.measures[axis];
+            let last_pixel = (measure.total_pixel_length() - 1.).max(0.);
+            measure.vis_idx_from_pixel(pixel.max(0.).min(last_pixel))
+        };
+        AxisPair::new(
+            vis_at(TableAxis::Rows, pos.y),
+            vis_at(TableAxis::Columns, pos.x),
+        )
+        .opt()
+    }
+
+    fn last_cell(data: &TableState<TableData>) -> Option<AxisPair<VisIdx>> {
+        let last = |axis: TableAxis| data.measures[axis].vis_len().checked_sub(1).map(VisIdx);
+        AxisPair::new(last(TableAxis::Rows), last(TableAxis::Columns)).opt()
+    }
+
+    // A page is as many rows as are in view, but always at least one
+    fn page_from(&self, data: &TableState<TableData>, row: VisIdx, down: bool) -> VisIdx {
+        let measure = &data.measures[TableAxis::Rows];
+        let view = self
+            .regions
+            .as_ref()
+            .map_or(0., |regions| regions.view_height());
+        let start = measure.first_pixel_from_vis(row).unwrap_or(0.);
+        let pixel = if down { start + view } else { start - view };
+        let last_pixel = (measure.total_pixel_length() - 1.).max(0.);
+        match measure.vis_idx_from_pixel(pixel.max(0.).min(last_pixel)) {
+            Some(to) if to != row => to,
+            _ => row + VisOffset(if down { 1 } else { -1 }),
+        }
+    }
+
+    // Where a navigation key goes from a cell. With Ctrl, arrows go to the edge of the data and
+    // Home and End to the corners of the grid.
+    fn nav_target(
+        &self,
+        data: &TableState<TableData>,
+        key: &KbKey,
+        jump: bool,
+        from: AxisPair<VisIdx>,
+    ) -> Option<AxisPair<VisIdx>> {
+        let last = Self::last_cell(data)?;
+        let mut to = from;
+        match key {
+            KbKey::ArrowUp | KbKey::ArrowDown | KbKey::ArrowLeft | KbKey::ArrowRight => {
+                let (axis, step) = match key {
+                    KbKey::ArrowUp => (TableAxis::Rows, -1),
+                    KbKey::ArrowDown => (TableAxis::Rows, 1),
+                    KbKey::ArrowLeft => (TableAxis::Columns, -1),
+                    _ => (TableAxis::Columns, 1),
+                };
+                to[axis] = if !jump {
+                    from[axis] + VisOffset(step)
+                } else if step < 0 {
+                    VisIdx(0)
+                } else {
+                    last[axis]
+                };
+            }
+            KbKey::PageUp | KbKey::PageDown => {
+                to.row = self.page_from(data, from.row, *key == KbKey::PageDown)
+            }
+            KbKey::Home => {
+                to.col = VisIdx(0);
+                if jump {
+                    to.row = VisIdx(0);
+                }
+            }
+            KbKey::End => {
+                to.col = last.col;
+                if jump {
+                    to.row = last.row;
+                }
+            }
+            _ => return None,
+        }
+        Some(AxisPair::new(to.row.min(last.row), to.col.min(last.col)))
+    }
+
+    // Scrolls so the row or column is in full view, if it isn't already
+    fn scroll_to(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut TableState<TableData>,
+        axis: TableAxis,
+        vis: VisIdx,
+        animate: bool,
+    ) {
+        self.scroll_animations[axis] = None;
+        let view = match (axis, self.regions.as_ref()) {
+            (TableAxis::Rows, Some(regions)) => regions.view_height(),
+            (TableAxis::Columns, Some(regions)) => regions.scrolling_width(),
+            (_, None) => return,
+        };
+        let from = data.scroll(axis);
+        match data.scroll_to_show(axis, vis, view) {
+            Some(to) if to != from && animate => {
+                self.scroll_animations[axis] = Some(ScrollAnimation {
+                    from,
+                    to,
+                    elapsed: Duration::default(),
+                });
+                ctx.request_anim_frame();
+            }
+            Some(to) => data.set_scroll(axis, to),
+            None => (),
+        }
+    }
+
+    // After the focus moves, or the far corner when extending, that cell is brought into view
+    fn scroll_to_selection(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut TableState<TableData>,
+        old_focus: Option<AxisPair<VisIdx>>,
+    ) {
+        let moved = if data.selection.vis_focus() != old_focus.as_ref() {
+            data.selection.vis_focus()
+        } else {
+            data.selection.vis_extent()
+        };
+        if let Some(vis) = moved.cloned() {
+            self.scroll_to(ctx, data, TableAxis::Rows, vis.row, false);
+            self.scroll_to(ctx, data, TableAxis::Columns, vis.col, false);
+        }
+    }
+
+    // Landing inside a merged block of cells selects the whole block
+    fn select_cell(
+        &self,
+        data: &TableState<TableData>,
+        mut vis: AxisPair<VisIdx>,
+    ) -> Option<TableSelection> {
+        let spans = self.spans(data);
+        if let Some(span) = spans.span_of(&vis) {
+            vis = AxisPair::new(span.start_row, span.start_col);
+        }
+        let log = spans.get_log_cell(&vis)?;
+        Some(TableSelection::SingleCell(SingleCell::new(vis, log)))
+    }
+
+    // With Shift the far corner moves, growing or shrinking the selection
+    fn navigate(
+        &self,
+        data: &TableState<TableData>,
+        ke: &KeyEvent,
+    ) -> Option<Option<TableSelection>> {
+        let jump = ke.mods.ctrl() || ke.mods.meta();
+        let from = if ke.mods.shift() {
+            data.selection.vis_extent()
+        } else {
+            data.selection.vis_focus()
+        };
+        let to = self.nav_target(data, &ke.key, jump, *from?)?;
+        Some(if ke.mods.shift() {
+            let log = self.spans(data).get_log_cell(&to);
+            log.and_then(|log| {
+                data.selection
+                    .move_extent(TableSelection::SingleCell(SingleCell::new(to, log)))
+            })
+        } else {
+            self.select_cell(data, to)
+        })
+    }
+
+    // Tab moves along the row and on to the start of the next, or back with Shift
+    fn tab(&self, data: &TableState<TableData>, back: bool) -> Option<TableSelection> {
+        let focus = *data.selection.vis_focus()?;
+        let last = Self::last_cell(data)?;
+        let step = if back { -1 } else { 1 };
+        let along = data
+            .selection
+            .move_focus(TableAxis::Columns, VisOffset(step), &self.spans(data))
+            .filter(|sel| {
+                sel.vis_focus()
+                    .map_or(false, |vis| vis.col != focus.col && vis.col <= last.col)
+            });
+        along.or_else(|| {
+            let row = focus.row + VisOffset(step);
+            if row == focus.row || row > last.row {
+                return None;
+            }
+            let col = if back { last.col } else { VisIdx(0) };
+            self.select_cell(data, AxisPair::new(row, col))
+        })
+    }
+
+    fn select_all(&self, data: &TableState<TableData>) -> Option<TableSelection> {
+        let last = Self::last_cell(data)?;
+        let rect = CellRect::new((VisIdx(0), last.row), (VisIdx(0), last.col));
+        data.selection.select_rect(&rect, &self.spans(data))
+    }
+
+    fn finish_fill(&mut self, ctx: &mut EventCtx, data: &mut TableState<TableData>) {
+        if let Some(fill) = self.filling.take() {
+            let report = fill_selection(
+                &self.cell_delegate,
+                &mut data.data,
+                &data.remaps,
+                &fill.source,
+                &fill.target,
+                fill.mode,
+            );
+            Self::report_failures(ctx, report);
+            let filled = data
+                .selection
+                .current()
+                .select_rect(&fill.target, &data.remaps);
+            if let Some(sel) = filled {
+                data.selection = data.selection.replace_current(sel);
+            }
+            ctx.request_paint();
+        }
+    }
+
+    // Fills the selection from its top row, or a single cell from the one above it. Each range
+    // added with Ctrl is filled on its own.
+    fn fill_down(&self, ctx: &mut EventCtx, data: &mut TableState<TableData>) {
+        let targets: Vec<CellRect> = match &data.selection {
+            TableSelection::Layers(layers) => layers.iter().filter_map(Self::fill_source).collect(),
+            selection => Self::fill_source(selection).into_iter().collect(),
+        };
+        let mut report = PasteReport::default();
+        for target in targets {
+            let source_row = if target.start_row < target.end_row {
+                target.start_row
+            } else if target.start_row > VisIdx(0) {
+                target.start_row + VisOffset(-1)
+            } else {
+                continue;
+            };
+            let source =
+                CellRect::new((source_row, source_row), (target.start_col, target.end_col));
+            let filled = fill_selection(
+                &self.cell_delegate,
+                &mut data.data,
+                &data.remaps,
+                &source,
+                &target.union(&source),
+                FillMode::Copy,
+            );
+            report.pasted += filled.pasted;
+            report.failures.extend(filled.failures);
+        }
+        Self::report_failures(ctx, report);
+    }
+
+    fn enter_edits(&self) -> bool {
+        self.resolved_config
+            .as_ref()
+            .map_or(false, |rtc| rtc.enter_edits)
+    }
+
+    // Opens the editor on the focused cell from the keyboard, telling it how it was started
+    fn edit_focus(&mut self, ctx: &mut EventCtx, data: &TableState<TableData>, start: EditStart) {
+        if let Some(focus) = data.selection.focus().cloned() {
+            let cd = &mut self.cell_delegate;
+            self.editing
+                .start_editing(ctx, &data.data, &focus, |cell_ctx| cd.make_editor(cell_ctx));
+            if self.editing.is_active() {
+                ctx.submit_command(Command::new(START_EDIT, start, ctx.widget_id()));
+            }
+        }
+    }
+
+    fn editor_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        let cell_delegate = &self.cell_delegate;
+        self.editing.handle_event(ctx, event, env, |cell, edited| {
+            cell_delegate.validate(cell, edited)
+        });
+    }
+
+    fn observe_rows(cell_delegate: &mut CellDel, data: &TableState<TableData>) {
+        if !cell_delegate.observes_rows() {
+            return;
+        }
+        let row_remap = &data.remaps[TableAxis::Rows];
+        let last_vis = row_remap.max_vis_idx(data.data.idx_len());
+
+        cell_delegate.start_observe();
+        for vis_row_idx in VisIdx::range_inc_iter(VisIdx(0), last_vis) {
+            if let Some(log_row_idx) = row_remap.get_log_idx(vis_row_idx) {
+                data.data
+                    .with(log_row_idx, |row| cell_delegate.observe(row));
+            }
+        }
+        cell_delegate.end_observe();
+    }
+
     fn measured_size(&mut self, measures: &AxisPair<AxisMeasure>) -> Size {
         measures.map(|m| m.total_pixel_length()).size()
     }
@@ -158,17 +1050,23 @@ where
         env: &Env,
         rect: &CellRect,
     ) {
+        let spans = self.spans(data).spans_in(rect);
         for vis_row_idx in rect.rows() {
             let col_remap = &data.remaps[TableAxis::Columns];
             let measures = &data.measures;
 
             if let Some(log_row_idx) = data.remaps[TableAxis::Rows].get_log_idx(vis_row_idx) {
                 let table_data = &data.data;
+                // Merged cells are painted afterwards, in one piece
+                let mut cols = rect.cols().filter(|vis_col_idx| {
+                    let vis = AxisPair::new(vis_row_idx, *vis_col_idx);
+                    !spans.iter().any(|span| span.contains_cell(&vis))
+                });
                 table_data.with(log_row_idx, |row| {
                     self.paint_row(
                         ctx,
                         env,
-                        &mut rect.cols(),
+                        &mut cols,
                         log_row_idx,
                         vis_row_idx,
                         row,
@@ -178,6 +1076,52 @@ where
                 });
             }
         }
+        for span in &spans {
+            self.paint_span(ctx, data, env, span);
+        }
+    }
+
+    // A merged block shows its top left cell, even if that is scrolled out of view
+    fn paint_span(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &TableState<TableData>,
+        env: &Env,
+        span: &CellRect,
+    ) -> Option<()> {
+        let rtc = self.resolved_config.as_ref()?;
+        let vis = AxisPair::new(span.start_row, span.start_col);
+        let sc = SingleCell::new(vis.clone(), data.remaps.get_log_cell(&vis)?);
+        let cell_rect = span.to_pixel_rect(&data.measures)?;
+        data.data.with(sc.log.row, |row| {
+            let row_style = self.cell_delegate.row_style(row);
+            let cell = CellCtx::Cell(&sc);
+            let styled_env = self.paint_cell_background(ctx, env, &cell, row, row_style, cell_rect);
+            let env = styled_env.as_ref().unwrap_or(env);
+            let padded_rect = cell_rect.inset(-rtc.cell_padding);
+            self.paint_cell(ctx, &cell, row, padded_rect, padded_rect, env);
+            self.paint_cell_marks(ctx, rtc, &sc, row, cell_rect);
+        })
+    }
+
+    // Fills the background from the style of the cell, returning the env to paint it with if
+    // the style changes it
+    fn paint_cell_background(
+        &self,
+        ctx: &mut PaintCtx,
+        env: &Env,
+        cell: &CellCtx,
+        row: &TableData::Item,
+        row_style: Option<CellStyle>,
+        cell_rect: Rect,
+    ) -> Option<Env> {
+        let style = cell_style_in_row(&self.cell_delegate, cell, row, row_style);
+        if let Some(background) = style.as_ref().and_then(|s| s.background.as_ref()) {
+            ctx.fill(cell_rect, background);
+        }
+        style
+            .filter(|s| s.changes_env())
+            .map(|s| s.apply_to_env(env))
     }
 
     fn paint_row(
@@ -192,12 +1136,19 @@ where
         measures: &AxisPair<AxisMeasure>,
     ) -> Option<()> {
         if let Some(rtc) = &self.resolved_config {
+            let row_style = self.cell_delegate.row_style(row);
+            let mut spilled = Vec::new();
+            let mut edges: Option<(SingleCell, SingleCell)> = None;
             for vis_col_idx in cols {
                 if let Some(log_col_idx) = col_remap.get_log_idx(vis_col_idx) {
                     let sc = SingleCell::new(
                         AxisPair::new(vis_row_idx, vis_col_idx),
                         AxisPair::new(log_row_idx, log_col_idx),
                     );
+                    edges = match edges {
+                        Some((first, _)) => Some((first, sc.clone())),
+                        None => Some((sc.clone(), sc.clone())),
+                    };
 
                     let cell_rect = Rect::from_origin_size(
                         measures
@@ -210,30 +1161,187 @@ where
                             .size(),
                     );
                     let padded_rect = cell_rect.inset(-rtc.cell_padding);
+                    let cell = CellCtx::Cell(&sc);
+                    let styled_env = self.paint_cell_background(
+                        ctx,
+                        env,
+                        &cell,
+                        row,
+                        row_style.clone(),
+                        cell_rect,
+                    );
 
-                    ctx.with_save(|ctx| {
-                        let layout_origin = padded_rect.origin().to_vec2();
-                        ctx.clip(padded_rect);
-                        ctx.transform(Affine::translate(layout_origin));
-                        let cell = CellCtx::Cell(&sc);
-                        ctx.with_child_ctx(padded_rect, |ctxt| {
-                            self.cell_delegate.paint(ctxt, &cell, row, env);
-                        });
-                    });
+                    if self.cell_delegate.spills(&cell, row) {
+                        // Painted once the rest of the row is done, so neighbours don't cover it
+                        let clip = self.spill_rect(&sc, row, col_remap, measures, padded_rect);
+                        spilled.push((sc.clone(), padded_rect, clip, styled_env));
+                    } else {
+                        let env = styled_env.as_ref().unwrap_or(env);
+                        self.paint_cell(ctx, &cell, row, padded_rect, padded_rect, env);
+                    }
 
-                    ctx.stroke_bottom_left_border(
-                        &cell_rect,
-                        &rtc.cells_border,
-                        rtc.cell_border_thickness,
-                    );
+                    self.paint_cell_marks(ctx, rtc, &sc, row, cell_rect);
                 } else {
                     log::warn!("Could not find logical column for {:?}", vis_col_idx)
                 }
             }
+
+            // Cells out of view spill into blank ones in view, so they are painted too
+            if let Some((first, last)) = edges {
+                let outside = [(first, VisOffset(-1)), (last, VisOffset(1))];
+                for (edge, step) in outside.iter() {
+                    let sc = match self.spilling_in(edge, *step, row, col_remap, measures) {
+                        Some(sc) => sc,
+                        None => continue,
+                    };
+                    let cell_rect = Rect::from_origin_size(
+                        measures
+                            .zip_with(&sc.vis, |m, vis| m.first_pixel_from_vis(*vis))
+                            .opt()?
+                            .point(),
+                        measures
+                            .zip_with(&sc.vis, |m, vis| m.pixels_length_for_vis(*vis))
+                            .opt()?
+                            .size(),
+                    );
+                    let padded_rect = cell_rect.inset(-rtc.cell_padding);
+                    let clip = self.spill_rect(&sc, row, col_remap, measures, padded_rect);
+                    let style = cell_style_in_row(
+                        &self.cell_delegate,
+                        &CellCtx::Cell(&sc),
+                        row,
+                        row_style.clone(),
+                    );
+                    let styled_env = style
+                        .filter(|s| s.changes_env())
+                        .map(|s| s.apply_to_env(env));
+                    spilled.push((sc, padded_rect, clip, styled_env));
+                }
+            }
+
+            for (sc, padded_rect, clip, styled_env) in spilled {
+                let env = styled_env.as_ref().unwrap_or(env);
+                self.paint_cell(ctx, &CellCtx::Cell(&sc), row, padded_rect, clip, env);
+            }
         }
         Some(())
     }
 
+    // A cell outside the painted columns whose content spills over the blank ones from the edge.
+    // The step is the way out from the edge.
+    fn spilling_in(
+        &self,
+        edge: &SingleCell,
+        step: VisOffset,
+        row: &TableData::Item,
+        col_remap: &Remap,
+        measures: &AxisPair<AxisMeasure>,
+    ) -> Option<SingleCell> {
+        let col = spill_source(edge.vis.col, step, |vis_col| {
+            Self::cell_in_row(edge, vis_col, col_remap, measures)
+                .map(|sc| self.cell_delegate.is_blank(&CellCtx::Cell(&sc), row))
+        })?;
+        let sc = Self::cell_in_row(edge, col, col_remap, measures)?;
+        if_opt!(self.cell_delegate.spills(&CellCtx::Cell(&sc), row), sc)
+    }
+
+    fn is_valid(&self, sc: &SingleCell, row: &TableData::Item) -> bool {
+        *self
+            .validity
+            .borrow_mut()
+            .entry((sc.log.row, sc.log.col))
+            .or_insert_with(|| self.cell_delegate.validate(&CellCtx::Cell(sc), row).is_ok())
+    }
+
+    // The invalid marker and the border, over the content
+    fn paint_cell_marks(
+        &self,
+        ctx: &mut PaintCtx,
+        rtc: &ResolvedTableConfig,
+        sc: &SingleCell,
+        row: &TableData::Item,
+        cell_rect: Rect,
+    ) {
+        if !self.is_valid(sc, row) {
+            paint_invalid_marker(ctx, cell_rect);
+        }
+
+        ctx.stroke_bottom_left_border(&cell_rect, &rtc.cells_border, rtc.cell_border_thickness);
+    }
+
+    fn paint_cell(
+        &self,
+        ctx: &mut PaintCtx,
+        cell: &CellCtx,
+        row: &TableData::Item,
+        padded_rect: Rect,
+        clip: Rect,
+        env: &Env,
+    ) {
+        ctx.with_save(|ctx| {
+            let layout_origin = padded_rect.origin().to_vec2();
+            ctx.clip(clip);
+            ctx.transform(Affine::translate(layout_origin));
+            ctx.with_child_ctx(padded_rect, |ctxt| {
+                self.cell_delegate.paint(ctxt, cell, row, env);
+            });
+        });
+    }
+
+    // Another cell in the same row, if the column is in the table
+    fn cell_in_row(
+        sc: &SingleCell,
+        vis_col: VisIdx,
+        col_remap: &Remap,
+        measures: &AxisPair<AxisMeasure>,
+    ) -> Option<SingleCell> {
+        measures[TableAxis::Columns].far_pixel_from_vis(vis_col)?;
+        let log_col = col_remap.get_log_idx(vis_col)?;
+        Some(SingleCell::new(
+            AxisPair::new(sc.vis.row, vis_col),
+            AxisPair::new(sc.log.row, log_col),
+        ))
+    }
+
+    fn is_blank_at(
+        &self,
+        sc: &SingleCell,
+        vis_col: VisIdx,
+        row: &TableData::Item,
+        col_remap: &Remap,
+        measures: &AxisPair<AxisMeasure>,
+    ) -> bool {
+        Self::cell_in_row(sc, vis_col, col_remap, measures).map_or(false, |neighbour| {
+            self.cell_delegate.is_blank(&CellCtx::Cell(&neighbour), row)
+        })
+    }
+
+    // The run of blank cells either side of a spilling cell, which its content may cover
+    fn spill_rect(
+        &self,
+        sc: &SingleCell,
+        row: &TableData::Item,
+        col_remap: &Remap,
+        measures: &AxisPair<AxisMeasure>,
+        padded_rect: Rect,
+    ) -> Rect {
+        let col_measure = &measures[TableAxis::Columns];
+        let (first, last) = blank_run(sc.vis.col, |vis_col| {
+            self.is_blank_at(sc, vis_col, row, col_remap, measures)
+        });
+
+        Rect::new(
+            col_measure
+                .first_pixel_from_vis(first)
+                .unwrap_or(padded_rect.x0),
+            padded_rect.y0,
+            col_measure
+                .far_pixel_from_vis(last)
+                .unwrap_or(padded_rect.x1),
+            padded_rect.y1,
+        )
+    }
+
     fn paint_selections(
         &mut self,
         ctx: &mut PaintCtx,
@@ -253,33 +1361,104 @@ where
             }
         }
 
+        if let Some(target) = self
+            .filling
+            .as_ref()
+            .and_then(|fill| fill.target.to_pixel_rect(&data.measures))
+        {
+            ctx.stroke(target, sel_color, (rtc.cell_border_thickness * 1.5).min(2.));
+        }
+
+        if let Some(handle) = self.fill_handle_rect(data) {
+            ctx.fill(handle, &rtc.focus_color);
+            ctx.stroke(handle, &rtc.cells_background, 1.);
+        }
+
         let focus = selected.focus?;
 
         ctx.stroke(
-            CellRect::point(focus.row, focus.col).to_pixel_rect(&data.measures)?,
+            self.cell_pixel_rect(data, &focus)?,
             &rtc.focus_color,
             (rtc.cell_border_thickness * 1.5).min(2.),
         );
         Some(())
     }
 
+    fn paint_tooltip(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &TableState<TableData>,
+        rtc: &ResolvedTableConfig,
+        visible: Rect,
+        env: &Env,
+    ) -> Option<()> {
+        let hover = self.hover.as_ref().filter(|hover| hover.shown)?;
+        let cell_rect = self.cell_pixel_rect(data, &hover.cell.vis)?;
+        let size = cell_rect.inset(-rtc.cell_padding).size();
+        let cell_delegate = &self.cell_delegate;
+        let tooltip = data
+            .data
+            .with(hover.cell.log.row, |row| {
+                cell_delegate.cell_tooltip(ctx.text(), size, &CellCtx::Cell(&hover.cell), row, env)
+            })
+            .flatten()?;
+
+        let layout = ctx
+            .text()
+            .new_text_layout(tooltip)
+            .font(FontFamily::SYSTEM_UI, env.get(theme::TEXT_SIZE_NORMAL))
+            .text_color(env.get(theme::LABEL_COLOR))
+            .max_width(TOOLTIP_MAX_WIDTH)
+            .build()
+            .ok()?;
+        let box_size = layout.size() + Size::new(TOOLTIP_PADDING * 2., TOOLTIP_PADDING * 2.);
+
+        // Below and to the right of the pointer, flipped above it if it would go out of view
+        let mut origin = hover.pos + Vec2::new(12., 16.);
+        if origin.y + box_size.height > visible.y1 {
+            origin.y = hover.pos.y - box_size.height - 4.;
+        }
+        origin.x = origin.x.min(visible.x1 - box_size.width).max(visible.x0);
+
+        let tooltip_rect = Rect::from_origin_size(origin, box_size);
+        ctx.fill(tooltip_rect, &env.get(theme::BACKGROUND_LIGHT));
+        ctx.stroke(tooltip_rect, &env.get(theme::BORDER_DARK), 1.);
+        ctx.draw_text(
+            &layout,
+            origin + Vec2::new(TOOLTIP_PADDING, TOOLTIP_PADDING),
+        );
+        Some(())
+    }
+
+    // The editor is laid out where it is in the region, rather than along all the columns
     fn paint_editing(
         &mut self,
         ctx: &mut PaintCtx,
         data: &TableState<TableData>,
+        start: f64,
         env: &Env,
     ) -> Option<()> {
+        let vis = match &self.editing {
+            Editing::Cell { single_cell, .. } => single_cell.vis.clone(),
+            _ => return Some(()),
+        };
+        let rect = self.cell_pixel_rect(data, &vis)? - Vec2::new(start, 0.);
         match &mut self.editing {
-            Editing::Cell { single_cell, child } => {
-                let vis = &single_cell.vis;
-                // TODO: excessive unwrapping
-                let rect = CellRect::point(vis.row, vis.col).to_pixel_rect(&data.measures)?;
-
+            Editing::Cell {
+                child,
+                value,
+                error,
+                ..
+            } => {
                 ctx.with_save(|ctx| {
                     ctx.render_ctx.clip(rect);
-                    data.data
-                        .with(single_cell.log.row, |row| child.paint(ctx, row, env));
+                    child.paint(ctx, value, env);
                 });
+
+                if let Some(error) = error {
+                    ctx.stroke(rect.inset(-1.), &EDITOR_ERROR_COLOR, 2.);
+                    paint_edit_error(ctx, rect, error, env);
+                }
             }
             _ => (),
         }
@@ -287,7 +1466,110 @@ where
     }
 }
 
+// A small triangle in the top right corner of a cell whose value fails validation
+fn paint_invalid_marker(ctx: &mut PaintCtx, cell_rect: Rect) {
+    let size = (cell_rect.height() / 3.).min(7.);
+    let mut marker = BezPath::new();
+    marker.move_to((cell_rect.x1 - size, cell_rect.y0));
+    marker.line_to((cell_rect.x1, cell_rect.y0));
+    marker.line_to((cell_rect.x1, cell_rect.y0 + size));
+    marker.close_path();
+    ctx.fill(marker, &EDITOR_ERROR_COLOR);
+}
+
+// The message for an edit that can't be committed, in a box below the editor
+fn paint_edit_error(ctx: &mut PaintCtx, editor_rect: Rect, error: &str, env: &Env) -> Option<()> {
+    let layout = ctx
+        .text()
+        .new_text_layout(error.to_string())
+        .font(FontFamily::SYSTEM_UI, env.get(theme::TEXT_SIZE_NORMAL))
+        .text_color(Color::WHITE)
+        .max_width(TOOLTIP_MAX_WIDTH)
+        .build()
+        .ok()?;
+    let box_size = layout.size() + Size::new(TOOLTIP_PADDING * 2., TOOLTIP_PADDING * 2.);
+    let mut origin = Point::new(editor_rect.x0, editor_rect.y1 + 2.);
+    // Above the editor if it would go out of view
+    if origin.y + box_size.height > ctx.region().bounding_box().y1 {
+        origin.y = editor_rect.y0 - box_size.height - 2.;
+    }
+    let message_rect = Rect::from_origin_size(origin, box_size);
+    ctx.fill(message_rect, &EDITOR_ERROR_COLOR);
+    ctx.draw_text(
+        &layout,
+        origin + Vec2::new(TOOLTIP_PADDING, TOOLTIP_PADDING),
+    );
+    Some(())
+}
+
 pub const INIT_CELLS: Selector<()> = Selector::new("druid-builtin.table.init-cells");
+// Sent by editors to commit their value and close, eg when a choice is clicked
+pub const COMMIT_EDIT: Selector<()> = Selector::new("druid-builtin.table.commit-edit");
+// Sent when some cells of a paste or fill could not be parsed or failed validation. Those cells
+// are left as they were, and the rest is applied.
+pub const PASTE_FAILED: Selector<PasteReport> = Selector::new("druid-builtin.table.paste-failed");
+// Sent by the column headings to open the filter for a column
+pub const SHOW_FILTER: Selector<LogIdx> = Selector::new("druid-builtin.table.show-filter");
+// Clears the filters on every column, showing all the rows
+pub const CLEAR_FILTERS: Selector<()> = Selector::new("druid-builtin.table.clear-filters");
+// Sorts the rows by just this column, after any fixed sorts. No direction stops sorting by it.
+// These commands are sent by the header menus, and can be sent by the app to the table.
+pub const SORT_COLUMN: Selector<(LogIdx, Option<SortDirection>)> =
+    Selector::new("druid-builtin.table.sort-column");
+// Hides a row or column until the hidden ones on its axis are shown again
+pub const HIDE_HEADER: Selector<(TableAxis, LogIdx)> =
+    Selector::new("druid-builtin.table.hide-header");
+pub const SHOW_HIDDEN: Selector<TableAxis> = Selector::new("druid-builtin.table.show-hidden");
+// Pins a column to one side of the table, or unpins it
+pub const PIN_COLUMN: Selector<(LogIdx, Option<Pin>)> =
+    Selector::new("druid-builtin.table.pin-column");
+// Fits a row or column to its content
+pub const AUTO_SIZE: Selector<(TableAxis, LogIdx)> = Selector::new("druid-builtin.table.auto-size");
+// Undoes moving, hiding, pinning and resizing headers
+pub const RESET_LAYOUT: Selector<()> = Selector::new("druid-builtin.table.reset-layout");
+// Sent to an editor opened from the keyboard, just after it is added
+pub const START_EDIT: Selector<EditStart> = Selector::new("druid-builtin.table.start-edit");
+// Scrolls the table until a row or column is in view. Hidden and filtered out ones are ignored.
+pub const SCROLL_TO: Selector<ScrollTo> = Selector::new("druid-builtin.table.scroll-to");
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScrollTo {
+    pub axis: TableAxis,
+    pub idx: LogIdx,
+    pub animate: bool,
+}
+
+impl ScrollTo {
+    pub fn row(idx: LogIdx) -> Self {
+        ScrollTo {
+            axis: TableAxis::Rows,
+            idx,
+            animate: false,
+        }
+    }
+
+    pub fn column(idx: LogIdx) -> Self {
+        ScrollTo {
+            axis: TableAxis::Columns,
+            idx,
+            animate: false,
+        }
+    }
+
+    // Glides there rather than jumping
+    pub fn animated(mut self) -> Self {
+        self.animate = true;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditStart {
+    // A key was typed on the cell, and the text should replace its content
+    Typed(String),
+    // F2 or Enter, to change the content
+    CaretAtEnd,
+}
 pub const REMAP_CHANGED: Selector<TableAxis> = Selector::new("druid-builtin.table.remap-changed");
 
 impl<TableData, ColDel> Widget<TableState<TableData>> for Cells<TableData, ColDel>
@@ -303,15 +1585,103 @@ where
         data: &mut TableState<TableData>,
         env: &Env,
     ) {
-        if let Some(rtc) = &self.resolved_config {
+        // The editor is where it is in this region, everything else goes along all the columns
+        let local_event = event;
+        let event = &column_event(data, self.region, self.regions.as_ref(), event);
+        if self.filter.is_some() {
+            if let Event::MouseDown(_)
+            | Event::MouseUp(_)
+            | Event::MouseMove(_)
+            | Event::Wheel(_)
+            | Event::KeyDown(_) = event
+            {
+                self.filter_event(ctx, event, data);
+                return;
+            }
+        }
+
+        // Copied out, as the helpers below need self mutably
+        let border = self
+            .resolved_config
+            .as_ref()
+            .map(|rtc| rtc.cell_border_thickness);
+        if let Some(border) = border {
             let mut new_selection: Option<TableSelection> = None;
             let mut remap_changed = AxisPair::new(false, false);
 
             match event {
+                Event::MouseMove(me) if !self.dragging_selection && self.filling.is_none() => {
+                    if self.over_fill_handle(data, me.pos) {
+                        ctx.set_cursor(&Cursor::Crosshair);
+                    }
+                    self.track_hover(ctx, data, me.pos)
+                }
+                Event::MouseDown(_) | Event::KeyDown(_) | Event::Wheel(_) => {
+                    if self.hide_tooltip() {
+                        ctx.request_paint();
+                    }
+                }
+                Event::Timer(token) => {
+                    if let Some(hover) = self.hover.as_mut().filter(|h| h.timer == *token) {
+                        hover.shown = true;
+                        ctx.request_paint();
+                        ctx.set_handled();
+                    }
+                }
+                Event::AnimFrame(interval) => {
+                    for axis in [TableAxis::Rows, TableAxis::Columns].iter() {
+                        if let Some(animation) = self.scroll_animations[*axis].as_mut() {
+                            let (scroll, done) = animation.advance(Duration::from_nanos(*interval));
+                            data.set_scroll(*axis, scroll);
+                            if done {
+                                self.scroll_animations[*axis] = None;
+                            } else {
+                                ctx.request_anim_frame();
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            // An editor capturing the mouse sees clicks first, and leaves the ones it doesn't want
+            if let Event::MouseDown(_) = event {
+                if self.editing.captures_mouse() {
+                    self.editor_event(ctx, local_event, env);
+                }
+            }
+
+            match event {
+                // The editor has taken the click
+                Event::MouseDown(_) if ctx.is_handled() => (),
+                Event::MouseDown(me) if me.count == 1 && self.over_fill_handle(data, me.pos) => {
+                    if let Some(source) = Self::fill_source(&data.selection) {
+                        // Ctrl copies the cells instead of continuing a series
+                        let mode = if me.mods.ctrl() || me.mods.meta() {
+                            FillMode::Copy
+                        } else {
+                            FillMode::Series
+                        };
+                        self.filling = Some(FillDrag {
+                            target: source.clone(),
+                            source,
+                            mode,
+                        });
+                        ctx.set_active(true);
+                        ctx.set_handled();
+                    }
+                }
                 Event::MouseDown(me) => {
                     if let Some(cell) = self.find_cell(data, &me.pos) {
                         if self.editing.is_editing(&cell) {
-                            self.editing.handle_event(ctx, event, &mut data.data, env);
+                            self.editor_event(ctx, local_event, env);
+                        } else if !self.commit_editing(ctx, &mut data.data) {
+                            // The edit was rejected, so the editor stays open
+                            ctx.set_handled();
+                        } else if self.cell_claims_event(ctx, data, &cell, event, env) {
+                            self.pressed_cell = Some(cell);
+                            ctx.set_active(true);
+                            ctx.set_handled();
                         } else {
                             if me.count == 1 {
                                 if me.mods.meta() || me.mods.ctrl() {
@@ -323,56 +1693,164 @@ where
                                 }
 
                                 ctx.set_handled();
-                                self.editing.stop_editing(&mut data.data);
                                 self.dragging_selection = true;
                                 ctx.set_active(true);
                             } else if me.count == 2 {
                                 let cd = &mut self.cell_delegate;
-                                self.editing.start_editing(
-                                    ctx,
-                                    &mut data.data,
-                                    &cell,
-                                    |cell_ctx| cd.make_editor(cell_ctx),
-                                );
+                                self.editing
+                                    .start_editing(ctx, &data.data, &cell, |cell_ctx| {
+                                        cd.make_editor(cell_ctx)
+                                    });
                             }
                         }
                     }
                 }
+                Event::MouseMove(me) if self.filling.is_some() => {
+                    if let (Some(fill), Some(pointer)) =
+                        (&mut self.filling, Self::nearest_cell(data, me.pos))
+                    {
+                        let target = fill_target(&fill.source, &pointer);
+                        if target != fill.target {
+                            fill.target = target;
+                            ctx.request_paint();
+                        }
+                    }
+                    ctx.set_cursor(&Cursor::Crosshair);
+                    ctx.set_handled();
+                }
+                Event::MouseUp(_) if self.filling.is_some() => {
+                    self.finish_fill(ctx, data);
+                    ctx.set_active(false);
+                    ctx.set_handled();
+                }
                 Event::MouseMove(me) if !self.editing.is_active() && self.dragging_selection => {
                     if let Some(cell) = self.find_cell(data, &me.pos) {
                         new_selection = data.selection.move_extent(cell.into());
                     }
                 }
+                Event::MouseUp(me) if self.pressed_cell.is_some() => {
+                    if let Some(pressed) = self.pressed_cell.take() {
+                        if self.find_cell(data, &me.pos).as_ref() == Some(&pressed) {
+                            self.cell_claims_event(ctx, data, &pressed, event, env);
+                        }
+                    }
+                    ctx.set_active(false);
+                    ctx.set_handled();
+                }
                 Event::MouseUp(_) if self.dragging_selection => {
                     self.dragging_selection = false;
                     ctx.set_active(false);
                 }
                 Event::Command(cmd) => {
-                    if let Some(_) = cmd.get(INIT_CELLS) {
+                    if cmd.is(INIT_CELLS) && self.is_main() {
                         data.remap_specs[TableAxis::Rows] = self.cell_delegate.initial_spec();
                         remap_changed[TableAxis::Rows] = true;
                         remap_changed[TableAxis::Columns] = true;
+                    } else if let Some(col) = cmd.get(SHOW_FILTER) {
+                        // The filter opens in the region showing its column
+                        match self.region_of_log_column(data, *col) {
+                            Some((region, regions)) if region != self.region => {
+                                ctx.submit_command(SHOW_FILTER.with(*col).to(regions.id(region)))
+                            }
+                            _ => self.open_filter(ctx, data, *col, env),
+                        }
+                        ctx.set_handled();
+                    } else if cmd.is(CLEAR_FILTERS) {
+                        data.remap_specs[TableAxis::Rows].clear_filters();
+                        ctx.set_handled();
+                    } else if self.layout_command(ctx, cmd, data, env) {
+                        ctx.set_handled();
+                    } else if let Some(scroll_to) = cmd.get(SCROLL_TO) {
+                        let vis = data.remaps[scroll_to.axis].get_vis_idx(scroll_to.idx);
+                        if let Some(vis) = vis {
+                            self.scroll_to(ctx, data, scroll_to.axis, vis, scroll_to.animate);
+                        }
+                        ctx.set_handled();
                     } else if let Some(ax) = cmd.get(REMAP_CHANGED) {
                         log::info!("Remap changed:{:?}", ax);
                         remap_changed[*ax] = true;
+                    } else if cmd.is(commands::COPY) && self.is_main() && !self.editing.is_active()
+                    {
+                        self.copy_to_clipboard(data);
+                        ctx.set_handled();
+                    } else if cmd.is(commands::PASTE) && self.is_main() && !self.editing.is_active()
+                    {
+                        self.paste_from_clipboard(ctx, data);
+                        ctx.set_handled();
+                    } else if cmd.is(commands::UNDO) && !self.editing.is_active() {
+                        if self.undo_paste(data) {
+                            ctx.set_handled();
+                        }
+                    } else if cmd.is(COMMIT_EDIT) && self.editing.is_active() {
+                        if self.commit_editing(ctx, &mut data.data) {
+                            self.focus_region(ctx, data);
+                        }
+                        ctx.set_handled();
                     } else {
-                        match &mut self.editing {
-                            Editing::Cell { single_cell, child } => {
-                                data.data.with_mut(single_cell.log.row, |row| {
-                                    child.event(ctx, event, row, env)
-                                });
-                            }
-                            _ => (),
+                        self.editor_event(ctx, local_event, env);
+                    }
+                }
+                Event::KeyDown(ke) if self.editing.is_active() => match &ke.key {
+                    KbKey::Enter | KbKey::Tab => {
+                        if self.commit_editing(ctx, &mut data.data) {
+                            // Like Excel, Enter moves down and Tab moves right (back with Shift)
+                            let (axis, step) = match (&ke.key, ke.mods.shift()) {
+                                (KbKey::Enter, false) => (TableAxis::Rows, 1),
+                                (KbKey::Enter, true) => (TableAxis::Rows, -1),
+                                (_, false) => (TableAxis::Columns, 1),
+                                (_, true) => (TableAxis::Columns, -1),
+                            };
+                            new_selection =
+                                data.selection
+                                    .move_focus(axis, VisOffset(step), &self.spans(data));
+                            ctx.request_focus();
                         }
+                        ctx.set_handled();
                     }
+                    KbKey::Escape => {
+                        self.editing.cancel();
+                        ctx.request_focus();
+                        ctx.request_layout();
+                        ctx.set_handled();
+                    }
+                    _ => self.editor_event(ctx, local_event, env),
+                },
+                Event::KeyDown(ke) if self.filling.is_some() => {
+                    if ke.key == KbKey::Escape {
+                        self.filling = None;
+                        ctx.set_active(false);
+                        ctx.request_paint();
+                    }
+                    ctx.set_handled();
                 }
                 Event::KeyDown(ke) if !self.editing.is_active() => {
+                    let plain_arrow = ke.mods.is_empty()
+                        && matches!(
+                            ke.key,
+                            KbKey::ArrowUp
+                                | KbKey::ArrowDown
+                                | KbKey::ArrowLeft
+                                | KbKey::ArrowRight
+                        );
+                    let navigated = if plain_arrow {
+                        None
+                    } else {
+                        self.navigate(data, ke)
+                    };
                     match &ke.key {
+                        _ if navigated.is_some() => {
+                            new_selection = navigated.flatten();
+                            ctx.set_handled();
+                        }
+                        KbKey::Tab => {
+                            new_selection = self.tab(data, ke.mods.shift());
+                            ctx.set_handled();
+                        }
                         KbKey::ArrowDown => {
                             new_selection = data.selection.move_focus(
                                 TableAxis::Rows,
                                 VisOffset(1),
-                                &data.remaps,
+                                &self.spans(data),
                             );
                             ctx.set_handled();
                         }
@@ -380,7 +1858,7 @@ where
                             new_selection = data.selection.move_focus(
                                 TableAxis::Rows,
                                 VisOffset(-1),
-                                &data.remaps,
+                                &self.spans(data),
                             );
                             ctx.set_handled();
                         }
@@ -388,7 +1866,7 @@ where
                             new_selection = data.selection.move_focus(
                                 TableAxis::Columns,
                                 VisOffset(1),
-                                &data.remaps,
+                                &self.spans(data),
                             );
                             ctx.set_handled();
                         }
@@ -396,13 +1874,16 @@ where
                             new_selection = data.selection.move_focus(
                                 TableAxis::Columns,
                                 VisOffset(-1),
-                                &data.remaps,
+                                &self.spans(data),
                             );
                             ctx.set_handled();
                         }
                         KbKey::Character(s) if s == " " => {
                             // This is to match Excel
-                            if ke.mods.meta() || ke.mods.ctrl() {
+                            if (ke.mods.meta() || ke.mods.ctrl()) && ke.mods.shift() {
+                                new_selection = self.select_all(data);
+                                ctx.set_handled();
+                            } else if ke.mods.meta() || ke.mods.ctrl() {
                                 new_selection = data
                                     .selection
                                     .extend_from_focus_in_axis(&TableAxis::Columns, &data.remaps);
@@ -412,35 +1893,92 @@ where
                                     .selection
                                     .extend_from_focus_in_axis(&TableAxis::Rows, &data.remaps);
                                 ctx.set_handled();
+                            } else {
+                                self.edit_focus(ctx, data, EditStart::Typed(s.clone()));
                             }
-
-                            // TODO - when Ctrl + Shift, select full grid
                         }
-                        KbKey::Copy => log::info!("Copy"),
+                        KbKey::Character(s)
+                            if (ke.mods.ctrl() || ke.mods.meta())
+                                && s.eq_ignore_ascii_case("a") =>
+                        {
+                            new_selection = self.select_all(data);
+                            ctx.set_handled();
+                        }
+                        KbKey::Character(s)
+                            if (ke.mods.ctrl() || ke.mods.meta())
+                                && s.eq_ignore_ascii_case("c") =>
+                        {
+                            self.copy_to_clipboard(data);
+                            ctx.set_handled();
+                        }
+                        KbKey::Character(s)
+                            if (ke.mods.ctrl() || ke.mods.meta())
+                                && s.eq_ignore_ascii_case("v") =>
+                        {
+                            self.paste_from_clipboard(ctx, data);
+                            ctx.set_handled();
+                        }
+                        KbKey::Character(s)
+                            if (ke.mods.ctrl() || ke.mods.meta())
+                                && s.eq_ignore_ascii_case("z") =>
+                        {
+                            if self.undo_paste(data) {
+                                ctx.set_handled();
+                            }
+                        }
+                        KbKey::Character(s)
+                            if (ke.mods.ctrl() || ke.mods.meta())
+                                && s.eq_ignore_ascii_case("d") =>
+                        {
+                            self.fill_down(ctx, data);
+                            ctx.set_handled();
+                        }
+                        KbKey::Paste => {
+                            self.paste_from_clipboard(ctx, data);
+                            ctx.set_handled();
+                        }
+                        // Typing replaces the content, like a spreadsheet
+                        KbKey::Character(s) if !(ke.mods.ctrl() || ke.mods.meta()) => {
+                            self.edit_focus(ctx, data, EditStart::Typed(s.clone()));
+                        }
+                        KbKey::F2 => self.edit_focus(ctx, data, EditStart::CaretAtEnd),
+                        KbKey::Enter if self.enter_edits() => {
+                            self.edit_focus(ctx, data, EditStart::CaretAtEnd)
+                        }
+                        KbKey::Enter => {
+                            let step = if ke.mods.shift() { -1 } else { 1 };
+                            new_selection = data.selection.move_focus(
+                                TableAxis::Rows,
+                                VisOffset(step),
+                                &self.spans(data),
+                            );
+                            ctx.set_handled();
+                        }
+                        KbKey::Copy => {
+                            self.copy_to_clipboard(data);
+                            ctx.set_handled();
+                        }
                         k => log::info!("Key {:?}", k),
                     }
                 }
-                _ => match &mut self.editing {
-                    Editing::Cell { single_cell, child } => {
-                        data.data
-                            .with_mut(single_cell.log.row, |row| child.event(ctx, event, row, env));
-                    }
-                    _ => (),
-                },
+                _ => self.editor_event(ctx, local_event, env),
             }
 
             if let Some(sel) = new_selection {
+                let old_focus = data.selection.vis_focus().cloned();
                 data.selection = sel;
                 if data.selection.has_focus() && !self.editing.is_active() {
-                    ctx.request_focus();
+                    self.focus_region(ctx, data);
                 }
+                self.scroll_to_selection(ctx, data, old_focus);
             }
 
             // TODO: move to update but need versioned pointers on measures
             if remap_changed[TableAxis::Rows] {
                 data.remap_axis(TableAxis::Rows, |d, s| self.cell_delegate.remap_items(d, s));
+                Self::observe_rows(&mut self.cell_delegate, data);
                 data.measures[TableAxis::Rows].set_axis_properties(
-                    rtc.cell_border_thickness,
+                    border,
                     data.data.idx_len(),
                     &data.remaps[TableAxis::Rows],
                 );
@@ -453,7 +1991,7 @@ where
                 });
                 log::info!("Remap for cols {:?}", data.remaps[TableAxis::Columns]);
                 data.measures[TableAxis::Columns].set_axis_properties(
-                    rtc.cell_border_thickness,
+                    border,
                     self.cell_delegate.number_of_columns_in_data(&data.data),
                     &data.remaps[TableAxis::Columns],
                 );
@@ -472,14 +2010,18 @@ where
     ) {
         if let LifeCycle::WidgetAdded = event {
             self.resolved_config = Some(self.config.resolve(env));
-            ctx.submit_command(Command::new(INIT_CELLS, (), ctx.widget_id()));
+            if self.is_main() {
+                ctx.submit_command(Command::new(INIT_CELLS, (), ctx.widget_id()));
+            }
+        } else if let LifeCycle::HotChanged(false) = event {
+            if self.hide_tooltip() {
+                ctx.request_paint();
+            }
         } else {
             match &mut self.editing {
-                Editing::Cell { single_cell, child } => {
+                Editing::Cell { child, value, .. } => {
                     log::info!("LC event {:?}", event);
-                    data.data.with(single_cell.log.row, |row| {
-                        child.lifecycle(ctx, event, row, env)
-                    });
+                    child.lifecycle(ctx, event, value, env)
                 }
                 _ => (),
             }
@@ -491,9 +2033,25 @@ where
         ctx: &mut UpdateCtx,
         old_data: &TableState<TableData>,
         data: &TableState<TableData>,
-        _env: &Env,
+        env: &Env,
     ) {
+        if let Editing::Cell { child, value, .. } = &mut self.editing {
+            child.update(ctx, value, env);
+        }
+
+        if !old_data.data.same(&data.data) {
+            self.validity.get_mut().clear();
+        }
+
         // TODO move all sorting up to table level so we don't need commands
+        if !self.is_main() {
+            if !old_data.remaps.same(&data.remaps) {
+                ctx.request_layout();
+            } else if !old_data.same(data) {
+                ctx.request_paint();
+            }
+            return;
+        }
         if !old_data.data.same(&data.data)
             || !old_data.remap_specs[TableAxis::Rows].same(&data.remap_specs[TableAxis::Rows])
         {
@@ -526,59 +2084,129 @@ where
         env: &Env,
     ) -> Size {
         bc.debug_check("TableCells");
+        let (start, end) = data.region_pixels(self.region);
+
+        // An editor on a merged cell covers the whole block
+        let span_rect = match &self.editing {
+            Editing::Cell { single_cell, .. } => self
+                .spans(data)
+                .span_of(&single_cell.vis)
+                .and_then(|span| span.to_pixel_rect(&data.measures)),
+            _ => None,
+        };
 
         match &mut self.editing {
-            Editing::Cell { single_cell, child } => {
+            Editing::Cell {
+                single_cell,
+                child,
+                value,
+                ..
+            } => {
                 let vis = &single_cell.vis;
                 (|| -> Option<_> {
-                    let bc = BoxConstraints::tight(
-                        data.measures
-                            .zip_with(&vis, |m, v| m.pixels_length_for_vis(*v))
-                            .opt()?
-                            .size(),
-                    );
-                    let origin = data
-                        .measures
-                        .zip_with(&vis, |m, v| m.first_pixel_from_vis(*v))
-                        .opt()?
-                        .point();
-                    data.data.with(single_cell.log.row, |row| {
-                        let size = child.layout(ctx, &bc, row, env);
-                        child.set_layout_rect(ctx, row, env, Rect::from_origin_size(origin, size))
-                    });
+                    let (bc, origin) = match span_rect {
+                        Some(rect) => (BoxConstraints::tight(rect.size()), rect.origin()),
+                        None => (
+                            BoxConstraints::tight(
+                                data.measures
+                                    .zip_with(&vis, |m, v| m.pixels_length_for_vis(*v))
+                                    .opt()?
+                                    .size(),
+                            ),
+                            data.measures
+                                .zip_with(&vis, |m, v| m.first_pixel_from_vis(*v))
+                                .opt()?
+                                .point(),
+                        ),
+                    };
+                    let origin = origin - Vec2::new(start, 0.);
+                    let size = child.layout(ctx, &bc, value, env);
+                    child.set_layout_rect(ctx, value, env, Rect::from_origin_size(origin, size));
                     Some(())
                 })();
             }
             _ => (),
         }
         let measured = self.measured_size(&data.measures);
-        let size = bc.constrain(measured);
-        size
+        bc.constrain(Size::new(end - start, measured.height))
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &TableState<TableData>, env: &Env) {
         self.cell_delegate.init(ctx, env); // TODO reduce calls? Invalidate on some changes
 
         let rtc = self.config.resolve(env);
-        let rect = ctx.region().bounding_box();
+        // Painted along all the columns, moved back to where this region starts
+        let (start, end) = data.region_pixels(self.region);
+        let rect = ctx.region().bounding_box() + Vec2::new(start, 0.);
+        let height = self.measured_size(&data.measures).height;
 
-        let draw_rect = rect.intersect(Rect::from_origin_size(
-            Point::ZERO,
-            self.measured_size(&data.measures),
-        ));
-
-        ctx.fill(draw_rect, &rtc.cells_background);
+        let draw_rect = rect.intersect(Rect::new(start, 0., end, height));
 
         let cell_rect = CellRect::new(
             data.measures[TableAxis::Rows].vis_range_from_pixels(draw_rect.y0, draw_rect.y1),
             data.measures[TableAxis::Columns].vis_range_from_pixels(draw_rect.x0, draw_rect.x1),
         );
+        let to_region = Affine::translate((-start, 0.));
+
+        ctx.with_save(|ctx| {
+            ctx.transform(to_region);
+            ctx.fill(draw_rect, &rtc.cells_background);
+            self.paint_cells(ctx, data, env, &cell_rect);
+            self.paint_selections(ctx, data, &rtc, &cell_rect);
+        });
 
-        self.paint_cells(ctx, data, env, &cell_rect);
-        self.paint_selections(ctx, data, &rtc, &cell_rect);
+        self.paint_editing(ctx, data, start, env);
+        ctx.with_save(|ctx| {
+            ctx.transform(to_region);
+            self.paint_tooltip(ctx, data, &rtc, rect, env);
+            self.paint_filter(ctx, data, env);
+        });
+    }
+}
+
+// The style a cell is painted with. Its own style and its column's rules win over its row's.
+fn cell_style_in_row<TableData, CellsDel>(
+    cell_delegate: &CellsDel,
+    cell: &CellCtx,
+    row: &TableData::Item,
+    row_style: Option<CellStyle>,
+) -> Option<CellStyle>
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CellsDel: CellsDelegate<TableData>,
+{
+    CellStyle::merge_opt(row_style, cell_delegate.cell_style(cell, row))
+}
 
-        self.paint_editing(ctx, data, env);
+// The columns either side of a cell that are blank, as far as the run goes
+fn blank_run(col: VisIdx, is_blank: impl Fn(VisIdx) -> bool) -> (VisIdx, VisIdx) {
+    let mut first = col;
+    while first.0 > 0 && is_blank(first - VisOffset(1)) {
+        first = first - VisOffset(1);
+    }
+    let mut last = col;
+    while is_blank(last + VisOffset(1)) {
+        last = last + VisOffset(1);
     }
+    (first, last)
+}
+
+// The first filled column stepping out from a blank edge, which might spill back over it.
+// Blankness is None for columns not in the table.
+fn spill_source(
+    edge: VisIdx,
+    step: VisOffset,
+    is_blank: impl Fn(VisIdx) -> Option<bool>,
+) -> Option<VisIdx> {
+    let mut col = edge;
+    while is_blank(col)? {
+        if step.0 < 0 && col.0 == 0 {
+            return None;
+        }
+        col = col + step;
+    }
+    if_opt!(col != edge, col)
 }
 
 impl<TableData, CellsDel> BindableAccess for Cells<TableData, CellsDel>
@@ -589,3 +2217,93 @@ where
 {
     bindable_self_body!();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chart_cells::ProgressBarCell;
+    use crate::columns::{column, CellRenderExt, ProvidedColumns, TextCell};
+    use crate::formatting::FormatRule;
+    use crate::test_fixtures::{cell, word_rows, Row};
+    use druid::im::Vector;
+    use druid::Color;
+
+    #[test]
+    fn cell_styles_win_over_the_row_style() {
+        let columns = ProvidedColumns::<Vector<Row>, _>::new(vec![
+            column("Word", TextCell::new().lens(lens!(Row, 0))),
+            column("Done", ProgressBarCell::new().lens(lens!(Row, 1))).format(FormatRule::when(
+                |row: &Row| row.1 >= 1.,
+                CellStyle::new().background(Color::RED).bold(),
+            )),
+        ])
+        .row_style(|row: &Row| {
+            if_opt!(
+                row.0.is_empty(),
+                CellStyle::new()
+                    .background(Color::grey8(200))
+                    .text_color(Color::BLACK)
+            )
+        });
+        let rows = word_rows(&[("", 1.), ("", 0.5), ("done", 1.)]);
+        let style = |row: usize, col: usize| {
+            let sc = cell(row, col);
+            let row = &rows[row];
+            cell_style_in_row::<Vector<Row>, _>(
+                &columns,
+                &CellCtx::Cell(&sc),
+                row,
+                CellsDelegate::<Vector<Row>>::row_style(&columns, row),
+            )
+        };
+
+        // The rule's background covers the row's, which still gives the text colour
+        let done = style(0, 1).unwrap();
+        assert_eq!(done.background, Some(Color::RED));
+        assert_eq!(done.text_color, Some(Color::BLACK));
+        assert!(done.bold);
+        // Cells without a style of their own take the row's
+        let word = style(0, 0).unwrap();
+        assert_eq!(word.background, Some(Color::grey8(200)));
+        assert!(!word.bold);
+        assert_eq!(style(1, 1).unwrap().background, Some(Color::grey8(200)));
+        // Rows without a style only show the rules
+        assert_eq!(style(2, 1).unwrap().background, Some(Color::RED));
+        assert!(style(2, 0).is_none());
+    }
+
+    fn blanks(cols: &[bool]) -> impl Fn(VisIdx) -> bool + '_ {
+        move |col: VisIdx| cols.get(col.0).copied().unwrap_or(false)
+    }
+
+    #[test]
+    fn blank_run_stops_at_filled_cells_and_edges() {
+        let row = [true, false, true, true, false, true, true];
+        assert_eq!(blank_run(VisIdx(4), blanks(&row)), (VisIdx(2), VisIdx(6)));
+        assert_eq!(blank_run(VisIdx(1), blanks(&row)), (VisIdx(0), VisIdx(3)));
+        let row = [false, false];
+        assert_eq!(blank_run(VisIdx(0), blanks(&row)), (VisIdx(0), VisIdx(0)));
+    }
+
+    #[test]
+    fn spill_source_finds_cells_out_of_view() {
+        let row = [false, true, true, false];
+        let is_blank = |col: VisIdx| row.get(col.0).copied();
+        // Painting from column 2 onwards, column 0 may spill over 1 and 2
+        assert_eq!(
+            spill_source(VisIdx(2), VisOffset(-1), is_blank),
+            Some(VisIdx(0))
+        );
+        assert_eq!(
+            spill_source(VisIdx(1), VisOffset(1), is_blank),
+            Some(VisIdx(3))
+        );
+        // A filled edge covers itself
+        assert_eq!(spill_source(VisIdx(3), VisOffset(1), is_blank), None);
+        // Blank to the end of the table either way
+        let row = [true, true];
+        let is_blank = |col: VisIdx| row.get(col.0).copied();
+        assert_eq!(spill_source(VisIdx(1), VisOffset(-1), is_blank), None);
+        assert_eq!(spill_source(VisIdx(0), VisOffset(1), is_blank), None);
+    }
+}
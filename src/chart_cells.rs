@@ -0,0 +1,445 @@
+use crate::columns::{CellCtx, CellRender, CommitResult, DataCompare, EditorFactory};
+use crate::editors::ParseEditor;
+use crate::vis::{AxisName, F64Range, LinearScale};
+use druid::im::Vector;
+use druid::kurbo::BezPath;
+use druid::{theme, Color, Env, KeyOrValue, PaintCtx, Point, Rect, RenderContext, Widget};
+use itertools::Itertools;
+use std::cmp::Ordering;
+
+// TODO The size should be on the CellCtx, should not be using region
+fn cell_bounds(ctx: &PaintCtx) -> Rect {
+    ctx.region().bounding_box().with_origin(Point::ORIGIN)
+}
+
+fn compare_f64(a: &f64, b: &f64) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+fn parse_f64(text: &str) -> Result<f64, String> {
+    text.trim().parse::<f64>().map_err(|e| e.to_string())
+}
+
+fn f64_editor() -> Option<Box<dyn Widget<f64>>> {
+    let editor = ParseEditor::new(|v: &f64| v.to_string(), parse_f64);
+    Some(Box::new(editor))
+}
+
+fn scale_for(
+    name: &'static str,
+    pixels: F64Range,
+    domain: F64Range,
+    zero: bool,
+) -> LinearScale<f64> {
+    LinearScale::new(
+        AxisName(name),
+        pixels,
+        &mut [domain.0, domain.1].iter().copied(),
+        false,
+        None,
+        zero,
+    )
+}
+
+// Horizontal bar scaled to the range of values observed in its column, like spreadsheet data bars
+#[derive(Clone)]
+pub struct DataBarCell {
+    color: KeyOrValue<Color>,
+    negative_color: KeyOrValue<Color>,
+    include_zero: bool,
+    fixed_domain: Option<F64Range>,
+    observed_domain: Option<F64Range>,
+}
+
+impl DataBarCell {
+    pub fn new() -> Self {
+        DataBarCell {
+            color: Color::rgb8(0x63, 0x8E, 0xC6).into(),
+            negative_color: Color::rgb8(0xD0, 0x4A, 0x4A).into(),
+            include_zero: true,
+            fixed_domain: None,
+            observed_domain: None,
+        }
+    }
+
+    pub fn color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn negative_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.negative_color = color.into();
+        self
+    }
+
+    // Whether bars grow from zero (the default) or from the smallest value in the column
+    pub fn include_zero(mut self, include_zero: bool) -> Self {
+        self.include_zero = include_zero;
+        self
+    }
+
+    // Use a fixed domain instead of the column's min/max
+    pub fn domain(mut self, min: f64, max: f64) -> Self {
+        self.fixed_domain = Some(F64Range(min, max));
+        self
+    }
+
+    fn current_domain(&self) -> Option<F64Range> {
+        self.fixed_domain.or(self.observed_domain)
+    }
+
+    // Where the bar for a value starts and ends across a cell of the width
+    fn bar_extent(&self, width: f64, data: f64) -> Option<(f64, f64)> {
+        let domain = match self.current_domain() {
+            Some(domain) if data.is_finite() => domain,
+            _ => return None,
+        };
+        let scale = scale_for("data_bar", F64Range(0., width), domain, self.include_zero);
+        let clamp = |x: f64| x.max(0.).min(width);
+        let base_x = if self.include_zero {
+            clamp(scale.range_val_raw(0.))
+        } else {
+            0.
+        };
+        let value_x = clamp(scale.range_val(&data));
+        Some((base_x.min(value_x), base_x.max(value_x)))
+    }
+}
+
+impl Default for DataBarCell {
+    fn default() -> Self {
+        DataBarCell::new()
+    }
+}
+
+impl CellRender<f64> for DataBarCell {
+    fn init(&mut self, _ctx: &mut PaintCtx, _env: &Env) {}
+
+    fn paint(&self, ctx: &mut PaintCtx, _cell: &CellCtx, data: &f64, env: &Env) {
+        let bounds = cell_bounds(ctx);
+        let (x0, x1) = match self.bar_extent(bounds.width(), *data) {
+            Some(extent) => extent,
+            None => return,
+        };
+        let color = if *data < 0. {
+            &self.negative_color
+        } else {
+            &self.color
+        };
+        let bar = Rect::new(x0, bounds.y0 + 1., x1, bounds.y1 - 1.);
+        ctx.fill(bar, &color.resolve(env));
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &f64) -> Option<String> {
+        Some(data.to_string())
+    }
+
+    fn observes_rows(&self) -> bool {
+        self.fixed_domain.is_none()
+    }
+
+    fn start_observe(&mut self) {
+        self.observed_domain = None;
+    }
+
+    fn observe(&mut self, data: &f64) {
+        if data.is_finite() {
+            self.observed_domain = Some(match self.observed_domain {
+                Some(F64Range(min, max)) => F64Range(min.min(*data), max.max(*data)),
+                None => F64Range(*data, *data),
+            })
+        }
+    }
+}
+
+impl DataCompare<f64> for DataBarCell {
+    fn compare(&self, a: &f64, b: &f64) -> Ordering {
+        compare_f64(a, b)
+    }
+}
+
+impl EditorFactory<f64> for DataBarCell {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<f64>>> {
+        f64_editor()
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut f64, text: &str) -> CommitResult {
+        *data = parse_f64(text)?;
+        Ok(())
+    }
+}
+
+// Progress towards completion, for values from 0 to 1
+#[derive(Clone)]
+pub struct ProgressBarCell {
+    color: KeyOrValue<Color>,
+    track_color: KeyOrValue<Color>,
+}
+
+impl ProgressBarCell {
+    pub fn new() -> Self {
+        ProgressBarCell {
+            color: Color::rgb8(0x4D, 0xB8, 0x58).into(),
+            track_color: theme::BACKGROUND_LIGHT.into(),
+        }
+    }
+
+    pub fn color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn track_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.track_color = color.into();
+        self
+    }
+}
+
+impl Default for ProgressBarCell {
+    fn default() -> Self {
+        ProgressBarCell::new()
+    }
+}
+
+impl CellRender<f64> for ProgressBarCell {
+    fn init(&mut self, _ctx: &mut PaintCtx, _env: &Env) {}
+
+    fn paint(&self, ctx: &mut PaintCtx, _cell: &CellCtx, data: &f64, env: &Env) {
+        let bounds = cell_bounds(ctx);
+        let track_height = (bounds.height() / 2.).min(12.);
+        let track = Rect::from_center_size(bounds.center(), (bounds.width(), track_height));
+        ctx.fill(track, &self.track_color.resolve(env));
+
+        if data.is_finite() {
+            let scale = scale_for(
+                "progress",
+                F64Range(track.x0, track.x1),
+                F64Range(0., 1.),
+                false,
+            );
+            let fraction = data.max(0.).min(1.);
+            let filled = track.with_size((scale.range_val(&fraction) - track.x0, track_height));
+            ctx.fill(filled, &self.color.resolve(env));
+        }
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &f64) -> Option<String> {
+        Some(data.to_string())
+    }
+}
+
+impl DataCompare<f64> for ProgressBarCell {
+    fn compare(&self, a: &f64, b: &f64) -> Ordering {
+        compare_f64(a, b)
+    }
+}
+
+impl EditorFactory<f64> for ProgressBarCell {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<f64>>> {
+        f64_editor()
+    }
+
+    fn validate(&self, _ctx: &CellCtx, data: &f64) -> CommitResult {
+        if (0. ..=1.).contains(data) {
+            Ok(())
+        } else {
+            Err(format!("{} is not between 0 and 1", data))
+        }
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut f64, text: &str) -> CommitResult {
+        *data = parse_f64(text)?;
+        Ok(())
+    }
+}
+
+// A small line chart of a series, scaled to that row's own values
+#[derive(Clone)]
+pub struct SparklineCell {
+    color: KeyOrValue<Color>,
+    line_width: f64,
+}
+
+impl SparklineCell {
+    pub fn new() -> Self {
+        SparklineCell {
+            color: Color::rgb8(0x4D, 0x58, 0xD8).into(),
+            line_width: 1.5,
+        }
+    }
+
+    pub fn color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn line_width(mut self, line_width: f64) -> Self {
+        self.line_width = line_width;
+        self
+    }
+}
+
+impl Default for SparklineCell {
+    fn default() -> Self {
+        SparklineCell::new()
+    }
+}
+
+impl CellRender<Vector<f64>> for SparklineCell {
+    fn init(&mut self, _ctx: &mut PaintCtx, _env: &Env) {}
+
+    fn paint(&self, ctx: &mut PaintCtx, _cell: &CellCtx, data: &Vector<f64>, env: &Env) {
+        let finite = data.iter().copied().filter(|v| v.is_finite());
+        let (min, max) = match finite.minmax().into_option() {
+            Some(min_max) if data.len() > 1 => min_max,
+            _ => return,
+        };
+
+        let bounds = cell_bounds(ctx).inset(-self.line_width);
+        let x_scale = scale_for(
+            "sparkline_x",
+            F64Range(bounds.x0, bounds.x1),
+            F64Range(0., (data.len() - 1) as f64),
+            false,
+        );
+        // Pixels grow downwards, so the range is flipped
+        let y_scale = scale_for(
+            "sparkline_y",
+            F64Range(bounds.y1, bounds.y0),
+            F64Range(min, max),
+            false,
+        );
+
+        let mut path = BezPath::new();
+        let mut started = false;
+        for (idx, value) in data.iter().enumerate().filter(|(_, v)| v.is_finite()) {
+            let point = Point::new(x_scale.range_val(&(idx as f64)), y_scale.range_val(value));
+            if started {
+                path.line_to(point);
+            } else {
+                path.move_to(point);
+                started = true;
+            }
+        }
+        ctx.stroke(path, &self.color.resolve(env), self.line_width);
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &Vector<f64>) -> Option<String> {
+        Some(data.iter().join(" "))
+    }
+}
+
+// Series are ordered by their most recent value
+impl DataCompare<Vector<f64>> for SparklineCell {
+    fn compare(&self, a: &Vector<f64>, b: &Vector<f64>) -> Ordering {
+        match (a.last(), b.last()) {
+            (Some(a), Some(b)) => compare_f64(a, b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+impl EditorFactory<Vector<f64>> for SparklineCell {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<Vector<f64>>>> {
+        None
+    }
+
+    // The values separated by spaces, as copied
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut Vector<f64>, text: &str) -> CommitResult {
+        *data = text
+            .split_whitespace()
+            .map(parse_f64)
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use druid::im::vector;
+
+    fn observed(mut cell: DataBarCell, values: &[f64]) -> DataBarCell {
+        cell.start_observe();
+        for v in values {
+            cell.observe(v);
+        }
+        cell.end_observe();
+        cell
+    }
+
+    fn domain(cell: &DataBarCell) -> Option<(f64, f64)> {
+        cell.current_domain().map(|F64Range(min, max)| (min, max))
+    }
+
+    #[test]
+    fn data_bars_observe_the_column_range() {
+        let cell = observed(DataBarCell::new(), &[2., f64::NAN, -1., 4.]);
+        assert!(cell.observes_rows());
+        assert_eq!(domain(&cell), Some((-1., 4.)));
+
+        // Observing again starts afresh
+        let cell = observed(cell, &[3.]);
+        assert_eq!(domain(&cell), Some((3., 3.)));
+
+        let cell = observed(DataBarCell::new(), &[]);
+        assert_eq!(domain(&cell), None);
+        assert_eq!(cell.bar_extent(100., 1.), None);
+
+        let fixed = DataBarCell::new().domain(0., 10.);
+        assert!(!fixed.observes_rows());
+        assert_eq!(domain(&fixed), Some((0., 10.)));
+    }
+
+    #[test]
+    fn data_bars_grow_from_zero_or_the_smallest_value() {
+        let from_zero = observed(DataBarCell::new(), &[2., 4.]);
+        assert_eq!(from_zero.bar_extent(100., 2.), Some((0., 50.)));
+        assert_eq!(from_zero.bar_extent(100., 4.), Some((0., 100.)));
+        assert_eq!(from_zero.bar_extent(100., f64::NAN), None);
+
+        let from_min = observed(DataBarCell::new().include_zero(false), &[2., 4.]);
+        assert_eq!(from_min.bar_extent(100., 3.), Some((0., 50.)));
+
+        // Negative values run left from zero
+        let signed = observed(DataBarCell::new(), &[-1., 4.]);
+        assert_eq!(signed.bar_extent(100., -1.), Some((0., 20.)));
+        assert_eq!(signed.bar_extent(100., 4.), Some((20., 100.)));
+    }
+
+    #[test]
+    fn data_bars_for_equal_values() {
+        let from_zero = observed(DataBarCell::new(), &[5., 5.]);
+        assert_eq!(from_zero.bar_extent(100., 5.), Some((0., 100.)));
+        // Without zero there is no range to scale to, so bars stay empty
+        let from_min = observed(DataBarCell::new().include_zero(false), &[5., 5.]);
+        assert_eq!(from_min.bar_extent(100., 5.), Some((0., 0.)));
+    }
+
+    #[test]
+    fn progress_is_valid_from_0_to_1() {
+        let cell = ProgressBarCell::new();
+        assert!(cell.validate(&CellCtx::Absent, &0.).is_ok());
+        assert!(cell.validate(&CellCtx::Absent, &1.).is_ok());
+        assert_eq!(
+            cell.validate(&CellCtx::Absent, &1.5),
+            Err("1.5 is not between 0 and 1".to_string())
+        );
+        assert!(cell.validate(&CellCtx::Absent, &-0.1).is_err());
+        assert!(cell.validate(&CellCtx::Absent, &f64::NAN).is_err());
+    }
+
+    #[test]
+    fn sparklines_sort_on_their_last_value() {
+        let cell = SparklineCell::new();
+        let rising = vector![1., 2., 5.];
+        let falling = vector![9., 3.];
+        let empty = Vector::new();
+        assert_eq!(cell.compare(&rising, &falling), Ordering::Greater);
+        assert_eq!(cell.compare(&falling, &rising), Ordering::Less);
+        assert_eq!(cell.compare(&rising, &vector![5.]), Ordering::Equal);
+        // Empty series come first
+        assert_eq!(cell.compare(&empty, &falling), Ordering::Less);
+        assert_eq!(cell.compare(&empty, &empty), Ordering::Equal);
+    }
+}
@@ -0,0 +1,554 @@
+use crate::cells::{EditStart, COMMIT_EDIT, START_EDIT};
+use crate::columns::{
+    CellCtx, CellRender, CommitResult, DataCompare, EditorFactory, TextCell, TextOptions,
+};
+use druid::piet::{FontFamily, PietText, Text, TextLayout, TextLayoutBuilder};
+use druid::widget::prelude::*;
+use druid::{theme, ArcStr, Color, Command, Data, KbKey, Point, Rect, Target, Vec2};
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// Values from a fixed set, eg a status or a currency, shown by their label.
+// Edited by picking from a dropdown list, and sorted in the order the choices were given.
+#[derive(Clone)]
+pub struct ChoiceCell<T> {
+    choices: Rc<Vec<(T, ArcStr)>>,
+    text: TextCell,
+}
+
+impl<T: Data> ChoiceCell<T> {
+    pub fn new<L: Into<ArcStr>>(choices: impl IntoIterator<Item = (T, L)>) -> Self {
+        ChoiceCell {
+            choices: Rc::new(
+                choices
+                    .into_iter()
+                    .map(|(value, label)| (value, label.into()))
+                    .collect(),
+            ),
+            text: TextCell::new(),
+        }
+    }
+
+    pub fn text_cell(mut self, text: TextCell) -> Self {
+        self.text = text;
+        self
+    }
+
+    fn position(&self, value: &T) -> Option<usize> {
+        self.choices
+            .iter()
+            .position(|(choice, _)| choice.same(value))
+    }
+
+    // Matches a label, ignoring case
+    fn position_of_label(&self, label: &str) -> Option<usize> {
+        let label = label.trim().to_lowercase();
+        self.choices
+            .iter()
+            .position(|(_, choice)| choice.to_lowercase() == label)
+    }
+
+    // Values that aren't one of the choices have no label
+    pub fn label(&self, value: &T) -> String {
+        self.position(value)
+            .map(|pos| self.choices[pos].1.to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl<T: Data> CellRender<T> for ChoiceCell<T> {
+    fn init(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.text.init(ctx, env)
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
+        self.text.paint(ctx, cell, &self.label(data), env)
+    }
+
+    // Decimal alignment looks at the labels in the column
+    fn observes_rows(&self) -> bool {
+        self.text.observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.text.start_observe()
+    }
+
+    fn observe(&mut self, data: &T) {
+        let label = self.label(data);
+        self.text.observe(&label)
+    }
+
+    fn end_observe(&mut self) {
+        self.text.end_observe()
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.text.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        self.text.spills(cell, &self.label(data))
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        self.text.is_blank(cell, &self.label(data))
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        self.text
+            .cell_tooltip(text, size, cell, &self.label(data), env)
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &T) -> Option<String> {
+        Some(self.label(data))
+    }
+}
+
+// Values that aren't one of the choices sort last
+impl<T: Data> DataCompare<T> for ChoiceCell<T> {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        let pos = |v| self.position(v).unwrap_or(usize::MAX);
+        pos(a).cmp(&pos(b))
+    }
+}
+
+impl<T: Data> EditorFactory<T> for ChoiceCell<T> {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<T>>> {
+        Some(Box::new(ChoiceEditor::new(self.choices.clone())))
+    }
+
+    fn validate(&self, _ctx: &CellCtx, data: &T) -> CommitResult {
+        match self.position(data) {
+            Some(_) => Ok(()),
+            None => Err("Not one of the choices".into()),
+        }
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut T, text: &str) -> CommitResult {
+        let pos = self
+            .position_of_label(text)
+            .ok_or_else(|| format!("'{}' is not one of the choices", text.trim()))?;
+        *data = self.choices[pos].0.clone();
+        Ok(())
+    }
+
+    // Choices taken at an even step through the list continue, wrapping around like weekdays
+    fn fill_series(&self, _ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        if source.len() < 2 {
+            return None;
+        }
+        let len = self.choices.len() as isize;
+        let positions = source
+            .iter()
+            .map(|label| self.position_of_label(label).map(|pos| pos as isize))
+            .collect::<Option<Vec<_>>>()?;
+        let step = (positions[1] - positions[0]).rem_euclid(len);
+        let even = positions
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).rem_euclid(len) == step);
+        if !even {
+            return None;
+        }
+        let last = positions[positions.len() - 1];
+        Some(
+            (1..=count as isize)
+                .map(|i| {
+                    let pos = (last + step * i).rem_euclid(len) as usize;
+                    self.choices[pos].1.to_string()
+                })
+                .collect(),
+        )
+    }
+}
+
+const MAX_SHOWN: usize = 8;
+const ITEM_PADDING: f64 = 3.;
+const MIN_LIST_WIDTH: f64 = 120.;
+
+// Shows the choices in a list below the cell. Typing filters the list, the arrow keys move
+// through it, and the highlighted choice is the edited value. Clicking a choice commits it.
+struct ChoiceEditor<T> {
+    choices: Rc<Vec<(T, ArcStr)>>,
+    filter: String,
+    // Indices of the choices matching the filter
+    matches: Vec<usize>,
+    // Position in matches
+    highlighted: Option<usize>,
+    hovered: Option<usize>,
+    first_shown: usize,
+    item_height: f64,
+}
+
+impl<T: Data> ChoiceEditor<T> {
+    fn new(choices: Rc<Vec<(T, ArcStr)>>) -> Self {
+        ChoiceEditor {
+            matches: (0..choices.len()).collect(),
+            choices,
+            filter: String::new(),
+            highlighted: None,
+            hovered: None,
+            first_shown: 0,
+            item_height: 0.,
+        }
+    }
+
+    fn refilter(&mut self, data: &mut T) {
+        let filter = self.filter.to_lowercase();
+        let choices = &self.choices;
+        self.matches = (0..choices.len())
+            .filter(|idx| choices[*idx].1.to_lowercase().contains(&filter))
+            .collect();
+        self.first_shown = 0;
+        self.hovered = None;
+        self.set_highlight(if_opt!(!self.matches.is_empty(), 0), data);
+    }
+
+    fn move_highlight(&mut self, step: isize, data: &mut T) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let last = self.matches.len() as isize - 1;
+        let pos = self
+            .highlighted
+            .map_or(0, |pos| (pos as isize + step).max(0).min(last));
+        self.set_highlight(Some(pos as usize), data);
+    }
+
+    fn set_highlight(&mut self, pos: Option<usize>, data: &mut T) {
+        self.highlighted = pos;
+        if let Some(pos) = pos {
+            *data = self.choices[self.matches[pos]].0.clone();
+            if pos < self.first_shown {
+                self.first_shown = pos;
+            } else if pos >= self.first_shown + MAX_SHOWN {
+                self.first_shown = pos + 1 - MAX_SHOWN;
+            }
+        }
+    }
+
+    fn list_rect(&self, size: Size) -> Rect {
+        let shown = self.matches.len().min(MAX_SHOWN).max(1);
+        Rect::new(
+            0.,
+            size.height,
+            size.width.max(MIN_LIST_WIDTH),
+            size.height + shown as f64 * self.item_height,
+        )
+    }
+
+    // Position in matches of the item under the mouse
+    fn item_at(&self, size: Size, pos: Point) -> Option<usize> {
+        let list = self.list_rect(size);
+        if !list.contains(pos) || self.item_height <= 0. {
+            return None;
+        }
+        let item = self.first_shown + ((pos.y - list.y0) / self.item_height) as usize;
+        if_opt!(item < self.matches.len(), item)
+    }
+}
+
+impl<T: Data> Widget<T> for ChoiceEditor<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, _env: &Env) {
+        // Being active means clicks on the list reach us, even though it is outside the cell
+        if !ctx.is_active() {
+            ctx.set_active(true);
+        }
+        match event {
+            Event::MouseMove(me) => {
+                let hovered = self.item_at(ctx.size(), me.pos);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseDown(me) => {
+                if let Some(pos) = self.item_at(ctx.size(), me.pos) {
+                    self.set_highlight(Some(pos), data);
+                    ctx.submit_command(Command::new(COMMIT_EDIT, (), Target::Auto));
+                    ctx.set_handled();
+                } else if ctx.size().to_rect().contains(me.pos) {
+                    ctx.set_handled();
+                } else {
+                    // Let the table commit and handle the click
+                    ctx.set_active(false);
+                }
+            }
+            Event::Wheel(we) if self.item_at(ctx.size(), we.pos).is_some() => {
+                let max_first = self.matches.len().saturating_sub(MAX_SHOWN);
+                let first = if we.wheel_delta.y > 0. {
+                    self.first_shown + 1
+                } else {
+                    self.first_shown.saturating_sub(1)
+                };
+                self.first_shown = first.min(max_first);
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) => {
+                if let Some(EditStart::Typed(typed)) = cmd.get(START_EDIT) {
+                    self.filter = typed.clone();
+                    self.refilter(data);
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
+            Event::KeyDown(ke) => {
+                match &ke.key {
+                    KbKey::ArrowDown => self.move_highlight(1, data),
+                    KbKey::ArrowUp => self.move_highlight(-1, data),
+                    KbKey::PageDown => self.move_highlight(MAX_SHOWN as isize, data),
+                    KbKey::PageUp => self.move_highlight(-(MAX_SHOWN as isize), data),
+                    KbKey::Backspace => {
+                        self.filter.pop();
+                        self.refilter(data);
+                    }
+                    KbKey::Character(s) if !(ke.mods.ctrl() || ke.mods.meta()) => {
+                        self.filter.push_str(s);
+                        self.refilter(data);
+                    }
+                    _ => return,
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            let current = self.choices.iter().position(|(c, _)| c.same(data));
+            self.highlighted = current;
+            if let Some(pos) = current {
+                self.first_shown = pos.saturating_sub(MAX_SHOWN - 1);
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        if !old_data.same(data) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        self.item_height = (env.get(theme::TEXT_SIZE_NORMAL) * 1.2 + ITEM_PADDING * 2.).round();
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let size = ctx.size();
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let text_color = env.get(theme::LABEL_COLOR);
+        ctx.fill(size.to_rect(), &env.get(theme::BACKGROUND_LIGHT));
+
+        // The filter being typed, or the current value
+        let (shown_text, color) = if self.filter.is_empty() {
+            let label = self.choices.iter().find(|(c, _)| c.same(data));
+            let label = label.map(|(_, label)| label.to_string());
+            (label.unwrap_or_default(), text_color.clone())
+        } else {
+            (self.filter.clone(), env.get(theme::PRIMARY_LIGHT))
+        };
+        let text = ctx.text();
+        let layouts = (
+            text.new_text_layout(shown_text)
+                .font(FontFamily::SYSTEM_UI, font_size)
+                .text_color(color)
+                .build(),
+            text.new_text_layout("▾")
+                .font(FontFamily::SYSTEM_UI, font_size)
+                .text_color(text_color.clone())
+                .build(),
+        );
+        if let (Ok(value), Ok(arrow)) = layouts {
+            let y = ((size.height - value.size().height) / 2.).max(0.);
+            ctx.draw_text(&value, (ITEM_PADDING, y));
+            ctx.draw_text(&arrow, (size.width - arrow.size().width - ITEM_PADDING, y));
+        }
+
+        // The list is painted above the rest of the table, as it goes outside the cell
+        let list = self.list_rect(size);
+        let item_height = self.item_height;
+        let labels: Vec<ArcStr> = self
+            .matches
+            .iter()
+            .skip(self.first_shown)
+            .take(MAX_SHOWN)
+            .map(|idx| self.choices[*idx].1.clone())
+            .collect();
+        let highlighted = self
+            .highlighted
+            .and_then(|h| h.checked_sub(self.first_shown));
+        let hovered = self.hovered.and_then(|h| h.checked_sub(self.first_shown));
+        let background = env.get(theme::BACKGROUND_LIGHT);
+        let border = env.get(theme::BORDER_DARK);
+        let highlight = env.get(theme::PRIMARY_DARK);
+        let placeholder = env.get(theme::PLACEHOLDER_COLOR);
+
+        ctx.paint_with_z_index(1, move |ctx| {
+            ctx.fill(list, &background);
+            let item_text = |ctx: &mut PaintCtx, label: &str, color: Color| {
+                ctx.text()
+                    .new_text_layout(label.to_string())
+                    .font(FontFamily::SYSTEM_UI, font_size)
+                    .text_color(color)
+                    .build()
+                    .ok()
+            };
+            if labels.is_empty() {
+                if let Some(layout) = item_text(ctx, "No matches", placeholder) {
+                    ctx.draw_text(
+                        &layout,
+                        list.origin() + Vec2::new(ITEM_PADDING, ITEM_PADDING),
+                    );
+                }
+            }
+            for (i, label) in labels.iter().enumerate() {
+                let item = Rect::from_origin_size(
+                    (list.x0, list.y0 + i as f64 * item_height),
+                    (list.width(), item_height),
+                );
+                if highlighted == Some(i) {
+                    ctx.fill(item, &highlight);
+                } else if hovered == Some(i) {
+                    ctx.fill(item, &highlight.clone().with_alpha(0.3));
+                }
+                if let Some(layout) = item_text(ctx, label, text_color.clone()) {
+                    ctx.draw_text(
+                        &layout,
+                        item.origin() + Vec2::new(ITEM_PADDING, ITEM_PADDING),
+                    );
+                }
+            }
+            ctx.stroke(list, &border, 1.);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::HorizontalAlignment;
+
+    fn weekdays() -> ChoiceCell<String> {
+        ChoiceCell::new(
+            vec![
+                ("mon", "Monday"),
+                ("tue", "Tuesday"),
+                ("wed", "Wednesday"),
+                ("thu", "Thursday"),
+                ("fri", "Friday"),
+            ]
+            .into_iter()
+            .map(|(value, label)| (value.to_string(), label)),
+        )
+    }
+
+    fn day(value: &str) -> String {
+        value.to_string()
+    }
+
+    fn labels(days: &[&str]) -> Vec<String> {
+        days.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn choices_sort_in_the_given_order() {
+        let cell = weekdays();
+        assert_eq!(cell.compare(&day("mon"), &day("wed")), Ordering::Less);
+        assert_eq!(cell.compare(&day("fri"), &day("tue")), Ordering::Greater);
+        // Unknown values go last
+        assert_eq!(cell.compare(&day("sun"), &day("fri")), Ordering::Greater);
+        assert_eq!(cell.compare(&day("sun"), &day("sat")), Ordering::Equal);
+    }
+
+    #[test]
+    fn only_choices_are_valid() {
+        let cell = weekdays();
+        assert_eq!(cell.validate(&CellCtx::Absent, &day("tue")), Ok(()));
+        assert_eq!(
+            cell.validate(&CellCtx::Absent, &day("sun")),
+            Err("Not one of the choices".to_string())
+        );
+    }
+
+    #[test]
+    fn pastes_by_label_ignoring_case() {
+        let cell = weekdays();
+        let mut data = day("mon");
+        assert_eq!(
+            cell.paste_text(&CellCtx::Absent, &mut data, " wednesday "),
+            Ok(())
+        );
+        assert_eq!(data, "wed");
+        assert_eq!(
+            cell.paste_text(&CellCtx::Absent, &mut data, "Sunday"),
+            Err("'Sunday' is not one of the choices".to_string())
+        );
+        assert_eq!(data, "wed");
+    }
+
+    #[test]
+    fn fill_series_wraps_around() {
+        let cell = weekdays();
+        let fill =
+            |source: &[&str], count| cell.fill_series(&CellCtx::Absent, &labels(source), count);
+        assert_eq!(
+            fill(&["Thursday", "Friday"], 3),
+            Some(labels(&["Monday", "Tuesday", "Wednesday"]))
+        );
+        assert_eq!(
+            fill(&["Monday", "Wednesday"], 2),
+            Some(labels(&["Friday", "Tuesday"]))
+        );
+        assert_eq!(fill(&["Monday", "Tuesday", "Thursday"], 2), None);
+        assert_eq!(fill(&["Monday", "Sunday"], 2), None);
+        assert_eq!(fill(&["Monday"], 2), None);
+    }
+
+    #[test]
+    fn labels_are_laid_out_as_text() {
+        let mut cell = weekdays();
+        assert!(cell.is_blank(&CellCtx::Absent, &day("sun")));
+        assert!(!cell.is_blank(&CellCtx::Absent, &day("mon")));
+        assert!(!cell.observes_rows());
+        cell.update_text_options(&|options| options.align = HorizontalAlignment::Decimal);
+        assert!(cell.observes_rows());
+    }
+
+    #[test]
+    fn typing_filters_the_editor_list() {
+        let mut editor = ChoiceEditor::new(weekdays().choices);
+        let mut data = day("mon");
+
+        editor.filter = "T".into();
+        editor.refilter(&mut data);
+        assert_eq!(editor.matches, vec![1, 3]);
+        assert_eq!(editor.highlighted, Some(0));
+        assert_eq!(data, "tue");
+
+        editor.move_highlight(1, &mut data);
+        assert_eq!(data, "thu");
+        // Stops at the ends of the matches
+        editor.move_highlight(5, &mut data);
+        assert_eq!(data, "thu");
+        editor.move_highlight(-1, &mut data);
+        assert_eq!(data, "tue");
+
+        // Nothing matching keeps the value
+        editor.filter = "x".into();
+        editor.refilter(&mut data);
+        assert!(editor.matches.is_empty());
+        assert_eq!(editor.highlighted, None);
+        assert_eq!(data, "tue");
+    }
+}
@@ -0,0 +1,291 @@
+use crate::axis_measure::LogIdx;
+use crate::columns::{CellCtx, CellRender, CommitResult, DataCompare, EditorFactory};
+use crate::selection::SingleCell;
+use druid::kurbo::Line;
+use druid::piet::{FontFamily, PietText, PietTextLayout, Text, TextLayout, TextLayoutBuilder};
+use druid::{
+    theme, ArcStr, Color, Command, Data, Env, Event, EventCtx, KeyOrValue, MouseButton, PaintCtx,
+    Point, RenderContext, Selector, Size, Target, Widget,
+};
+use std::cmp::Ordering;
+
+// What a clickable cell sends. The payload is the logical row, optionally with the cell's data.
+#[derive(Clone)]
+enum ClickCommand<T> {
+    Row(Selector<LogIdx>),
+    RowData(Selector<(LogIdx, T)>),
+}
+
+#[derive(Clone)]
+struct Clicks<T> {
+    command: ClickCommand<T>,
+    target: Target,
+}
+
+impl<T: Data> Clicks<T> {
+    fn new(command: ClickCommand<T>) -> Self {
+        Clicks {
+            command,
+            target: Target::Auto,
+        }
+    }
+
+    fn command(&self, row: LogIdx, data: &T) -> Command {
+        match &self.command {
+            ClickCommand::Row(sel) => Command::new(*sel, row, self.target),
+            ClickCommand::RowData(sel) => Command::new(*sel, (row, data.clone()), self.target),
+        }
+    }
+
+    // Claim left presses on what can be clicked so they don't select, and send the command when
+    // released there
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        hits: impl Fn(Point) -> bool,
+    ) -> bool {
+        let row = match cell {
+            CellCtx::Cell(SingleCell { log, .. }) => log.row,
+            _ => return false,
+        };
+        match event {
+            Event::MouseDown(me) => me.button == MouseButton::Left && hits(me.pos),
+            Event::MouseUp(me) if hits(me.pos) => {
+                ctx.submit_command(self.command(row, data));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Text is laid out from the cell origin, so it covers a rect of its size there
+fn hits_text(text_size: Size, pos: Point) -> bool {
+    text_size.to_rect().contains(pos)
+}
+
+fn label_layout(
+    text: &mut PietText,
+    label: &str,
+    color: Color,
+    env: &Env,
+) -> Option<PietTextLayout> {
+    text.new_text_layout(label.to_string())
+        .font(FontFamily::SYSTEM_UI, env.get(theme::TEXT_SIZE_NORMAL))
+        .text_color(color)
+        .build()
+        .ok()
+}
+
+// A push button in every row, eg "Open" or "Delete"
+#[derive(Clone)]
+pub struct ButtonCell<T> {
+    label: ArcStr,
+    clicks: Clicks<T>,
+    color: KeyOrValue<Color>,
+    text_color: KeyOrValue<Color>,
+}
+
+impl<T: Data> ButtonCell<T> {
+    // Sends the logical row index of the clicked row
+    pub fn new(label: impl Into<ArcStr>, command: Selector<LogIdx>) -> Self {
+        Self::with_command(label.into(), ClickCommand::Row(command))
+    }
+
+    // Sends the logical row index along with the cell's data
+    pub fn with_data(label: impl Into<ArcStr>, command: Selector<(LogIdx, T)>) -> Self {
+        Self::with_command(label.into(), ClickCommand::RowData(command))
+    }
+
+    fn with_command(label: ArcStr, command: ClickCommand<T>) -> Self {
+        ButtonCell {
+            label,
+            clicks: Clicks::new(command),
+            color: theme::BUTTON_DARK.into(),
+            text_color: theme::LABEL_COLOR.into(),
+        }
+    }
+
+    pub fn target(mut self, target: impl Into<Target>) -> Self {
+        self.clicks.target = target.into();
+        self
+    }
+
+    pub fn color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn text_color(mut self, text_color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.text_color = text_color.into();
+        self
+    }
+}
+
+impl<T: Data> CellRender<T> for ButtonCell<T> {
+    fn init(&mut self, _ctx: &mut PaintCtx, _env: &Env) {}
+
+    fn paint(&self, ctx: &mut PaintCtx, _cell: &CellCtx, _data: &T, env: &Env) {
+        // TODO The size should be on the CellCtx, should not be using region
+        let bounds = ctx.region().bounding_box().with_origin(Point::ORIGIN);
+        let button = bounds
+            .inset(-1.)
+            .to_rounded_rect(env.get(theme::BUTTON_BORDER_RADIUS));
+        ctx.fill(button, &self.color.resolve(env));
+        ctx.stroke(button, &env.get(theme::BORDER_DARK), 1.);
+
+        let text_color = self.text_color.resolve(env);
+        if let Some(layout) = label_layout(ctx.text(), &self.label, text_color, env) {
+            let origin = bounds.center() - layout.size().to_vec2() / 2.;
+            ctx.draw_text(&layout, origin);
+        }
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        _env: &Env,
+    ) -> bool {
+        // The button fills the cell
+        self.clicks.event(ctx, cell, event, data, |_| true)
+    }
+}
+
+// Buttons have nothing to sort on
+impl<T> DataCompare<T> for ButtonCell<T> {
+    fn compare(&self, _a: &T, _b: &T) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T> EditorFactory<T> for ButtonCell<T> {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<T>>> {
+        None
+    }
+}
+
+// Underlined text that sends a command when clicked, eg to open a URL
+#[derive(Clone)]
+pub struct LinkCell {
+    clicks: Clicks<String>,
+    color: KeyOrValue<Color>,
+}
+
+impl LinkCell {
+    pub fn new(command: Selector<LogIdx>) -> Self {
+        Self::with_command(ClickCommand::Row(command))
+    }
+
+    // Sends the logical row index along with the link text
+    pub fn with_data(command: Selector<(LogIdx, String)>) -> Self {
+        Self::with_command(ClickCommand::RowData(command))
+    }
+
+    fn with_command(command: ClickCommand<String>) -> Self {
+        LinkCell {
+            clicks: Clicks::new(command),
+            color: Color::rgb8(0x1A, 0x5F, 0xB4).into(),
+        }
+    }
+
+    pub fn target(mut self, target: impl Into<Target>) -> Self {
+        self.clicks.target = target.into();
+        self
+    }
+
+    pub fn color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+}
+
+impl CellRender<String> for LinkCell {
+    fn init(&mut self, _ctx: &mut PaintCtx, _env: &Env) {}
+
+    fn paint(&self, ctx: &mut PaintCtx, _cell: &CellCtx, data: &String, env: &Env) {
+        let color = self.color.resolve(env);
+        if let Some(layout) = label_layout(ctx.text(), data, color.clone(), env) {
+            ctx.draw_text(&layout, (0., 0.));
+            let size = layout.size();
+            let y = size.height.round() - 0.5;
+            ctx.stroke(Line::new((0., y), (size.width, y)), &color, 1.);
+        }
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &String) -> Option<String> {
+        Some(data.clone())
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &String,
+        env: &Env,
+    ) -> bool {
+        // Only the text is a link, the rest of the cell selects as usual
+        let text_size = label_layout(ctx.text(), data, self.color.resolve(env), env)
+            .map(|layout| layout.size());
+        self.clicks.event(ctx, cell, event, data, |pos| {
+            text_size.map_or(false, |size| hits_text(size, pos))
+        })
+    }
+}
+
+impl DataCompare<String> for LinkCell {
+    fn compare(&self, a: &String, b: &String) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl EditorFactory<String> for LinkCell {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<String>>> {
+        None
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut String, text: &str) -> CommitResult {
+        *data = text.to_string();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OPEN: Selector<LogIdx> = Selector::new("druid-table.test.open");
+    const OPEN_URL: Selector<(LogIdx, String)> = Selector::new("druid-table.test.open-url");
+
+    #[test]
+    fn links_are_hit_on_their_text() {
+        let text = Size::new(60., 15.);
+        assert!(hits_text(text, Point::new(0., 0.)));
+        assert!(hits_text(text, Point::new(59., 14.)));
+        assert!(!hits_text(text, Point::new(61., 5.)));
+        assert!(!hits_text(text, Point::new(10., 16.)));
+        assert!(!hits_text(text, Point::new(-1., 5.)));
+    }
+
+    #[test]
+    fn commands_carry_the_row() {
+        let url = "https://example.com".to_string();
+        let clicks = Clicks::<String>::new(ClickCommand::Row(OPEN));
+        let command = clicks.command(LogIdx(3), &url);
+        assert_eq!(command.get(OPEN), Some(&LogIdx(3)));
+        assert_eq!(command.target(), Target::Auto);
+
+        let mut clicks = Clicks::new(ClickCommand::RowData(OPEN_URL));
+        clicks.target = Target::Global;
+        let command = clicks.command(LogIdx(5), &url);
+        assert_eq!(command.get(OPEN_URL), Some(&(LogIdx(5), url)));
+        assert!(!command.is(OPEN));
+        assert_eq!(command.target(), Target::Global);
+    }
+}
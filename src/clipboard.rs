@@ -0,0 +1,476 @@
+use crate::axis_measure::{AxisPair, LogIdx, TableAxis, VisIdx};
+use crate::cells::CellsDelegate;
+use crate::columns::{CellCtx, EditorFactory};
+use crate::data::{IndexedData, Remap};
+use crate::selection::{CellRect, SingleCell, TableSelection};
+use druid::{Application, ClipboardFormat, Data};
+use std::collections::BTreeSet;
+
+const HTML_FORMAT: &str = "text/html";
+
+// Cells copied from a table as text, in the order they are shown
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CopiedCells {
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CopiedCells {
+    fn all_rows(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.headers.iter().chain(self.rows.iter())
+    }
+
+    // Tab separated, as spreadsheets paste. Fields with tabs, newlines or quotes are quoted.
+    pub fn to_tsv(&self) -> String {
+        self.to_delimited('\t')
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.to_delimited(',')
+    }
+
+    fn to_delimited(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        for row in self.all_rows() {
+            let fields: Vec<String> = row.iter().map(|f| quote_field(f, delimiter)).collect();
+            out.push_str(&fields.join(&delimiter.to_string()));
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<table>");
+        if let Some(headers) = &self.headers {
+            out.push_str("<thead>");
+            push_html_row(&mut out, headers, "th");
+            out.push_str("</thead>");
+        }
+        out.push_str("<tbody>");
+        for row in &self.rows {
+            push_html_row(&mut out, row, "td");
+        }
+        out.push_str("</tbody></table>");
+        out
+    }
+
+    // Offers TSV as plain text and an HTML table, so rich editors keep the table structure
+    pub fn put_on_clipboard(&self) {
+        let mut clipboard = Application::global().clipboard();
+        clipboard.put_formats(&[
+            ClipboardFormat::new(ClipboardFormat::TEXT, self.to_tsv()),
+            ClipboardFormat::new(HTML_FORMAT, self.to_html()),
+        ]);
+    }
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+    if field.contains(|c| c == delimiter || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn push_html_row(out: &mut String, row: &[String], tag: &str) {
+    out.push_str("<tr>");
+    for field in row {
+        out.push_str(&format!("<{}>{}</{}>", tag, escape_html(field), tag));
+    }
+    out.push_str("</tr>");
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Every cell that is shown, from the first to the last
+pub(crate) fn shown_bounds<TableData, CD>(
+    delegate: &CD,
+    data: &TableData,
+    remaps: &AxisPair<Remap>,
+) -> CellRect
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    CellRect::new(
+        (
+            VisIdx(0),
+            remaps[TableAxis::Rows].max_vis_idx(data.idx_len()),
+        ),
+        (
+            VisIdx(0),
+            remaps[TableAxis::Columns].max_vis_idx(delegate.number_of_columns_in_data(data)),
+        ),
+    )
+}
+
+// Copies the text of the selected cells, using each column's text form. Ranges added with Ctrl
+// are copied together, closing up the rows and columns between them, with blanks for cells they
+// share a row or column with but don't select. None if nothing is selected.
+pub fn copy_selection<TableData, CD>(
+    delegate: &CD,
+    data: &TableData,
+    remaps: &AxisPair<Remap>,
+    selection: &TableSelection,
+    include_headers: bool,
+) -> Option<CopiedCells>
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    let bounds = shown_bounds(delegate, data, remaps);
+    let rects = selection.selected_rects(&bounds);
+    if rects.is_empty() {
+        return None;
+    }
+    // Whole rows and columns reach past the bounds
+    let selected = |axis: TableAxis| -> BTreeSet<VisIdx> {
+        let (first, last) = bounds.range(axis);
+        rects
+            .iter()
+            .flat_map(|rect| {
+                let (start, end) = rect.range(axis);
+                VisIdx::range_inc_iter(start.max(first), end.min(last))
+            })
+            .collect()
+    };
+
+    let log_cols: Vec<(VisIdx, LogIdx)> = selected(TableAxis::Columns)
+        .into_iter()
+        .filter_map(|vis| Some((vis, remaps[TableAxis::Columns].get_log_idx(vis)?)))
+        .collect();
+
+    let headers = if_opt!(
+        include_headers,
+        log_cols
+            .iter()
+            .map(|(_, log)| delegate.column_header(*log).unwrap_or_default())
+            .collect()
+    );
+
+    let rows = selected(TableAxis::Rows)
+        .into_iter()
+        .filter_map(|vis_row| {
+            let log_row = remaps[TableAxis::Rows].get_log_idx(vis_row)?;
+            data.with(log_row, |row| {
+                log_cols
+                    .iter()
+                    .map(|(vis_col, log_col)| {
+                        let vis = AxisPair::new(vis_row, *vis_col);
+                        if !rects.iter().any(|rect| rect.contains_cell(&vis)) {
+                            return String::new();
+                        }
+                        let cell = SingleCell::new(vis, AxisPair::new(log_row, *log_col));
+                        delegate
+                            .cell_text(&CellCtx::Cell(&cell), row)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+        })
+        .collect();
+
+    Some(CopiedCells { headers, rows })
+}
+
+// Splits tab separated text into rows of fields, undoing the quoting of to_tsv.
+// A final line break, as spreadsheets add, doesn't start another row.
+pub fn parse_tsv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            '\t' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasteFailure {
+    pub cell: AxisPair<LogIdx>,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PasteReport {
+    pub pasted: usize,
+    pub failures: Vec<PasteFailure>,
+}
+
+impl PasteReport {
+    // Parses the text into the cell of the row and validates it, leaving the row as it was if
+    // either fails
+    pub(crate) fn write_cell<RowData: Clone>(
+        &mut self,
+        editors: &impl EditorFactory<RowData>,
+        cell: &SingleCell,
+        row: &mut RowData,
+        text: &str,
+    ) {
+        let cell_ctx = CellCtx::Cell(cell);
+        let mut pasted = row.clone();
+        let result = editors
+            .paste_text(&cell_ctx, &mut pasted, text)
+            .and_then(|_| editors.validate(&cell_ctx, &pasted));
+        match result {
+            Ok(()) => {
+                *row = pasted;
+                self.pasted += 1;
+            }
+            Err(reason) => self.failures.push(PasteFailure {
+                cell: cell.log,
+                text: text.to_string(),
+                reason,
+            }),
+        }
+    }
+}
+
+// Writes tab separated text into the table, starting at the top left of the selection.
+// A selection bigger than one cell is filled, repeating the text if it is smaller. Only the
+// current range is pasted into when others were added with Ctrl.
+// Each cell is parsed by its column, and cells that fail to parse or validate are left as they
+// were and reported. All the rows are written in one go, so the paste is a single change.
+pub fn paste_into_selection<TableData, CD>(
+    delegate: &CD,
+    data: &mut TableData,
+    remaps: &AxisPair<Remap>,
+    selection: &TableSelection,
+    text: &str,
+) -> PasteReport
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    let mut report = PasteReport::default();
+    let grid = parse_tsv(text);
+    let grid_cols = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    if grid.is_empty() || grid_cols == 0 {
+        return report;
+    }
+
+    let last = AxisPair::new(
+        remaps[TableAxis::Rows].max_vis_idx(data.idx_len()),
+        remaps[TableAxis::Columns].max_vis_idx(delegate.number_of_columns_in_data(data)),
+    );
+    let bounds = CellRect::new((VisIdx(0), last.row), (VisIdx(0), last.col));
+    let target = match selection.current().bounding_rect(&bounds) {
+        Some(target) => target,
+        None => return report,
+    };
+    let span = |start: VisIdx, end: VisIdx, pasted: usize| {
+        let selected = end.0 - start.0 + 1;
+        if selected > 1 {
+            selected
+        } else {
+            pasted
+        }
+    };
+    let rows = span(target.start_row, target.end_row, grid.len());
+    let cols = span(target.start_col, target.end_col, grid_cols);
+
+    let mut changed_rows = Vec::new();
+    for r in 0..rows {
+        let vis_row = VisIdx(target.start_row.0 + r);
+        let log_row = match remaps[TableAxis::Rows].get_log_idx(vis_row) {
+            Some(log_row) if vis_row <= last.row => log_row,
+            _ => break,
+        };
+        let mut row = match data.with(log_row, |row| row.clone()) {
+            Some(row) => row,
+            None => break,
+        };
+        let grid_row = &grid[r % grid.len()];
+        for c in 0..cols {
+            let vis_col = VisIdx(target.start_col.0 + c);
+            let log_col = match remaps[TableAxis::Columns].get_log_idx(vis_col) {
+                Some(log_col) if vis_col <= last.col => log_col,
+                _ => break,
+            };
+            let text = grid_row.get(c % grid_cols).map_or("", String::as_str);
+            let cell = SingleCell::new(
+                AxisPair::new(vis_row, vis_col),
+                AxisPair::new(log_row, log_col),
+            );
+            report.write_cell(delegate, &cell, &mut row, text);
+        }
+        if data.with(log_row, |existing| !existing.same(&row)) == Some(true) {
+            changed_rows.push((log_row, row));
+        }
+    }
+
+    for (log_row, row) in changed_rows {
+        data.with_mut(log_row, |existing| *existing = row);
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::RemapDetails;
+    use crate::selection::CellRange;
+    use crate::test_fixtures::{at, cell, pristine_remaps, word_columns, word_rows};
+    use druid::im::vector;
+
+    fn copied(headers: Option<&[&str]>, rows: &[&[&str]]) -> CopiedCells {
+        let strings = |row: &[&str]| row.iter().map(|s| s.to_string()).collect();
+        CopiedCells {
+            headers: headers.map(strings),
+            rows: rows.iter().map(|row| strings(row)).collect(),
+        }
+    }
+
+    #[test]
+    fn delimited_fields_are_quoted() {
+        let cells = copied(None, &[&["a", "b\tc"], &["say \"hi\"", "d,e"]]);
+        assert_eq!(cells.to_tsv(), "a\t\"b\tc\"\r\n\"say \"\"hi\"\"\"\td,e\r\n");
+        assert_eq!(cells.to_csv(), "a,b\tc\r\n\"say \"\"hi\"\"\",\"d,e\"\r\n");
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let cells = copied(Some(&["Name"]), &[&["<b>&</b>"]]);
+        assert_eq!(
+            cells.to_html(),
+            "<table><thead><tr><th>Name</th></tr></thead>\
+             <tbody><tr><td>&lt;b&gt;&amp;&lt;/b&gt;</td></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn tsv_round_trips() {
+        let cells = copied(
+            None,
+            &[&["a", "b\tc", ""], &["say \"hi\"", "two\nlines", "x"]],
+        );
+        assert_eq!(parse_tsv(&cells.to_tsv()), cells.rows);
+        assert_eq!(parse_tsv("1\t2\n3"), vec![vec!["1", "2"], vec!["3"]]);
+        assert!(parse_tsv("").is_empty());
+    }
+
+    #[test]
+    fn paste_fills_selection_and_reports_failures() {
+        let columns = word_columns();
+        let mut data = word_rows(&[("one", 0.25), ("two", 0.5), ("three", 1.)]);
+        let remaps = pristine_remaps();
+
+        // From the focused cell, clipped to the table
+        let focus = TableSelection::SingleCell(cell(1, 0));
+        let report = paste_into_selection(&columns, &mut data, &remaps, &focus, "a\tnope\nb\t2\nc");
+        assert_eq!(report.pasted, 2);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].cell, AxisPair::new(LogIdx(1), LogIdx(1)));
+        assert_eq!(data[1], ("a".into(), 0.5));
+        // Fails validation, as progress is at most 1
+        assert_eq!(data[2], ("b".into(), 1.));
+        // Beyond the columns, or outside a cell, there is nothing to paste into
+        assert!(columns
+            .paste_text(&CellCtx::Cell(&cell(0, 2)), &mut data[0], "x")
+            .is_err());
+        assert!(columns
+            .paste_text(&CellCtx::Absent, &mut data[0], "x")
+            .is_err());
+
+        // One value fills the whole range
+        let range = TableSelection::CellRange(CellRange::new(cell(0, 1), cell(2, 1)));
+        let report = paste_into_selection(&columns, &mut data, &remaps, &range, "0.75\r\n");
+        assert_eq!(report.pasted, 3);
+        assert!(data.iter().all(|(_, done)| *done == 0.75));
+    }
+
+    #[test]
+    fn copies_selected_range_in_visual_order() {
+        let columns = word_columns();
+        let data = word_rows(&[("one", 0.25), ("two", 0.5), ("three", 1.)]);
+        // Rows shown in reverse
+        let remaps = AxisPair::new(
+            Remap::Selected(RemapDetails::Full(vector![LogIdx(2), LogIdx(1), LogIdx(0)])),
+            Remap::Pristine,
+        );
+        let cell = |row: usize, col: usize| {
+            SingleCell::new(at(row, col), AxisPair::new(LogIdx(2 - row), LogIdx(col)))
+        };
+        let selection = TableSelection::CellRange(CellRange::new(cell(1, 1), cell(0, 0)));
+
+        let cells = copy_selection(&columns, &data, &remaps, &selection, true).unwrap();
+        assert_eq!(
+            cells,
+            copied(Some(&["Word", "Done"]), &[&["three", "1"], &["two", "0.5"]])
+        );
+
+        let single = TableSelection::SingleCell(cell(2, 1));
+        let cells = copy_selection(&columns, &data, &remaps, &single, false).unwrap();
+        assert_eq!(cells.to_tsv(), "0.25\r\n");
+        assert_eq!(
+            copy_selection(
+                &columns,
+                &data,
+                &remaps,
+                &TableSelection::NoSelection,
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn copies_the_union_of_added_ranges() {
+        let columns = word_columns();
+        let data = word_rows(&[("one", 0.25), ("two", 0.5), ("three", 1.)]);
+        let remaps = pristine_remaps();
+        let selection = TableSelection::SingleCell(cell(0, 0))
+            .add_selection(TableSelection::SingleCell(cell(2, 1)))
+            .unwrap();
+        let bounds = CellRect::new((VisIdx(0), VisIdx(2)), (VisIdx(0), VisIdx(1)));
+        assert_eq!(selection.selected_cells(&bounds).len(), 2);
+        assert_eq!(selection.vis_focus(), Some(&cell(2, 1).vis));
+
+        // The row between them is closed up
+        let cells = copy_selection(&columns, &data, &remaps, &selection, false).unwrap();
+        assert_eq!(cells, copied(None, &[&["one", ""], &["", "1"]]));
+
+        // Extending moves the current range, keeping the one added before
+        let selection = selection
+            .move_extent(TableSelection::SingleCell(cell(1, 1)))
+            .unwrap();
+        let cells = copy_selection(&columns, &data, &remaps, &selection, false).unwrap();
+        assert_eq!(
+            cells,
+            copied(None, &[&["one", ""], &["", "0.5"], &["", "1"]])
+        );
+    }
+}
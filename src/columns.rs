@@ -4,13 +4,18 @@ use std::ops::{Deref, DerefMut};
 use crate::axis_measure::{AxisPair, LogIdx};
 use crate::data::SortDirection::Ascending;
 use crate::data::{RemapDetails, SortDirection, SortSpec};
+use crate::editors::TextEditor;
+use crate::fill::numeric_series;
+use crate::filters::passes_filters;
+use crate::formatting::{CellStyle, FormatRule, STYLE_BOLD, STYLE_STRIKETHROUGH, STYLE_TEXT_COLOR};
 use crate::selection::SingleCell;
 use crate::{CellsDelegate, IndexedData, IndexedItems, Remap, RemapSpec, Remapper, TableAxis};
 use druid::im::Vector;
 use druid::kurbo::{Line, PathEl};
-use druid::piet::{FontFamily, Text, TextLayoutBuilder};
+use druid::piet::{
+    FontFamily, FontWeight, PietText, PietTextLayout, Text, TextLayout, TextLayoutBuilder,
+};
 use druid::widget::prelude::*;
-use druid::widget::TextBox;
 use druid::{theme, ArcStr, Color, Data, Env, KeyOrValue, Lens, PaintCtx, Point, WidgetExt};
 use std::cmp::Ordering;
 use std::fmt;
@@ -18,8 +23,35 @@ use std::fmt::{Debug, Formatter};
 
 pub trait EditorFactory<RowData> {
     fn make_editor(&mut self, ctx: &CellCtx) -> Option<Box<dyn Widget<RowData>>>;
+
+    // Editors work on a copy of the row, which is written back when the edit is committed.
+    // Returning an error rejects the edited value, and the editor stays open.
+    fn can_commit(&self, _ctx: &CellCtx, _original: &RowData, _edited: &RowData) -> CommitResult {
+        Ok(())
+    }
+
+    // Checks a value, whether it is being edited or already stored.
+    // Invalid values can't be committed, and stored ones are marked in the table.
+    fn validate(&self, _ctx: &CellCtx, _data: &RowData) -> CommitResult {
+        Ok(())
+    }
+
+    // Writes text pasted into the cell to the row, in the form the cell copies as
+    fn paste_text(&self, _ctx: &CellCtx, _data: &mut RowData, _text: &str) -> CommitResult {
+        Err(NOT_PASTEABLE.into())
+    }
+
+    // The next values after the text of some cells, when the fill handle is dragged past them.
+    // None when they aren't a series, and the cells are repeated instead.
+    fn fill_series(&self, _ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        numeric_series(source, count)
+    }
 }
 
+pub type CommitResult = Result<(), String>;
+
+const NOT_PASTEABLE: &str = "This column can't be pasted into";
+
 pub trait CellDelegate<RowData>:
     CellRender<RowData> + DataCompare<RowData> + EditorFactory<RowData>
 {
@@ -32,12 +64,75 @@ impl<T> CellRender<T> for Box<dyn CellDelegate<T>> {
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
         self.deref().paint(ctx, cell, data, env);
     }
+    fn observes_rows(&self) -> bool {
+        self.deref().observes_rows()
+    }
+    fn start_observe(&mut self) {
+        self.deref_mut().start_observe()
+    }
+    fn observe(&mut self, data: &T) {
+        self.deref_mut().observe(data)
+    }
+    fn end_observe(&mut self) {
+        self.deref_mut().end_observe()
+    }
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        self.deref().cell_style(cell, data)
+    }
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        self.deref().event(ctx, cell, event, data, env)
+    }
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.deref_mut().update_text_options(update)
+    }
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        self.deref().spills(cell, data)
+    }
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        self.deref().is_blank(cell, data)
+    }
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        self.deref().cell_tooltip(text, size, cell, data, env)
+    }
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        self.deref().cell_text(cell, data)
+    }
 }
 
 impl<RowData> EditorFactory<RowData> for Box<dyn CellDelegate<RowData>> {
     fn make_editor(&mut self, ctx: &CellCtx) -> Option<Box<dyn Widget<RowData>>> {
         self.deref_mut().make_editor(ctx)
     }
+
+    fn can_commit(&self, ctx: &CellCtx, original: &RowData, edited: &RowData) -> CommitResult {
+        self.deref().can_commit(ctx, original, edited)
+    }
+
+    fn validate(&self, ctx: &CellCtx, data: &RowData) -> CommitResult {
+        self.deref().validate(ctx, data)
+    }
+
+    fn paste_text(&self, ctx: &CellCtx, data: &mut RowData, text: &str) -> CommitResult {
+        self.deref().paste_text(ctx, data, text)
+    }
+
+    fn fill_series(&self, ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        self.deref().fill_series(ctx, source, count)
+    }
 }
 
 impl<T> DataCompare<T> for Box<dyn CellDelegate<T>> {
@@ -59,18 +154,127 @@ impl<T> CellRender<T> for Box<dyn CellRender<T>> {
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
         self.deref().paint(ctx, cell, data, env);
     }
+    fn observes_rows(&self) -> bool {
+        self.deref().observes_rows()
+    }
+    fn start_observe(&mut self) {
+        self.deref_mut().start_observe()
+    }
+    fn observe(&mut self, data: &T) {
+        self.deref_mut().observe(data)
+    }
+    fn end_observe(&mut self) {
+        self.deref_mut().end_observe()
+    }
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        self.deref().cell_style(cell, data)
+    }
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        self.deref().event(ctx, cell, event, data, env)
+    }
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.deref_mut().update_text_options(update)
+    }
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        self.deref().spills(cell, data)
+    }
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        self.deref().is_blank(cell, data)
+    }
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        self.deref().cell_tooltip(text, size, cell, data, env)
+    }
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        self.deref().cell_text(cell, data)
+    }
 }
 
 #[derive(Debug)]
 pub enum CellCtx<'a> {
     Absent,
     Cell(&'a SingleCell),
-    Header(&'a TableAxis, LogIdx, Option<&'a SortSpec>),
+    // A cell by its place in the data alone, as rows that aren't shown have no visual place.
+    // Only its text is asked for, eg to filter rows.
+    Logical(AxisPair<LogIdx>),
+    // The spec of the cross axis, eg the sort of the rows for a column header
+    Header(&'a TableAxis, LogIdx, &'a RemapSpec),
 }
 
 pub trait CellRender<T> {
     fn init(&mut self, ctx: &mut PaintCtx, env: &Env); // Use to cache resources like fonts
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env);
+
+    // Renderers that scale to the values in their column (eg data bars) return true here.
+    // Whenever the data or row remap changes, they are then shown every visible row in order,
+    // between start_observe and end_observe.
+    fn observes_rows(&self) -> bool {
+        false
+    }
+    fn start_observe(&mut self) {}
+    fn observe(&mut self, _data: &T) {}
+    fn end_observe(&mut self) {}
+
+    // Styling for the whole cell (eg conditional formatting), applied by the table around paint
+    fn cell_style(&self, _cell: &CellCtx, _data: &T) -> Option<CellStyle> {
+        None
+    }
+
+    // Renderers that react to the mouse (eg buttons) return true to claim an event in their cell,
+    // which then doesn't go to the selection. Mouse positions are relative to the cell.
+    // After a claimed MouseDown, the MouseUp is passed on if it is over the same cell.
+    fn event(
+        &self,
+        _ctx: &mut EventCtx,
+        _cell: &CellCtx,
+        _event: &Event,
+        _data: &T,
+        _env: &Env,
+    ) -> bool {
+        false
+    }
+
+    // Column level text options, for renderers that draw text
+    fn update_text_options(&mut self, _update: &dyn Fn(&mut TextOptions)) {}
+
+    // Renderers whose content may spill over empty neighbouring cells return true here.
+    // They are then clipped to the run of blank cells around them rather than to their own cell.
+    fn spills(&self, _cell: &CellCtx, _data: &T) -> bool {
+        false
+    }
+    fn is_blank(&self, _cell: &CellCtx, _data: &T) -> bool {
+        false
+    }
+
+    // Shown when the mouse rests over the cell. Size is the space the cell is painted in.
+    fn cell_tooltip(
+        &self,
+        _text: &mut PietText,
+        _size: Size,
+        _cell: &CellCtx,
+        _data: &T,
+        _env: &Env,
+    ) -> Option<String> {
+        None
+    }
+
+    // The cell as plain text, used when copying. None if it has no text form, eg a button.
+    fn cell_text(&self, _cell: &CellCtx, _data: &T) -> Option<String> {
+        None
+    }
 }
 
 impl<T, CR: CellRender<T>> CellRender<T> for Vec<CR> {
@@ -91,6 +295,116 @@ impl<T, CR: CellRender<T>> CellRender<T> for Vec<CR> {
             }
         }
     }
+
+    fn observes_rows(&self) -> bool {
+        self.iter().any(|col| col.observes_rows())
+    }
+
+    fn start_observe(&mut self) {
+        for col in self {
+            col.start_observe()
+        }
+    }
+
+    fn observe(&mut self, data: &T) {
+        for col in self.iter_mut().filter(|col| col.observes_rows()) {
+            col.observe(data)
+        }
+    }
+
+    fn end_observe(&mut self) {
+        for col in self {
+            col.end_observe()
+        }
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        if let CellCtx::Cell(SingleCell {
+            log: AxisPair { col, .. },
+            ..
+        }) = cell
+        {
+            self.get(col.0)?.cell_style(cell, data)
+        } else {
+            None
+        }
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        if let CellCtx::Cell(SingleCell {
+            log: AxisPair { col, .. },
+            ..
+        }) = cell
+        {
+            if let Some(cell_render) = self.get(col.0) {
+                return cell_render.event(ctx, cell, event, data, env);
+            }
+        }
+        false
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        for col in self {
+            col.update_text_options(update)
+        }
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self.get(col.0).map_or(false, |cr| cr.spills(cell, data)),
+            _ => false,
+        }
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self.get(col.0).map_or(false, |cr| cr.is_blank(cell, data)),
+            _ => false,
+        }
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        if let CellCtx::Cell(SingleCell {
+            log: AxisPair { col, .. },
+            ..
+        }) = cell
+        {
+            self.get(col.0)?.cell_tooltip(text, size, cell, data, env)
+        } else {
+            None
+        }
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            })
+            | CellCtx::Logical(AxisPair { col, .. }) => self.get(col.0)?.cell_text(cell, data),
+            _ => None,
+        }
+    }
 }
 
 impl<T, EF: EditorFactory<T>> EditorFactory<T> for Vec<EF> {
@@ -106,6 +420,51 @@ impl<T, EF: EditorFactory<T>> EditorFactory<T> for Vec<EF> {
         }
         None
     }
+
+    fn can_commit(&self, cell: &CellCtx, original: &T, edited: &T) -> CommitResult {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self
+                .get(col.0)
+                .map_or(Ok(()), |ef| ef.can_commit(cell, original, edited)),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate(&self, cell: &CellCtx, data: &T) -> CommitResult {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self.get(col.0).map_or(Ok(()), |ef| ef.validate(cell, data)),
+            _ => Ok(()),
+        }
+    }
+
+    fn paste_text(&self, cell: &CellCtx, data: &mut T, text: &str) -> CommitResult {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self.get(col.0).map_or_else(
+                || Err(NOT_PASTEABLE.into()),
+                |ef| ef.paste_text(cell, data, text),
+            ),
+            _ => Err(NOT_PASTEABLE.into()),
+        }
+    }
+
+    fn fill_series(&self, cell: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        match cell {
+            CellCtx::Cell(SingleCell {
+                log: AxisPair { col, .. },
+                ..
+            }) => self.get(col.0)?.fill_series(cell, source, count),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -169,6 +528,85 @@ where
             inner.paint(ctx, cell, inner_data, env);
         })
     }
+
+    fn observes_rows(&self) -> bool {
+        self.0.inner.observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.0.inner.start_observe()
+    }
+
+    fn observe(&mut self, data: &T) {
+        let inner = &mut self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.observe(inner_data))
+    }
+
+    fn end_observe(&mut self) {
+        self.0.inner.end_observe()
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.cell_style(cell, inner_data))
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        let inner = &self.0.inner;
+        self.0.wrapper.with(data, |inner_data| {
+            inner.event(ctx, cell, event, inner_data, env)
+        })
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.0.inner.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.spills(cell, inner_data))
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.is_blank(cell, inner_data))
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        let inner = &self.0.inner;
+        self.0.wrapper.with(data, |inner_data| {
+            inner.cell_tooltip(text, size, cell, inner_data, env)
+        })
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.cell_text(cell, inner_data))
+    }
 }
 
 impl<T, U, L, DC> DataCompare<T> for LensWrapped<T, U, L, DC>
@@ -200,6 +638,33 @@ where
             None
         }
     }
+
+    fn can_commit(&self, ctx: &CellCtx, original: &T, edited: &T) -> CommitResult {
+        let inner = &self.0.inner;
+        self.0.wrapper.with(original, |original| {
+            self.0
+                .wrapper
+                .with(edited, |edited| inner.can_commit(ctx, original, edited))
+        })
+    }
+
+    fn validate(&self, ctx: &CellCtx, data: &T) -> CommitResult {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with(data, |inner_data| inner.validate(ctx, inner_data))
+    }
+
+    fn paste_text(&self, ctx: &CellCtx, data: &mut T, text: &str) -> CommitResult {
+        let inner = &self.0.inner;
+        self.0
+            .wrapper
+            .with_mut(data, |inner_data| inner.paste_text(ctx, inner_data, text))
+    }
+
+    fn fill_series(&self, ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        self.0.inner.fill_series(ctx, source, count)
+    }
 }
 
 impl<T, U, F, CR> CellRender<T> for FuncWrapped<T, U, F, CR>
@@ -218,6 +683,73 @@ where
         let inner_data = (self.0.wrapper)(data);
         inner.paint(ctx, cell, &inner_data, env);
     }
+
+    fn observes_rows(&self) -> bool {
+        self.0.inner.observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.0.inner.start_observe()
+    }
+
+    fn observe(&mut self, data: &T) {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.observe(&inner_data)
+    }
+
+    fn end_observe(&mut self) {
+        self.0.inner.end_observe()
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.cell_style(cell, &inner_data)
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.event(ctx, cell, event, &inner_data, env)
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.0.inner.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.spills(cell, &inner_data)
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.is_blank(cell, &inner_data)
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        let inner_data = (self.0.wrapper)(data);
+        self.0
+            .inner
+            .cell_tooltip(text, size, cell, &inner_data, env)
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        let inner_data = (self.0.wrapper)(data);
+        self.0.inner.cell_text(cell, &inner_data)
+    }
 }
 
 impl<T, U, F, DC> DataCompare<T> for FuncWrapped<T, U, F, DC>
@@ -234,12 +766,116 @@ where
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right,
+    // Lines up the decimal points of the values in the column
+    Decimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerticalAlignment {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextOverflow {
+    Clip,
+    Ellipsis,
+    // Like Excel, text runs on over empty neighbouring cells
+    Spill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextOptions {
+    pub align: HorizontalAlignment,
+    pub vertical_align: VerticalAlignment,
+    pub overflow: TextOverflow,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            align: HorizontalAlignment::Left,
+            vertical_align: VerticalAlignment::Top,
+            overflow: TextOverflow::Clip,
+        }
+    }
+}
+
+impl TextOptions {
+    // Where text of the size starts in the bounds. For decimal alignment, point_x is how far
+    // into the text its decimal point is, and reserve the room the column keeps after it.
+    fn text_origin(
+        &self,
+        bounds: Size,
+        size: Size,
+        point_x: f64,
+        reserve: f64,
+        truncated: bool,
+    ) -> Point {
+        let free = bounds - size;
+        let x = match self.align {
+            HorizontalAlignment::Left => 0.,
+            HorizontalAlignment::Center => free.width / 2.,
+            HorizontalAlignment::Right => free.width,
+            HorizontalAlignment::Decimal if truncated => 0.,
+            HorizontalAlignment::Decimal => bounds.width - reserve - point_x,
+        };
+        // Only spilling text may start outside the cell
+        let x = if self.overflow == TextOverflow::Spill {
+            x
+        } else {
+            x.max(0.)
+        };
+        let y = match self.vertical_align {
+            VerticalAlignment::Top => 0.,
+            VerticalAlignment::Middle => free.height / 2.,
+            VerticalAlignment::Bottom => free.height,
+        };
+        Point::new(x, y)
+    }
+}
+
+const ELLIPSIS: &str = "\u{2026}";
+
+// The first characters of the text with an ellipsis after them
+fn ellipsized(text: &str, keep: usize) -> String {
+    let end = text
+        .char_indices()
+        .nth(keep)
+        .map_or(text.len(), |(idx, _)| idx);
+    format!("{}{}", text[..end].trim_end(), ELLIPSIS)
+}
+
+// The most, up to max, that still fits. Fitting is assumed to stop at some point and not start
+// again, so this is a binary search.
+fn most_that_fits(max: usize, mut fits: impl FnMut(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, max);
+    while low < high {
+        let mid = (low + high + 1) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
 #[derive(Clone)]
 pub struct TextCell {
     text_color: KeyOrValue<Color>,
     font_name: KeyOrValue<ArcStr>,
     font_size: KeyOrValue<f64>,
+    options: TextOptions,
     cached_font: Option<FontFamily>,
+    // Most characters from the decimal point onwards in the column, for decimal alignment
+    decimal_chars: usize,
 }
 
 impl TextCell {
@@ -248,7 +884,9 @@ impl TextCell {
             text_color: Color::BLACK.into(),
             font_name: ArcStr::from("Gill Sans").into(),
             font_size: theme::TEXT_SIZE_NORMAL.into(),
+            options: TextOptions::default(),
             cached_font: None,
+            decimal_chars: 0,
         }
     }
 
@@ -267,6 +905,21 @@ impl TextCell {
         self
     }
 
+    pub fn align(mut self, align: HorizontalAlignment) -> TextCell {
+        self.options.align = align;
+        self
+    }
+
+    pub fn vertical_align(mut self, vertical_align: VerticalAlignment) -> TextCell {
+        self.options.vertical_align = vertical_align;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: TextOverflow) -> TextCell {
+        self.options.overflow = overflow;
+        self
+    }
+
     fn resolve_font(&self, ctx: &mut PaintCtx, env: &Env) -> FontFamily {
         let font: FontFamily = ctx
             .text()
@@ -275,18 +928,107 @@ impl TextCell {
         font
     }
 
+    fn layout(
+        &self,
+        factory: &mut PietText,
+        text: String,
+        font: &FontFamily,
+        color: &Color,
+        env: &Env,
+    ) -> Option<PietTextLayout> {
+        let mut builder = factory
+            .new_text_layout(text)
+            .font(font.clone(), self.font_size.resolve(env))
+            .text_color(color.clone());
+        if env.try_get(STYLE_BOLD).unwrap_or(false) {
+            builder = builder.default_attribute(FontWeight::BOLD);
+        }
+        builder.build().ok()
+    }
+
+    // The longest prefix of the text that fits in the width with an ellipsis after it
+    fn ellipsis_layout(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &str,
+        width: f64,
+        font: &FontFamily,
+        color: &Color,
+        env: &Env,
+    ) -> Option<PietTextLayout> {
+        // The whole text didn't fit, so at least one character goes
+        let keep = most_that_fits(data.chars().count().saturating_sub(1), |keep| {
+            self.layout(ctx.text(), ellipsized(data, keep), font, color, env)
+                .map_or(false, |layout| layout.size().width <= width)
+        });
+        self.layout(ctx.text(), ellipsized(data, keep), font, color, env)
+    }
+
+    // Room to leave at the right for the longest fraction in the column
+    fn decimal_reserve(
+        &self,
+        ctx: &mut PaintCtx,
+        font: &FontFamily,
+        color: &Color,
+        env: &Env,
+    ) -> f64 {
+        if self.decimal_chars == 0 {
+            return 0.;
+        }
+        let widest_fraction = format!(".{}", "0".repeat(self.decimal_chars - 1));
+        self.layout(ctx.text(), widest_fraction, font, color, env)
+            .map(|layout| layout.size().width)
+            .unwrap_or(0.)
+    }
+
     fn paint_impl(&self, ctx: &mut PaintCtx, data: &str, env: &Env, font: &FontFamily) {
         // TODO: error handling
         // TODO: wrapping (multi line)
 
-        if let Ok(layout) = ctx
-            .text()
-            .new_text_layout(data.to_string())
-            .font(font.clone(), self.font_size.resolve(env))
-            .text_color(self.text_color.resolve(env))
-            .build()
-        {
-            ctx.draw_text(&layout, (0.0, 0.0));
+        // TODO The size should be on the CellCtx, should not be using region
+        let bounds = ctx.region().bounding_box().size();
+        let text_color = env
+            .try_get(STYLE_TEXT_COLOR)
+            .unwrap_or_else(|_| self.text_color.resolve(env));
+
+        let layout = match self.layout(ctx.text(), data.to_string(), font, &text_color, env) {
+            Some(layout) => layout,
+            None => return,
+        };
+        let truncated =
+            self.options.overflow == TextOverflow::Ellipsis && layout.size().width > bounds.width;
+        let layout = if truncated {
+            match self.ellipsis_layout(ctx, data, bounds.width, font, &text_color, env) {
+                Some(layout) => layout,
+                None => return,
+            }
+        } else {
+            layout
+        };
+
+        let size = layout.size();
+        let decimal = self.options.align == HorizontalAlignment::Decimal && !truncated;
+        let (point_x, reserve) = if decimal {
+            let point_x = match data.rfind('.') {
+                Some(idx) => layout.hit_test_text_position(idx).point.x,
+                None => size.width,
+            };
+            (point_x, self.decimal_reserve(ctx, font, &text_color, env))
+        } else {
+            (0., 0.)
+        };
+        let Point { x, y } = self
+            .options
+            .text_origin(bounds, size, point_x, reserve, truncated);
+
+        ctx.draw_text(&layout, (x, y));
+        if env.try_get(STYLE_STRIKETHROUGH).unwrap_or(false) {
+            let line_y = (y + size.height / 2.).round() + 0.5;
+            ctx.stroke(
+                Line::new((x, line_y), (x + size.width, line_y)),
+                &text_color,
+                1.,
+            );
         }
     }
 }
@@ -319,11 +1061,63 @@ impl CellRender<String> for TextCell {
             self.paint_impl(ctx, &data, env, &font);
         }
     }
+
+    fn observes_rows(&self) -> bool {
+        self.options.align == HorizontalAlignment::Decimal
+    }
+
+    fn start_observe(&mut self) {
+        self.decimal_chars = 0;
+    }
+
+    fn observe(&mut self, data: &String) {
+        if let Some(idx) = data.rfind('.') {
+            self.decimal_chars = self.decimal_chars.max(data[idx..].chars().count());
+        }
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        update(&mut self.options)
+    }
+
+    fn spills(&self, _cell: &CellCtx, data: &String) -> bool {
+        self.options.overflow == TextOverflow::Spill && !data.is_empty()
+    }
+
+    fn is_blank(&self, _cell: &CellCtx, data: &String) -> bool {
+        data.is_empty()
+    }
+
+    // Truncated text is shown in full
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        _cell: &CellCtx,
+        data: &String,
+        env: &Env,
+    ) -> Option<String> {
+        if data.is_empty() || self.options.overflow == TextOverflow::Spill {
+            return None;
+        }
+        let font = self.cached_font.as_ref()?;
+        let layout = self.layout(text, data.clone(), font, &Color::BLACK, env)?;
+        if_opt!(layout.size().width > size.width, data.clone())
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &String) -> Option<String> {
+        Some(data.clone())
+    }
 }
 
 impl EditorFactory<String> for TextCell {
     fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<String>>> {
-        Some(Box::new(TextBox::new().expand_height()))
+        Some(Box::new(TextEditor::new()))
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut String, text: &str) -> CommitResult {
+        *data = text.to_string();
+        Ok(())
     }
 }
 
@@ -370,35 +1164,75 @@ impl<T, I: CellRender<T>> CellRender<T> for HeaderCell<T, I> {
     }
 
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
-        match cell {
-            CellCtx::Header(_, _, Some(ss)) => {
-                // TODO The size should be on the CellCtx, should not be using region
-                let rect = ctx
-                    .region()
-                    .bounding_box()
-                    .with_origin(Point::ORIGIN)
-                    .inset(-3.);
-                let rad = rect.height() * 0.25;
-                let up = ss.direction == Ascending;
-
-                let arrow = make_arrow(
-                    &Point::new(rect.max_x() - rad, rect.min_y()),
-                    up,
-                    rect.height(),
-                    rad,
-                );
-                ctx.render_ctx.stroke(&arrow[..], &Color::WHITE, 1.0);
-                let rect1 = ctx.region().bounding_box();
-                let rect1 = rect1
-                    .with_origin(Point::ORIGIN)
-                    .with_size((rect1.width() - (rad + 3.) * 2., rect1.height()));
-                ctx.clip(rect1);
-                self.inner.paint(ctx, cell, data, env);
+        let (col, spec) = match cell {
+            CellCtx::Header(_, col, spec) => (*col, *spec),
+            _ => return self.inner.paint(ctx, cell, data, env),
+        };
+        let sort = spec.sort_of(col);
+        let fixed = spec.is_sort_fixed(col);
+        if sort.is_none() && !fixed {
+            return self.inner.paint(ctx, cell, data, env);
+        }
+
+        // TODO The size should be on the CellCtx, should not be using region
+        let rect = ctx
+            .region()
+            .bounding_box()
+            .with_origin(Point::ORIGIN)
+            .inset(-3.);
+        let rad = rect.height() * 0.25;
+        let mid_x = rect.max_x() - rad;
+        // Fixed sorts are greyed, and can't be changed
+        let color = if fixed {
+            env.get(theme::PLACEHOLDER_COLOR)
+        } else {
+            Color::WHITE
+        };
+        let mut reserved = (rad + 3.) * 2.;
+
+        if let Some((priority, direction)) = sort {
+            let arrow = make_arrow(
+                &Point::new(mid_x, rect.min_y()),
+                direction == Ascending,
+                rect.height(),
+                rad,
+            );
+            ctx.render_ctx.stroke(&arrow[..], &color, 1.0);
+            if fixed {
+                let base = Line::new((mid_x - rad, rect.max_y()), (mid_x + rad, rect.max_y()));
+                ctx.render_ctx.stroke(base, &color, 1.5);
             }
-            _ => {
-                self.inner.paint(ctx, cell, data, env);
+
+            // Numbered from 1 when sorting by more than one column
+            if spec.sorted_columns() > 1 {
+                let layout = ctx
+                    .text()
+                    .new_text_layout((priority + 1).to_string())
+                    .font(
+                        FontFamily::SYSTEM_UI,
+                        env.get(theme::TEXT_SIZE_NORMAL) * 0.7,
+                    )
+                    .text_color(color.clone())
+                    .build();
+                if let Ok(layout) = layout {
+                    let width = layout.size().width;
+                    ctx.draw_text(&layout, (mid_x - rad - 1. - width, rect.min_y()));
+                    reserved += width + 1.;
+                }
             }
+        } else {
+            // A bar where the arrow would be, as the column can't be sorted
+            let y = rect.center().y;
+            ctx.render_ctx
+                .stroke(Line::new((mid_x - rad, y), (mid_x + rad, y)), &color, 1.5);
         }
+
+        let rect1 = ctx.region().bounding_box();
+        let rect1 = rect1
+            .with_origin(Point::ORIGIN)
+            .with_size(((rect1.width() - reserved).max(0.), rect1.height()));
+        ctx.clip(rect1);
+        self.inner.paint(ctx, cell, data, env);
     }
 }
 
@@ -415,6 +1249,13 @@ pub struct TableColumn<T: Data, CD: CellDelegate<T>> {
     pub(crate) sort_order: Option<usize>,
     pub(crate) sort_fixed: bool,
     pub(crate) sort_dir: Option<SortDirection>,
+    formats: Vec<FormatRule<T>>,
+    tooltip: Option<Box<dyn Fn(&T) -> Option<String>>>,
+    commit_check: Option<Box<dyn Fn(&T, &T) -> CommitResult>>,
+    validator: Option<Box<dyn Fn(&T) -> CommitResult>>,
+    paste: Option<Box<dyn Fn(&mut T, &str) -> CommitResult>>,
+    pub(crate) merge_repeats: bool,
+    pub(crate) column_span: Option<Box<dyn Fn(&T) -> usize>>,
     phantom_: PhantomData<T>,
 }
 
@@ -481,6 +1322,13 @@ impl<T: Data, CD: CellDelegate<T>> TableColumn<T, CD> {
             sort_fixed: false,
             sort_dir: None,
             width: Default::default(),
+            formats: Vec::new(),
+            tooltip: None,
+            commit_check: None,
+            validator: None,
+            paste: None,
+            merge_repeats: false,
+            column_span: None,
             phantom_: PhantomData::default(),
         }
     }
@@ -499,6 +1347,72 @@ impl<T: Data, CD: CellDelegate<T>> TableColumn<T, CD> {
         self.sort_fixed = true;
         self
     }
+
+    // Rules are applied in the order they were added, so later rules win where they overlap
+    pub fn format(mut self, rule: FormatRule<T>) -> Self {
+        self.formats.push(rule);
+        self
+    }
+
+    // Text options set on the column override those of the cell renderer
+    pub fn align(mut self, align: HorizontalAlignment) -> Self {
+        self.cell_delegate
+            .update_text_options(&|options| options.align = align);
+        self
+    }
+
+    pub fn vertical_align(mut self, vertical_align: VerticalAlignment) -> Self {
+        self.cell_delegate
+            .update_text_options(&|options| options.vertical_align = vertical_align);
+        self
+    }
+
+    pub fn overflow(mut self, overflow: TextOverflow) -> Self {
+        self.cell_delegate
+            .update_text_options(&|options| options.overflow = overflow);
+        self
+    }
+
+    // Tooltip content for a row, eg an explanation or validation message.
+    // Takes precedence over the full text of truncated cells.
+    pub fn tooltip(mut self, tooltip: impl Fn(&T) -> Option<String> + 'static) -> Self {
+        self.tooltip = Some(Box::new(tooltip));
+        self
+    }
+
+    // Called with the original and edited rows when an edit in this column is committed.
+    // An error rejects the edit and keeps the editor open.
+    pub fn on_commit(mut self, check: impl Fn(&T, &T) -> CommitResult + 'static) -> Self {
+        self.commit_check = Some(Box::new(check));
+        self
+    }
+
+    // Values in this column that fail are shown with an error marker, and can't be committed by
+    // an editor. The message is shown while editing and as the cell's tooltip.
+    pub fn validate(mut self, validator: impl Fn(&T) -> CommitResult + 'static) -> Self {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    // Parses text pasted into this column, instead of the cell delegate
+    pub fn on_paste(mut self, paste: impl Fn(&mut T, &str) -> CommitResult + 'static) -> Self {
+        self.paste = Some(Box::new(paste));
+        self
+    }
+
+    // Runs of the same value one above another are shown as one tall cell, eg the category of
+    // rows sorted by it
+    pub fn merge_repeats(mut self) -> Self {
+        self.merge_repeats = true;
+        self
+    }
+
+    // How many columns a cell covers in a row, starting from this one and going right in the
+    // order they are shown. Used for eg a section title across the table.
+    pub fn span_columns(mut self, span: impl Fn(&T) -> usize + 'static) -> Self {
+        self.column_span = Some(Box::new(span));
+        self
+    }
 }
 
 impl<T: Data, CR: CellDelegate<T>> CellRender<T> for TableColumn<T, CR> {
@@ -509,6 +1423,82 @@ impl<T: Data, CR: CellDelegate<T>> CellRender<T> for TableColumn<T, CR> {
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
         self.cell_delegate.paint(ctx, cell, data, env)
     }
+
+    fn observes_rows(&self) -> bool {
+        self.cell_delegate.observes_rows() || self.formats.iter().any(|f| f.observes_rows())
+    }
+
+    fn start_observe(&mut self) {
+        self.cell_delegate.start_observe();
+        for format in &mut self.formats {
+            format.start_observe()
+        }
+    }
+
+    fn observe(&mut self, data: &T) {
+        if self.cell_delegate.observes_rows() {
+            self.cell_delegate.observe(data)
+        }
+        for format in self.formats.iter_mut().filter(|f| f.observes_rows()) {
+            format.observe(data)
+        }
+    }
+
+    fn end_observe(&mut self) {
+        self.cell_delegate.end_observe();
+        for format in &mut self.formats {
+            format.end_observe()
+        }
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        self.formats.iter().fold(
+            self.cell_delegate.cell_style(cell, data),
+            |style, format| CellStyle::merge_opt(style, format.style(data)),
+        )
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        self.cell_delegate.event(ctx, cell, event, data, env)
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.cell_delegate.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        self.cell_delegate.spills(cell, data)
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        self.cell_delegate.is_blank(cell, data)
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        self.tooltip
+            .as_ref()
+            .and_then(|tooltip| tooltip(data))
+            .or_else(|| self.validate(cell, data).err())
+            .or_else(|| self.cell_delegate.cell_tooltip(text, size, cell, data, env))
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        self.cell_delegate.cell_text(cell, data)
+    }
 }
 
 impl<T: Data, CR: CellDelegate<T>> DataCompare<T> for TableColumn<T, CR> {
@@ -521,13 +1511,43 @@ impl<T: Data, CR: CellDelegate<T>> EditorFactory<T> for TableColumn<T, CR> {
     fn make_editor(&mut self, ctx: &CellCtx) -> Option<Box<dyn Widget<T>>> {
         self.cell_delegate.make_editor(ctx)
     }
+
+    fn can_commit(&self, ctx: &CellCtx, original: &T, edited: &T) -> CommitResult {
+        self.cell_delegate.can_commit(ctx, original, edited)?;
+        match &self.commit_check {
+            Some(check) => check(original, edited),
+            None => Ok(()),
+        }
+    }
+
+    fn validate(&self, ctx: &CellCtx, data: &T) -> CommitResult {
+        self.cell_delegate.validate(ctx, data)?;
+        match &self.validator {
+            Some(validator) => validator(data),
+            None => Ok(()),
+        }
+    }
+
+    fn paste_text(&self, ctx: &CellCtx, data: &mut T, text: &str) -> CommitResult {
+        match &self.paste {
+            Some(paste) => paste(data, text),
+            None => self.cell_delegate.paste_text(ctx, data, text),
+        }
+    }
+
+    fn fill_series(&self, ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        self.cell_delegate.fill_series(ctx, source, count)
+    }
 }
 
+pub(crate) type RowStyleFn<RowData> = Box<dyn Fn(&RowData) -> Option<CellStyle>>;
+
 pub struct ProvidedColumns<TableData: IndexedData, ColumnType: CellDelegate<TableData::Item>>
 where
     TableData::Item: Data,
 {
     cols: Vec<TableColumn<TableData::Item, ColumnType>>,
+    row_style: Option<RowStyleFn<TableData::Item>>,
     phantom_td: PhantomData<TableData>,
 }
 
@@ -539,9 +1559,18 @@ where
     pub fn new(cols: Vec<TableColumn<TableData::Item, ColumnType>>) -> Self {
         ProvidedColumns {
             cols,
+            row_style: None,
             phantom_td: Default::default(),
         }
     }
+
+    pub fn row_style(
+        mut self,
+        row_style: impl Fn(&TableData::Item) -> Option<CellStyle> + 'static,
+    ) -> Self {
+        self.row_style = Some(Box::new(row_style));
+        self
+    }
 }
 
 impl<TableData: IndexedData<Idx = LogIdx>, ColumnType: CellDelegate<TableData::Item>>
@@ -574,6 +1603,11 @@ where
         {
             spec.add_sort(SortSpec::new(idx, dir.clone()))
         }
+        spec.sort_fixed.extend(
+            (0..self.cols.len())
+                .filter(|idx| self.sort_fixed(*idx))
+                .map(LogIdx),
+        );
         spec
     }
 
@@ -581,8 +1615,17 @@ where
         if remap_spec.is_empty() {
             Remap::new() // Todo: preserve moves
         } else {
-            //Todo: Filter
-            let mut idxs: Vector<LogIdx> = (0usize..table_data.idx_len()).map(LogIdx).collect(); //TODO Give up if too big?
+            let mut idxs: Vector<LogIdx> = (0usize..table_data.idx_len())
+                .map(LogIdx)
+                .filter(|idx| !remap_spec.is_hidden(idx))
+                .filter(|idx| {
+                    table_data
+                        .with(*idx, |row| {
+                            passes_filters(&self.cols, &remap_spec.filters, *idx, row)
+                        })
+                        .unwrap_or(false)
+                })
+                .collect(); //TODO Give up if too big?
             idxs.sort_by(|a, b| {
                 table_data
                     .with(*a, |a| {
@@ -618,6 +1661,64 @@ where
     fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &TableData::Item, env: &Env) {
         self.cols.paint(ctx, cell, data, env);
     }
+
+    fn observes_rows(&self) -> bool {
+        self.cols.observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.cols.start_observe()
+    }
+
+    fn observe(&mut self, data: &TableData::Item) {
+        self.cols.observe(data)
+    }
+
+    fn end_observe(&mut self) {
+        self.cols.end_observe()
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &TableData::Item) -> Option<CellStyle> {
+        self.cols.cell_style(cell, data)
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &TableData::Item,
+        env: &Env,
+    ) -> bool {
+        self.cols.event(ctx, cell, event, data, env)
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.cols.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &TableData::Item) -> bool {
+        self.cols.spills(cell, data)
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &TableData::Item) -> bool {
+        self.cols.is_blank(cell, data)
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &TableData::Item,
+        env: &Env,
+    ) -> Option<String> {
+        self.cols.cell_tooltip(text, size, cell, data, env)
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &TableData::Item) -> Option<String> {
+        self.cols.cell_text(cell, data)
+    }
 }
 
 impl<TableData: IndexedData<Idx = LogIdx>, ColumnType: CellDelegate<TableData::Item>>
@@ -631,6 +1732,27 @@ where
     ) -> Option<Box<dyn Widget<<TableData as IndexedItems>::Item>>> {
         self.cols.make_editor(ctx)
     }
+
+    fn can_commit(
+        &self,
+        ctx: &CellCtx,
+        original: &TableData::Item,
+        edited: &TableData::Item,
+    ) -> CommitResult {
+        self.cols.can_commit(ctx, original, edited)
+    }
+
+    fn validate(&self, ctx: &CellCtx, data: &TableData::Item) -> CommitResult {
+        self.cols.validate(ctx, data)
+    }
+
+    fn paste_text(&self, ctx: &CellCtx, data: &mut TableData::Item, text: &str) -> CommitResult {
+        self.cols.paste_text(ctx, data, text)
+    }
+
+    fn fill_series(&self, ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        self.cols.fill_series(ctx, source, count)
+    }
 }
 
 impl<TableData: IndexedData<Idx = LogIdx>, ColumnType: CellDelegate<TableData::Item>>
@@ -641,4 +1763,94 @@ where
     fn number_of_columns_in_data(&self, _data: &TableData) -> usize {
         self.cols.len()
     }
+
+    fn row_style(&self, data: &TableData::Item) -> Option<CellStyle> {
+        self.row_style.as_ref().and_then(|f| f(data))
+    }
+
+    fn column_header(&self, col: LogIdx) -> Option<String> {
+        self.cols.get(col.0).map(|tc| tc.header.clone())
+    }
+
+    fn merges_cells(&self) -> bool {
+        self.cols
+            .iter()
+            .any(|tc| tc.merge_repeats || tc.column_span.is_some())
+    }
+
+    fn merges_repeats(&self, col: LogIdx) -> bool {
+        self.cols.get(col.0).map_or(false, |tc| tc.merge_repeats)
+    }
+
+    fn column_span(&self, col: LogIdx, data: &TableData::Item) -> usize {
+        self.cols
+            .get(col.0)
+            .and_then(|tc| tc.column_span.as_ref())
+            .map_or(1, |span| span(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(align: HorizontalAlignment, overflow: TextOverflow) -> TextOptions {
+        TextOptions {
+            align,
+            vertical_align: VerticalAlignment::Middle,
+            overflow,
+        }
+    }
+
+    #[test]
+    fn text_origin_follows_alignment() {
+        let bounds = Size::new(100., 20.);
+        let size = Size::new(40., 10.);
+        let origin =
+            |align| options(align, TextOverflow::Clip).text_origin(bounds, size, 0., 0., false);
+        assert_eq!(origin(HorizontalAlignment::Left), Point::new(0., 5.));
+        assert_eq!(origin(HorizontalAlignment::Center), Point::new(30., 5.));
+        assert_eq!(origin(HorizontalAlignment::Right), Point::new(60., 5.));
+
+        // The point 30 into the text sits 15 from the right, where the longest fraction needs it
+        let decimal = options(HorizontalAlignment::Decimal, TextOverflow::Clip);
+        assert_eq!(
+            decimal.text_origin(bounds, size, 30., 15., false),
+            Point::new(55., 5.)
+        );
+        assert_eq!(
+            decimal.text_origin(bounds, size, 30., 15., true),
+            Point::new(0., 5.)
+        );
+    }
+
+    #[test]
+    fn only_spilling_text_starts_outside_the_cell() {
+        let bounds = Size::new(50., 20.);
+        let size = Size::new(80., 20.);
+        let right = options(HorizontalAlignment::Right, TextOverflow::Clip);
+        assert_eq!(right.text_origin(bounds, size, 0., 0., false).x, 0.);
+        let right = options(HorizontalAlignment::Right, TextOverflow::Spill);
+        assert_eq!(right.text_origin(bounds, size, 0., 0., false).x, -30.);
+    }
+
+    #[test]
+    fn ellipsis_keeps_what_fits() {
+        assert_eq!(ellipsized("Hello world", 6), "Hello\u{2026}");
+        assert_eq!(ellipsized("h\u{e9}llo", 2), "h\u{e9}\u{2026}");
+        assert_eq!(ellipsized("abc", 10), "abc\u{2026}");
+
+        assert_eq!(most_that_fits(10, |n| n <= 4), 4);
+        assert_eq!(most_that_fits(10, |_| true), 10);
+        assert_eq!(most_that_fits(10, |n| n == 0), 0);
+    }
+
+    #[test]
+    fn text_spills_unless_blank() {
+        let cell = TextCell::new().overflow(TextOverflow::Spill);
+        assert!(cell.spills(&CellCtx::Absent, &"long text".to_string()));
+        assert!(!cell.spills(&CellCtx::Absent, &String::new()));
+        assert!(cell.is_blank(&CellCtx::Absent, &String::new()));
+        assert!(!TextCell::new().spills(&CellCtx::Absent, &"long text".to_string()));
+    }
 }
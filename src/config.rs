@@ -16,6 +16,12 @@ pub struct TableConfig {
     pub cell_padding: KeyOrValue<f64>,
     pub selection_color: KeyOrValue<Color>,
     pub focus_color: KeyOrValue<Color>,
+    // Whether Enter starts editing the focused cell, rather than moving down
+    pub enter_edits: bool,
+    // Whether copied cells start with a row of column headers
+    pub copy_headers: bool,
+    // Whether column headers have a button to filter the rows by the column's values
+    pub filters: bool,
 }
 
 pub struct ResolvedTableConfig {
@@ -29,6 +35,8 @@ pub struct ResolvedTableConfig {
     pub(crate) cell_padding: f64,
     pub(crate) selection_color: Color,
     pub(crate) focus_color: Color,
+    pub(crate) enter_edits: bool,
+    pub(crate) copy_headers: bool,
 }
 
 impl ResolvedTableConfig {
@@ -59,6 +67,9 @@ impl TableConfig {
             cell_padding: 2.0.into(),
             selection_color: Color::rgb8(0xB0, 0xEE, 0xFF).into(),
             focus_color: Color::rgb8(0x4D, 0x58, 0xD8).into(),
+            enter_edits: false,
+            copy_headers: false,
+            filters: false,
         }
     }
 
@@ -74,6 +85,8 @@ impl TableConfig {
             cell_padding: self.cell_padding.resolve(env),
             selection_color: self.selection_color.resolve(env),
             focus_color: self.focus_color.resolve(env),
+            enter_edits: self.enter_edits,
+            copy_headers: self.copy_headers,
         }
     }
 }
@@ -1,6 +1,7 @@
 use crate::axis_measure::{LogIdx, VisIdx, VisOffset};
 use crate::data::SortDirection::Descending;
 use druid::im::HashMap;
+use druid::im::HashSet;
 use druid::im::Vector;
 use druid::Data;
 use std::cmp::Ordering;
@@ -99,6 +100,15 @@ impl Remap {
         }
     }
 
+    // How many of the len items are shown
+    pub fn vis_len(&self, len: usize) -> usize {
+        if let Remap::Selected(RemapDetails::Full(v)) = self {
+            v.len()
+        } else {
+            len
+        }
+    }
+
     pub fn max_vis_idx(&self, len: usize) -> VisIdx {
         if let Remap::Selected(RemapDetails::Full(v)) = self {
             VisIdx(v.len()) + VisOffset(-1)
@@ -123,6 +133,14 @@ impl Remap {
             _ => Some(LogIdx(vis_idx.0)), // Dunno if right for internal
         }
     }
+
+    // Where an item is shown, if it is
+    pub fn get_vis_idx(&self, log_idx: LogIdx) -> Option<VisIdx> {
+        match self {
+            Remap::Selected(RemapDetails::Full(v)) => v.index_of(&log_idx).map(VisIdx),
+            _ => Some(VisIdx(log_idx.0)),
+        }
+    }
 }
 
 impl Default for Remap {
@@ -163,11 +181,23 @@ impl SortSpec {
     }
 }
 
+// Which side of the table a column is pinned to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Data)]
+pub enum Pin {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Debug, Data)]
 pub struct RemapSpec {
     pub(crate) sort_by: Vector<SortSpec>, // columns sorted
-    // filters
+    pub(crate) filters: HashMap<LogIdx, HashSet<String>>, // Values hidden in each column
     pub(crate) placements: im::HashMap<LogIdx, (VisIdx, usize)>, // Explicit moves
+    pub(crate) hidden: HashSet<LogIdx>,                          // Hidden until shown again
+    pub(crate) folded: HashSet<LogIdx>,                          // In collapsed groups
+    pub(crate) collapsed: HashSet<usize>,                        // Ids of collapsed column groups
+    pub(crate) sort_fixed: HashSet<LogIdx>, // Columns whose sort can't be changed
+    pub(crate) pinned: Vector<(LogIdx, Pin)>, // In the order they were pinned
 }
 
 impl RemapSpec {
@@ -176,10 +206,68 @@ impl RemapSpec {
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.sort_by.is_empty()
+        self.sort_by.is_empty() && self.filters.is_empty() && !self.hides_any()
+    }
+
+    fn hides_any(&self) -> bool {
+        !self.hidden.is_empty() || !self.folded.is_empty()
+    }
+
+    pub(crate) fn is_hidden(&self, log_idx: &LogIdx) -> bool {
+        self.hidden.contains(log_idx) || self.folded.contains(log_idx)
+    }
+
+    // Hides the rows with any of these values in the column. No values clears its filter.
+    pub(crate) fn set_filter(&mut self, col: LogIdx, hidden: HashSet<String>) {
+        if hidden.is_empty() {
+            self.filters.remove(&col);
+        } else {
+            self.filters.insert(col, hidden);
+        }
+    }
+
+    pub(crate) fn clear_filters(&mut self) {
+        self.filters.clear()
+    }
+
+    // Where the column comes in the sort, from 0, and which way it is sorted
+    pub fn sort_of(&self, col: LogIdx) -> Option<(usize, SortDirection)> {
+        self.sort_by
+            .iter()
+            .enumerate()
+            .find(|(_, s)| s.idx == col.0)
+            .map(|(priority, s)| (priority, s.direction))
+    }
+
+    pub fn sorted_columns(&self) -> usize {
+        self.sort_by.len()
+    }
+
+    pub fn is_sort_fixed(&self, col: LogIdx) -> bool {
+        self.sort_fixed.contains(&col)
+    }
+
+    // Sorts by just this column after any fixed sorts, or with no direction stops sorting by it
+    pub(crate) fn set_sort(&mut self, log_idx: LogIdx, direction: Option<SortDirection>) -> bool {
+        if self.sort_fixed.contains(&log_idx) {
+            return false;
+        }
+        match direction {
+            Some(direction) => {
+                let sort_fixed = &self.sort_fixed;
+                self.sort_by.retain(|s| sort_fixed.contains(&LogIdx(s.idx)));
+                self.add_sort(SortSpec::new(log_idx.0, direction));
+            }
+            None => self.sort_by.retain(|s| s.idx != log_idx.0),
+        }
+        true
     }
 
     pub(crate) fn toggle_sort(&mut self, log_idx: LogIdx, extend: bool) -> bool {
+        if self.sort_fixed.contains(&log_idx) {
+            return false;
+        }
+        let sort_fixed = &self.sort_fixed;
         let sort_by = &mut self.sort_by;
         let log_idx = log_idx.0;
 
@@ -192,13 +280,13 @@ impl RemapSpec {
                 }
             }
             _ => {
+                // Fixed sorts stay ahead of the others
                 if !extend {
-                    sort_by.clear();
+                    sort_by.retain(|s| sort_fixed.contains(&LogIdx(s.idx)));
                 }
                 sort_by.push_back(SortSpec::new(log_idx, SortDirection::Ascending));
             }
         }
-        // Handle sorting disabled for a column
         true
     }
 
@@ -208,21 +296,63 @@ impl RemapSpec {
         log::info!("Placing {:?} at {:?}", log_idx, vis_idx)
     }
 
+    pub fn pinned(&self, log_idx: LogIdx) -> Option<Pin> {
+        self.pinned
+            .iter()
+            .find(|(li, _)| *li == log_idx)
+            .map(|(_, pin)| *pin)
+    }
+
+    // Pinned columns show at that side, after any pinned before them. No pin unpins it.
+    pub(crate) fn pin(&mut self, log_idx: LogIdx, pin: Option<Pin>) {
+        self.pinned.retain(|(li, _)| *li != log_idx);
+        if let Some(pin) = pin {
+            self.placements.remove(&log_idx);
+            self.pinned.push_back((log_idx, pin));
+        }
+    }
+
+    // The pinned columns shown at that side
+    pub(crate) fn pinned_count(&self, pin: Pin) -> usize {
+        self.pinned_to(pin).count()
+    }
+
+    fn pinned_to(&self, pin: Pin) -> impl Iterator<Item = LogIdx> + '_ {
+        self.pinned
+            .iter()
+            .filter(move |(li, p)| *p == pin && !self.is_hidden(li))
+            .map(|(li, _)| *li)
+    }
+
+    // Puts back the order and visibility of everything, leaving sorts and filters.
+    // Collapsed groups are opened again too, as their columns would stay hidden otherwise.
+    pub(crate) fn reset_layout(&mut self) {
+        self.placements.clear();
+        self.hidden.clear();
+        self.pinned.clear();
+        self.folded.clear();
+        self.collapsed.clear();
+    }
+
     pub(crate) fn remap_placements(&self, max_log_idx: LogIdx) -> Remap {
-        if self.placements.is_empty() {
+        if self.placements.is_empty() && !self.hides_any() && self.pinned.is_empty() {
             Remap::new()
         } else {
-            let mut all: Vector<LogIdx> = Vector::new();
+            let mut all: Vector<LogIdx> = self.pinned_to(Pin::Left).collect();
+            let left = all.len();
+            let moves = |li: &LogIdx| !self.is_hidden(li) && self.pinned(*li).is_none();
             let unplaced_log: Vector<LogIdx> = (0..=max_log_idx.0)
-                .filter(|li| !self.placements.contains_key(&LogIdx(*li)))
                 .map(LogIdx)
+                .filter(|li| !self.placements.contains_key(li) && moves(li))
                 .collect();
-            let mut s_placements: Vec<_> = self.placements.iter().collect();
+            let mut s_placements: Vec<_> =
+                self.placements.iter().filter(|(li, _)| moves(li)).collect();
             s_placements.sort_by_key(|(_, (_, o))| Reverse(*o));
             let mut placed_by_vis: HashMap<VisIdx, LogIdx> = HashMap::new();
 
             for (log, (vis, _)) in s_placements {
-                let mut v_a = *vis;
+                // Placed among the unpinned columns
+                let mut v_a = VisIdx(vis.0.saturating_sub(left));
                 while placed_by_vis.contains_key(&v_a) {
                     v_a = v_a + VisOffset(1)
                 }
@@ -230,7 +360,7 @@ impl RemapSpec {
             }
 
             for log in unplaced_log {
-                while let Some(place) = placed_by_vis.remove(&VisIdx(all.len())) {
+                while let Some(place) = placed_by_vis.remove(&VisIdx(all.len() - left)) {
                     all.push_back(place);
                 }
                 all.push_back(log);
@@ -238,6 +368,7 @@ impl RemapSpec {
             for place in placed_by_vis.values() {
                 all.push_back(*place)
             }
+            all.extend(self.pinned_to(Pin::Right));
             Remap::Selected(RemapDetails::Full(all))
         }
     }
@@ -247,7 +378,13 @@ impl Default for RemapSpec {
     fn default() -> Self {
         RemapSpec {
             sort_by: Vector::default(),
+            filters: HashMap::default(),
             placements: HashMap::default(),
+            hidden: HashSet::default(),
+            folded: HashSet::default(),
+            collapsed: HashSet::default(),
+            sort_fixed: HashSet::default(),
+            pinned: Vector::default(),
         }
     }
 }
@@ -261,3 +398,77 @@ where
     fn initial_spec(&self) -> RemapSpec;
     fn remap_items(&self, table_data: &TableData, remap_spec: &RemapSpec) -> Remap;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn toggle_sort_keeps_fixed_sorts() {
+        let mut spec = RemapSpec::default();
+        spec.add_sort(SortSpec::new(2, SortDirection::Descending));
+        spec.sort_fixed.insert(LogIdx(2));
+
+        assert!(spec.toggle_sort(LogIdx(0), false));
+        assert!(spec.toggle_sort(LogIdx(1), true));
+        assert_eq!(spec.sort_of(LogIdx(1)), Some((2, SortDirection::Ascending)));
+        assert!(spec.toggle_sort(LogIdx(1), true));
+        assert_eq!(
+            spec.sort_of(LogIdx(1)),
+            Some((2, SortDirection::Descending))
+        );
+
+        assert!(spec.toggle_sort(LogIdx(3), false));
+        assert_eq!(spec.sorted_columns(), 2);
+        assert_eq!(
+            spec.sort_of(LogIdx(2)),
+            Some((0, SortDirection::Descending))
+        );
+        assert_eq!(spec.sort_of(LogIdx(3)), Some((1, SortDirection::Ascending)));
+
+        assert!(!spec.toggle_sort(LogIdx(2), false));
+        assert_eq!(
+            spec.sort_of(LogIdx(2)),
+            Some((0, SortDirection::Descending))
+        );
+
+        assert!(spec.set_sort(LogIdx(1), Some(SortDirection::Descending)));
+        assert_eq!(spec.sorted_columns(), 2);
+        assert!(!spec.set_sort(LogIdx(2), None));
+        assert!(spec.set_sort(LogIdx(1), None));
+        assert_eq!(spec.sorted_columns(), 1);
+    }
+
+    #[test]
+    fn pinned_columns_go_to_the_sides() {
+        let mut spec = RemapSpec::default();
+        spec.pin(LogIdx(3), Some(Pin::Left));
+        spec.pin(LogIdx(0), Some(Pin::Right));
+        spec.pin(LogIdx(1), Some(Pin::Left));
+        spec.place(LogIdx(4), VisIdx(2));
+        let remap = spec.remap_placements(LogIdx(4));
+        let order: Vec<_> = VisIdx::range_inc_iter(VisIdx(0), VisIdx(4))
+            .filter_map(|vis| remap.get_log_idx(vis))
+            .map(|log| log.0)
+            .collect();
+        assert_eq!(order, vec![3, 1, 4, 2, 0]);
+
+        spec.pin(LogIdx(3), None);
+        assert_eq!(spec.pinned(LogIdx(3)), None);
+        spec.reset_layout();
+        assert!(spec.remap_placements(LogIdx(4)).is_pristine());
+    }
+
+    #[test]
+    fn reset_layout_opens_collapsed_groups() {
+        let mut spec = RemapSpec::default();
+        spec.collapsed.insert(0);
+        spec.folded.extend(vec![LogIdx(1), LogIdx(2)]);
+        assert!(!spec.remap_placements(LogIdx(3)).is_pristine());
+
+        spec.reset_layout();
+        assert!(spec.collapsed.is_empty());
+        assert!(spec.folded.is_empty());
+        assert!(spec.remap_placements(LogIdx(3)).is_pristine());
+    }
+}
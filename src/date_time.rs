@@ -0,0 +1,264 @@
+use crate::columns::{
+    CellCtx, CellRender, CommitResult, DataCompare, EditorFactory, TextCell, TextOptions,
+};
+use crate::editors::ParseEditor;
+use chrono::{NaiveDate, NaiveDateTime};
+use druid::piet::PietText;
+use druid::{Data, Env, PaintCtx, Size, Widget};
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// chrono types don't implement Data, so rows hold this instead
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTimeValue(pub NaiveDateTime);
+
+impl Data for DateTimeValue {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl From<NaiveDateTime> for DateTimeValue {
+    fn from(dt: NaiveDateTime) -> Self {
+        DateTimeValue(dt)
+    }
+}
+
+impl From<NaiveDate> for DateTimeValue {
+    fn from(d: NaiveDate) -> Self {
+        DateTimeValue(d.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+    }
+}
+
+// Tried in order after the display format when parsing typed input
+const FALLBACK_DATE_TIME_FORMATS: &[&str] =
+    &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"];
+const FALLBACK_DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d/%m/%Y"];
+
+#[derive(Clone)]
+struct DateTimeFormat {
+    format: Rc<str>,
+}
+
+impl DateTimeFormat {
+    fn format(&self, value: &DateTimeValue) -> String {
+        value.0.format(&self.format).to_string()
+    }
+
+    fn parse(&self, input: &str) -> Result<DateTimeValue, String> {
+        let input = input.trim();
+        let with_time =
+            std::iter::once(&*self.format).chain(FALLBACK_DATE_TIME_FORMATS.iter().copied());
+        for fmt in with_time {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(input, fmt) {
+                return Ok(DateTimeValue(dt));
+            }
+        }
+        // A format without a time part only parses as a date
+        let date_only = std::iter::once(&*self.format).chain(FALLBACK_DATE_FORMATS.iter().copied());
+        for fmt in date_only {
+            if let Ok(d) = NaiveDate::parse_from_str(input, fmt) {
+                return Ok(d.into());
+            }
+        }
+        Err(format!(
+            "Could not parse '{}' as a date ({})",
+            input, self.format
+        ))
+    }
+}
+
+#[derive(Clone)]
+pub struct DateTimeCell {
+    format: DateTimeFormat,
+    text: TextCell,
+}
+
+impl DateTimeCell {
+    // The format uses chrono strftime syntax, eg "%Y-%m-%d %H:%M"
+    pub fn new(format: impl AsRef<str>) -> Self {
+        DateTimeCell {
+            format: DateTimeFormat {
+                format: format.as_ref().into(),
+            },
+            text: TextCell::new(),
+        }
+    }
+
+    pub fn text_cell(mut self, text: TextCell) -> Self {
+        self.text = text;
+        self
+    }
+
+    pub fn format(&self, value: &DateTimeValue) -> String {
+        self.format.format(value)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<DateTimeValue, String> {
+        self.format.parse(input)
+    }
+}
+
+impl Default for DateTimeCell {
+    fn default() -> Self {
+        DateTimeCell::new("%Y-%m-%d %H:%M")
+    }
+}
+
+impl CellRender<DateTimeValue> for DateTimeCell {
+    fn init(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.text.init(ctx, env)
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &DateTimeValue, env: &Env) {
+        self.text.paint(ctx, cell, &self.format(data), env)
+    }
+
+    // Decimal alignment looks at the formatted dates in the column
+    fn observes_rows(&self) -> bool {
+        self.text.observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.text.start_observe()
+    }
+
+    fn observe(&mut self, data: &DateTimeValue) {
+        let text = self.format(data);
+        self.text.observe(&text)
+    }
+
+    fn end_observe(&mut self) {
+        self.text.end_observe()
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.text.update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &DateTimeValue) -> bool {
+        self.text.spills(cell, &self.format(data))
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &DateTimeValue) -> bool {
+        self.text.is_blank(cell, &self.format(data))
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &DateTimeValue,
+        env: &Env,
+    ) -> Option<String> {
+        self.text
+            .cell_tooltip(text, size, cell, &self.format(data), env)
+    }
+
+    fn cell_text(&self, _cell: &CellCtx, data: &DateTimeValue) -> Option<String> {
+        Some(self.format(data))
+    }
+}
+
+impl DataCompare<DateTimeValue> for DateTimeCell {
+    fn compare(&self, a: &DateTimeValue, b: &DateTimeValue) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl EditorFactory<DateTimeValue> for DateTimeCell {
+    fn make_editor(&mut self, _ctx: &CellCtx) -> Option<Box<dyn Widget<DateTimeValue>>> {
+        let format = self.format.clone();
+        let parse = self.format.clone();
+        let editor = ParseEditor::new(move |v| format.format(v), move |s| parse.parse(s));
+        Some(Box::new(editor))
+    }
+
+    fn paste_text(&self, _ctx: &CellCtx, data: &mut DateTimeValue, text: &str) -> CommitResult {
+        *data = self.parse(text)?;
+        Ok(())
+    }
+
+    // Evenly spaced dates continue with the same gap
+    fn fill_series(&self, _ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        if source.len() < 2 {
+            return None;
+        }
+        let values = source
+            .iter()
+            .map(|text| self.parse(text).ok())
+            .collect::<Option<Vec<_>>>()?;
+        let last = values[values.len() - 1].0;
+        let step = (last - values[0].0) / (values.len() - 1) as i32;
+        Some(
+            (1..=count)
+                .map(|i| self.format(&DateTimeValue(last + step * i as i32)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::{HorizontalAlignment, TextOverflow};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn date_time(year: i32, month: u32, day: u32, hour: u32, min: u32) -> DateTimeValue {
+        date(year, month, day)
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn parse_round_trips_display_format() {
+        let cell = DateTimeCell::new("%d %b %Y %H:%M");
+        let value = date_time(2020, 10, 3, 14, 5);
+        let text = cell.format(&value);
+        assert_eq!(text, "03 Oct 2020 14:05");
+        assert_eq!(cell.parse(&text), Ok(value));
+    }
+
+    #[test]
+    fn parse_falls_back_to_iso_and_dates() {
+        let cell = DateTimeCell::new("%d %b %Y %H:%M");
+        assert_eq!(
+            cell.parse("2020-10-03 14:05:00"),
+            Ok(date_time(2020, 10, 3, 14, 5))
+        );
+        assert_eq!(cell.parse(" 2020-10-03 "), Ok(date(2020, 10, 3).into()));
+        assert!(cell.parse("yesterday").is_err());
+    }
+
+    #[test]
+    fn fill_series_keeps_the_gap() {
+        let cell = DateTimeCell::new("%Y-%m-%d");
+        let source = vec!["2020-12-30".to_string(), "2020-12-31".to_string()];
+        assert_eq!(
+            cell.fill_series(&CellCtx::Absent, &source, 2),
+            Some(vec!["2021-01-01".to_string(), "2021-01-02".to_string()])
+        );
+        assert_eq!(cell.fill_series(&CellCtx::Absent, &source[..1], 2), None);
+    }
+
+    #[test]
+    fn text_options_reach_the_inner_text() {
+        let mut cell = DateTimeCell::new("%Y-%m-%d");
+        let value: DateTimeValue = date(2020, 10, 3).into();
+        assert!(!cell.observes_rows());
+        assert!(!cell.spills(&CellCtx::Absent, &value));
+        assert!(!cell.is_blank(&CellCtx::Absent, &value));
+
+        cell.update_text_options(&|options| {
+            options.align = HorizontalAlignment::Decimal;
+            options.overflow = TextOverflow::Spill;
+        });
+        assert!(cell.observes_rows());
+        assert!(cell.spills(&CellCtx::Absent, &value));
+    }
+}
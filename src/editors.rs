@@ -0,0 +1,205 @@
+use crate::cells::{EditStart, START_EDIT};
+use druid::text::Selection;
+use druid::widget::prelude::*;
+use druid::widget::TextBox;
+use druid::{Color, Data, Point, Selector, WidgetPod};
+
+pub(crate) const EDITOR_ERROR_COLOR: Color = Color::rgb8(0xD0, 0x20, 0x20);
+
+// Sent up by an editor when its text stops or starts parsing, with why it doesn't.
+// The table keeps it with the edit, showing it and refusing to commit while there is one.
+pub const EDITOR_ERROR: Selector<Option<String>> = Selector::new("druid-table.editor-error");
+
+type TextBoxPod = WidgetPod<String, TextBox<String>>;
+
+// When the table opens an editor from the keyboard, the text box takes focus so typing goes on
+// in it. Returns how the edit was started.
+fn start_text_edit<'a>(
+    ctx: &mut EventCtx,
+    event: &'a Event,
+    text_box: &TextBoxPod,
+    caret_to_end: &mut bool,
+) -> Option<&'a EditStart> {
+    match event {
+        Event::Command(cmd) => {
+            let start = cmd.get(START_EDIT)?;
+            *caret_to_end = true;
+            ctx.set_focus(text_box.id());
+            ctx.set_handled();
+            Some(start)
+        }
+        _ => None,
+    }
+}
+
+// Text boxes select all their text when they take focus from the keyboard, which would make
+// the next key replace what was typed to start the edit
+fn place_caret(text_box: &mut TextBoxPod, text: &str, caret_to_end: &mut bool) {
+    if *caret_to_end && text_box.has_focus() {
+        *caret_to_end = false;
+        let caret = Selection::caret(text.len());
+        text_box
+            .widget_mut()
+            .text_mut()
+            .borrow_mut()
+            .set_selection(caret);
+    }
+}
+
+// The editor for text cells
+pub struct TextEditor {
+    text_box: TextBoxPod,
+    caret_to_end: bool,
+}
+
+impl TextEditor {
+    pub fn new() -> Self {
+        TextEditor {
+            text_box: WidgetPod::new(TextBox::new()),
+            caret_to_end: false,
+        }
+    }
+}
+
+impl Default for TextEditor {
+    fn default() -> Self {
+        TextEditor::new()
+    }
+}
+
+impl Widget<String> for TextEditor {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut String, env: &Env) {
+        let start = start_text_edit(ctx, event, &self.text_box, &mut self.caret_to_end);
+        if let Some(EditStart::Typed(typed)) = start {
+            *data = typed.clone();
+        } else if start.is_none() {
+            self.text_box.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &String, env: &Env) {
+        self.text_box.lifecycle(ctx, event, data, env);
+        place_caret(&mut self.text_box, data, &mut self.caret_to_end);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &String, data: &String, env: &Env) {
+        self.text_box.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &String,
+        env: &Env,
+    ) -> Size {
+        let size = self.text_box.layout(ctx, bc, data, env);
+        self.text_box.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &String, env: &Env) {
+        self.text_box.paint(ctx, data, env);
+    }
+}
+
+// An editor that edits a text buffer, and only writes back to the data when the text parses.
+// While the text is invalid a red border is drawn, the data keeps its last good value, and the
+// table is told why with EDITOR_ERROR.
+pub struct ParseEditor<T> {
+    text_box: TextBoxPod,
+    caret_to_end: bool,
+    buffer: String,
+    format: Box<dyn Fn(&T) -> String>,
+    parse: Box<dyn Fn(&str) -> Result<T, String>>,
+    error: Option<String>,
+}
+
+impl<T: Data> ParseEditor<T> {
+    pub fn new(
+        format: impl Fn(&T) -> String + 'static,
+        parse: impl Fn(&str) -> Result<T, String> + 'static,
+    ) -> Self {
+        ParseEditor {
+            text_box: WidgetPod::new(TextBox::new()),
+            caret_to_end: false,
+            buffer: String::new(),
+            format: Box::new(format),
+            parse: Box::new(parse),
+            error: None,
+        }
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    fn has_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    fn reparse(&mut self, data: &mut T) {
+        match (self.parse)(&self.buffer) {
+            Ok(value) => {
+                self.error = None;
+                if !value.same(data) {
+                    *data = value;
+                }
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for ParseEditor<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let old_buffer = self.buffer.clone();
+        match start_text_edit(ctx, event, &self.text_box, &mut self.caret_to_end) {
+            Some(EditStart::Typed(typed)) => self.buffer = typed.clone(),
+            Some(EditStart::CaretAtEnd) => (),
+            None => self.text_box.event(ctx, event, &mut self.buffer, env),
+        }
+        if old_buffer != self.buffer {
+            let old_error = self.error.clone();
+            self.reparse(data);
+            if old_error != self.error {
+                ctx.submit_notification(EDITOR_ERROR.with(self.error.clone()));
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.buffer = (self.format)(data);
+        }
+        self.text_box.lifecycle(ctx, event, &self.buffer, env);
+        place_caret(&mut self.text_box, &self.buffer, &mut self.caret_to_end);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        // Don't clobber text the user is still fixing up
+        if !old_data.same(data) && !self.has_error() {
+            let current = (self.parse)(&self.buffer);
+            if current.map_or(true, |current| !current.same(data)) {
+                self.buffer = (self.format)(data);
+            }
+        }
+        self.text_box.update(ctx, &self.buffer, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        let size = self.text_box.layout(ctx, bc, &self.buffer, env);
+        self.text_box
+            .set_origin(ctx, &self.buffer, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        self.text_box.paint(ctx, &self.buffer, env);
+        if self.has_error() {
+            let rect = ctx.size().to_rect().inset(-1.);
+            ctx.stroke(rect, &EDITOR_ERROR_COLOR, 2.);
+        }
+    }
+}
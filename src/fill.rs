@@ -0,0 +1,251 @@
+use crate::axis_measure::{AxisPair, LogIdx, TableAxis, VisIdx, VisOffset};
+use crate::cells::CellsDelegate;
+use crate::clipboard::PasteReport;
+use crate::columns::CellCtx;
+use crate::data::{IndexedData, Remap};
+use crate::selection::{CellDemap, CellRect, SingleCell};
+use druid::Data;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillMode {
+    // Repeat the source cells
+    Copy,
+    // Continue a series where the column recognises one, otherwise repeat
+    Series,
+}
+
+// Continues evenly spaced numbers, to as many decimal places as the most precise of them.
+// A single number is not a series.
+pub fn numeric_series(source: &[String], count: usize) -> Option<Vec<String>> {
+    if source.len() < 2 {
+        return None;
+    }
+    let values = source
+        .iter()
+        .map(|text| text.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+        .collect::<Option<Vec<f64>>>()?;
+    let last = values[values.len() - 1];
+    let step = (last - values[0]) / (values.len() - 1) as f64;
+    let decimals = source
+        .iter()
+        .map(|text| decimal_places(text.trim()))
+        .max()
+        .unwrap_or(0);
+    Some(
+        (1..=count)
+            .map(|i| format!("{:.*}", decimals, last + step * i as f64))
+            .collect(),
+    )
+}
+
+fn decimal_places(number: &str) -> usize {
+    number.find('.').map_or(0, |dot| number.len() - dot - 1)
+}
+
+// The cells the fill handle would fill when dragged to the pointer. The source is extended along
+// whichever axis the pointer is further outside it.
+pub fn fill_target(source: &CellRect, pointer: &AxisPair<VisIdx>) -> CellRect {
+    let outside = |axis: TableAxis| {
+        let (start, end) = source.range(axis);
+        let at = pointer[axis];
+        if at > end {
+            at.0 - end.0
+        } else if at < start {
+            start.0 - at.0
+        } else {
+            0
+        }
+    };
+    let (rows, cols) = (outside(TableAxis::Rows), outside(TableAxis::Columns));
+    if rows == 0 && cols == 0 {
+        source.clone()
+    } else if rows >= cols {
+        source.union(&CellRect::point(pointer.row, source.start_col))
+    } else {
+        source.union(&CellRect::point(source.start_row, pointer.col))
+    }
+}
+
+// The axis the target extends the source along, and whether it extends forwards
+fn fill_direction(source: &CellRect, target: &CellRect) -> Option<(TableAxis, bool)> {
+    for axis in [TableAxis::Rows, TableAxis::Columns].iter().copied() {
+        let (src_start, src_end) = source.range(axis);
+        let (tgt_start, tgt_end) = target.range(axis);
+        if tgt_end > src_end {
+            return Some((axis, true));
+        } else if tgt_start < src_start {
+            return Some((axis, false));
+        }
+    }
+    None
+}
+
+// Fills the cells of the target outside the source from the source cells next to them, each
+// row or column on its own. The text of the source cells is parsed into the new cells by their
+// column, so failures are reported as for a paste. All the rows are written in one go.
+pub fn fill_selection<TableData, CD>(
+    delegate: &CD,
+    data: &mut TableData,
+    remaps: &AxisPair<Remap>,
+    source: &CellRect,
+    target: &CellRect,
+    mode: FillMode,
+) -> PasteReport
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    let mut report = PasteReport::default();
+    let (axis, forwards) = match fill_direction(source, target) {
+        Some(direction) => direction,
+        None => return report,
+    };
+    let (src_start, src_end) = source.range(axis);
+    let (tgt_start, tgt_end) = target.range(axis);
+
+    // Both ordered moving away from the source, so a series continues the right way
+    let mut from: Vec<VisIdx> = VisIdx::range_inc_iter(src_start, src_end).collect();
+    let to: Vec<VisIdx> = if forwards {
+        VisIdx::range_inc_iter(src_end + VisOffset(1), tgt_end).collect()
+    } else {
+        from.reverse();
+        VisIdx::range_inc_iter(tgt_start, src_start + VisOffset(-1))
+            .rev()
+            .collect()
+    };
+
+    let mut rows: BTreeMap<LogIdx, TableData::Item> = BTreeMap::new();
+    let (cross_start, cross_end) = source.range(axis.cross_axis());
+    for cross in VisIdx::range_inc_iter(cross_start, cross_end) {
+        let cell_at = |main: &VisIdx| {
+            let vis = AxisPair::new_for_axis(axis, *main, cross);
+            remaps
+                .get_log_cell(&vis)
+                .map(|log| SingleCell::new(vis, log))
+        };
+        let texts: Option<Vec<String>> = from
+            .iter()
+            .map(|main| {
+                let cell = cell_at(main)?;
+                data.with(cell.log.row, |row| {
+                    delegate.cell_text(&CellCtx::Cell(&cell), row)
+                })
+                .flatten()
+            })
+            .collect();
+        // Columns without a text form can't be filled
+        let texts = match texts {
+            Some(texts) if !texts.is_empty() => texts,
+            _ => continue,
+        };
+        let cells: Vec<SingleCell> = to.iter().filter_map(|main| cell_at(main)).collect();
+        let series = match (mode, cells.first()) {
+            (_, None) => continue,
+            (FillMode::Series, Some(first)) => {
+                delegate.fill_series(&CellCtx::Cell(first), &texts, cells.len())
+            }
+            (FillMode::Copy, _) => None,
+        };
+        let values =
+            series.unwrap_or_else(|| texts.iter().cycle().take(cells.len()).cloned().collect());
+
+        for (cell, text) in cells.iter().zip(values) {
+            let row = match rows.entry(cell.log.row) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => match data.with(cell.log.row, |row| row.clone()) {
+                    Some(row) => entry.insert(row),
+                    None => continue,
+                },
+            };
+            report.write_cell(delegate, cell, row, &text);
+        }
+    }
+
+    for (log_row, row) in rows {
+        if data.with(log_row, |existing| !existing.same(&row)) == Some(true) {
+            data.with_mut(log_row, |existing| *existing = row);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_fixtures::{at, pristine_remaps, word_columns, word_rows};
+
+    fn strings(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn numeric_series_continues_step() {
+        assert_eq!(
+            numeric_series(&strings(&["1", "3"]), 3),
+            Some(strings(&["5", "7", "9"]))
+        );
+        assert_eq!(
+            numeric_series(&strings(&["0.5", "0.25"]), 2),
+            Some(strings(&["0.00", "-0.25"]))
+        );
+        assert_eq!(numeric_series(&strings(&["1"]), 2), None);
+        assert_eq!(numeric_series(&strings(&["1", "two"]), 2), None);
+    }
+
+    #[test]
+    fn target_extends_along_one_axis() {
+        let source = CellRect::new((VisIdx(2), VisIdx(3)), (VisIdx(1), VisIdx(1)));
+        assert_eq!(fill_target(&source, &at(3, 1)), source);
+        assert_eq!(
+            fill_target(&source, &at(6, 2)),
+            CellRect::new((VisIdx(2), VisIdx(6)), (VisIdx(1), VisIdx(1)))
+        );
+        assert_eq!(
+            fill_target(&source, &at(3, 4)),
+            CellRect::new((VisIdx(2), VisIdx(3)), (VisIdx(1), VisIdx(4)))
+        );
+        assert_eq!(
+            fill_target(&source, &at(0, 1)),
+            CellRect::new((VisIdx(0), VisIdx(3)), (VisIdx(1), VisIdx(1)))
+        );
+    }
+
+    #[test]
+    fn fills_series_and_copies() {
+        let columns = word_columns();
+        let mut data = word_rows(&[("a", 0.4), ("b", 0.7), ("", 0.), ("", 0.), ("", 0.)]);
+        let remaps = pristine_remaps();
+        let source = CellRect::new((VisIdx(0), VisIdx(1)), (VisIdx(0), VisIdx(1)));
+        let target = CellRect::new((VisIdx(0), VisIdx(4)), (VisIdx(0), VisIdx(1)));
+
+        let report = fill_selection(
+            &columns,
+            &mut data,
+            &remaps,
+            &source,
+            &target,
+            FillMode::Series,
+        );
+        // The words repeat, and progress past 1 fails validation
+        assert_eq!(report.pasted, 4);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].cell, AxisPair::new(LogIdx(3), LogIdx(1)));
+        assert_eq!(data[2], ("a".into(), 1.));
+        assert_eq!(data[3], ("b".into(), 0.));
+        assert_eq!(data[4], ("a".into(), 0.));
+
+        let report = fill_selection(
+            &columns,
+            &mut data,
+            &remaps,
+            &CellRect::point(VisIdx(4), VisIdx(1)),
+            &CellRect::new((VisIdx(2), VisIdx(4)), (VisIdx(1), VisIdx(1))),
+            FillMode::Copy,
+        );
+        assert_eq!(report.pasted, 2);
+        assert!(data.iter().skip(2).all(|(_, done)| *done == 0.));
+    }
+}
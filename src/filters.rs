@@ -0,0 +1,370 @@
+use crate::axis_measure::{AxisPair, LogIdx};
+use crate::columns::{CellCtx, CellRender};
+use crate::data::IndexedData;
+use druid::im::{HashMap, HashSet};
+use druid::kurbo::BezPath;
+use druid::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use druid::{
+    theme, Color, Data, Env, Event, EventCtx, KbKey, PaintCtx, Point, Rect, RenderContext, Vec2,
+};
+use std::collections::BTreeMap;
+
+const ITEM_PADDING: f64 = 4.;
+const POPUP_WIDTH: f64 = 220.;
+const MAX_SHOWN: usize = 8;
+const CHECK_SIZE: f64 = 12.;
+
+// The text a filter sees for a cell. Filters look at every row, shown or not, so the cell only
+// has its logical position.
+pub(crate) fn filter_text<T>(
+    render: &impl CellRender<T>,
+    row_idx: LogIdx,
+    col: LogIdx,
+    row: &T,
+) -> String {
+    render
+        .cell_text(&CellCtx::Logical(AxisPair::new(row_idx, col)), row)
+        .unwrap_or_default()
+}
+
+// Whether none of the row's values are hidden by the filters
+pub(crate) fn passes_filters<T>(
+    render: &impl CellRender<T>,
+    filters: &HashMap<LogIdx, HashSet<String>>,
+    row_idx: LogIdx,
+    row: &T,
+) -> bool {
+    filters
+        .iter()
+        .all(|(col, hidden)| !hidden.contains(&filter_text(render, row_idx, *col, row)))
+}
+
+// The values in a column with how many rows have each, in order. Rows hidden by the filters on
+// other columns are left out.
+pub fn distinct_values<TableData>(
+    render: &impl CellRender<TableData::Item>,
+    data: &TableData,
+    filters: &HashMap<LogIdx, HashSet<String>>,
+    col: LogIdx,
+) -> Vec<(String, usize)>
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+{
+    let others = filters.without(&col);
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for row_idx in (0..data.idx_len()).map(LogIdx) {
+        data.with(row_idx, |row| {
+            if passes_filters(render, &others, row_idx, row) {
+                *counts
+                    .entry(filter_text(render, row_idx, col, row))
+                    .or_default() += 1;
+            }
+        });
+    }
+    counts.into_iter().collect()
+}
+
+pub(crate) enum FilterAction {
+    // The values to hide in the column, none to clear its filter
+    Apply(HashSet<String>),
+    ClearAll,
+    Close,
+}
+
+// The filter for one column, listing its values to tick the ones to show. Typing narrows the
+// list, and applying while searching hides the values that don't match as well.
+#[derive(Clone)]
+pub(crate) struct FilterPopup {
+    pub(crate) col: LogIdx,
+    values: Vec<(String, usize)>,
+    hidden: HashSet<String>,
+    search: String,
+    first_shown: usize,
+    item_height: f64,
+    rect: Rect,
+}
+
+impl FilterPopup {
+    pub(crate) fn new(col: LogIdx, values: Vec<(String, usize)>, hidden: HashSet<String>) -> Self {
+        FilterPopup {
+            col,
+            values,
+            hidden,
+            search: String::new(),
+            first_shown: 0,
+            item_height: 0.,
+            rect: Rect::ZERO,
+        }
+    }
+
+    fn matches(&self) -> Vec<usize> {
+        let search = self.search.to_lowercase();
+        self.values
+            .iter()
+            .enumerate()
+            .filter(|(_, (value, _))| value.to_lowercase().contains(&search))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn result(&self) -> HashSet<String> {
+        let matches: HashSet<usize> = self.matches().into_iter().collect();
+        self.values
+            .iter()
+            .enumerate()
+            .filter(|(idx, (value, _))| self.hidden.contains(value) || !matches.contains(idx))
+            .map(|(_, (value, _))| value.clone())
+            .collect()
+    }
+
+    // Shows every matching value, unless they all are already
+    fn toggle_all(&mut self, matches: &[usize]) {
+        let all_shown = matches
+            .iter()
+            .all(|idx| !self.hidden.contains(&self.values[*idx].0));
+        for idx in matches {
+            let value = &self.values[*idx].0;
+            if all_shown {
+                self.hidden.insert(value.clone());
+            } else {
+                self.hidden.remove(value);
+            }
+        }
+    }
+
+    fn toggle(&mut self, idx: usize) {
+        let value = &self.values[idx].0;
+        if self.hidden.contains(value) {
+            self.hidden.remove(value);
+        } else {
+            self.hidden.insert(value.clone());
+        }
+    }
+
+    // Places the popup at the origin, with the search box, "Select all", the values and then
+    // the buttons in rows
+    pub(crate) fn layout(&mut self, origin: Point, env: &Env) -> Rect {
+        self.item_height = (env.get(theme::TEXT_SIZE_NORMAL) * 1.2 + ITEM_PADDING * 2.).round();
+        self.rect = Rect::from_origin_size(
+            origin,
+            (POPUP_WIDTH, (MAX_SHOWN + 3) as f64 * self.item_height),
+        );
+        self.rect
+    }
+
+    fn row_rect(&self, row: usize) -> Rect {
+        Rect::from_origin_size(
+            (self.rect.x0, self.rect.y0 + row as f64 * self.item_height),
+            (self.rect.width(), self.item_height),
+        )
+    }
+
+    fn button_rects(&self) -> [Rect; 3] {
+        let row = self.row_rect(MAX_SHOWN + 2);
+        let width = row.width() / 3.;
+        let button = |i: usize| {
+            Rect::from_origin_size((row.x0 + i as f64 * width, row.y0), (width, row.height()))
+        };
+        [button(0), button(1), button(2)]
+    }
+
+    pub(crate) fn event(&mut self, ctx: &mut EventCtx, event: &Event) -> Option<FilterAction> {
+        let action = match event {
+            Event::MouseDown(me) if !self.rect.contains(me.pos) => Some(FilterAction::Close),
+            Event::MouseDown(me) if self.item_height > 0. => {
+                let row = ((me.pos.y - self.rect.y0) / self.item_height) as usize;
+                let matches = self.matches();
+                let [ok, clear, clear_all] = self.button_rects();
+                if ok.contains(me.pos) {
+                    Some(FilterAction::Apply(self.result()))
+                } else if clear.contains(me.pos) {
+                    Some(FilterAction::Apply(HashSet::new()))
+                } else if clear_all.contains(me.pos) {
+                    Some(FilterAction::ClearAll)
+                } else {
+                    if row == 1 {
+                        self.toggle_all(&matches);
+                    } else if let Some(idx) = row
+                        .checked_sub(2)
+                        .and_then(|shown| matches.get(self.first_shown + shown))
+                    {
+                        self.toggle(*idx);
+                    }
+                    None
+                }
+            }
+            Event::Wheel(we) => {
+                let max_first = self.matches().len().saturating_sub(MAX_SHOWN);
+                let first = if we.wheel_delta.y > 0. {
+                    self.first_shown + 1
+                } else {
+                    self.first_shown.saturating_sub(1)
+                };
+                self.first_shown = first.min(max_first);
+                None
+            }
+            Event::KeyDown(ke) => match &ke.key {
+                KbKey::Escape => Some(FilterAction::Close),
+                KbKey::Enter => Some(FilterAction::Apply(self.result())),
+                KbKey::Backspace => {
+                    self.search.pop();
+                    self.first_shown = 0;
+                    None
+                }
+                KbKey::Character(s) if !(ke.mods.ctrl() || ke.mods.meta()) => {
+                    self.search.push_str(s);
+                    self.first_shown = 0;
+                    None
+                }
+                _ => None,
+            },
+            _ => return None,
+        };
+        ctx.request_paint();
+        ctx.set_handled();
+        action
+    }
+
+    pub(crate) fn paint(&self, ctx: &mut PaintCtx, env: &Env) {
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let text_color = env.get(theme::LABEL_COLOR);
+        let placeholder = env.get(theme::PLACEHOLDER_COLOR);
+        let border = env.get(theme::BORDER_DARK);
+        let check_color = env.get(theme::PRIMARY_LIGHT);
+        ctx.fill(self.rect, &env.get(theme::BACKGROUND_LIGHT));
+
+        // Text ending at the point rather than starting there when right aligned
+        let draw_text = |ctx: &mut PaintCtx, text: &str, color: Color, at: Point, right: bool| {
+            if let Ok(layout) = ctx
+                .text()
+                .new_text_layout(text.to_string())
+                .font(FontFamily::SYSTEM_UI, font_size)
+                .text_color(color)
+                .build()
+            {
+                let x = if right {
+                    at.x - layout.size().width
+                } else {
+                    at.x
+                };
+                ctx.draw_text(&layout, (x, at.y));
+            }
+        };
+        let check_box = |ctx: &mut PaintCtx, row: Rect, checked: bool| {
+            let mid_y = row.center().y;
+            let rect = Rect::from_origin_size(
+                (row.x0 + ITEM_PADDING, mid_y - CHECK_SIZE / 2.),
+                (CHECK_SIZE, CHECK_SIZE),
+            );
+            ctx.stroke(rect, &border, 1.);
+            if checked {
+                let mut tick = BezPath::new();
+                tick.move_to(rect.origin() + Vec2::new(2.5, CHECK_SIZE / 2.));
+                tick.line_to(rect.origin() + Vec2::new(CHECK_SIZE / 2. - 1., CHECK_SIZE - 3.));
+                tick.line_to(rect.origin() + Vec2::new(CHECK_SIZE - 2., 2.5));
+                ctx.stroke(tick, &check_color, 2.);
+            }
+        };
+        let text_origin = |row: Rect, indent: f64| row.origin() + Vec2::new(indent, ITEM_PADDING);
+        let indent = CHECK_SIZE + ITEM_PADDING * 2.;
+
+        // The search box
+        let search_row = self.row_rect(0).inset(-2.);
+        ctx.stroke(search_row, &border, 1.);
+        if self.search.is_empty() {
+            let at = text_origin(search_row, 2.);
+            draw_text(ctx, "Search", placeholder.clone(), at, false);
+        } else {
+            let at = text_origin(search_row, 2.);
+            draw_text(ctx, &self.search, text_color.clone(), at, false);
+        }
+
+        let matches = self.matches();
+        let all_row = self.row_rect(1);
+        let all_shown = matches
+            .iter()
+            .all(|idx| !self.hidden.contains(&self.values[*idx].0));
+        check_box(ctx, all_row, all_shown);
+        let at = text_origin(all_row, indent);
+        draw_text(ctx, "(Select all)", text_color.clone(), at, false);
+
+        if matches.is_empty() {
+            let row = self.row_rect(2);
+            let at = text_origin(row, indent);
+            draw_text(ctx, "No matches", placeholder.clone(), at, false);
+        }
+        for (i, idx) in matches
+            .iter()
+            .skip(self.first_shown)
+            .take(MAX_SHOWN)
+            .enumerate()
+        {
+            let row = self.row_rect(i + 2);
+            let (value, count) = &self.values[*idx];
+            check_box(ctx, row, !self.hidden.contains(value));
+            let shown = if value.is_empty() { "(Blank)" } else { value };
+            let count = count.to_string();
+            let count_at = Point::new(row.x1 - ITEM_PADDING, row.y0 + ITEM_PADDING);
+            ctx.with_save(|ctx| {
+                ctx.clip(row);
+                let at = text_origin(row, indent);
+                draw_text(ctx, shown, text_color.clone(), at, false);
+            });
+            draw_text(ctx, &count, placeholder.clone(), count_at, true);
+        }
+
+        for (button, label) in self
+            .button_rects()
+            .iter()
+            .zip(&["OK", "Clear", "Clear all"])
+        {
+            let button = button.inset(-2.);
+            ctx.stroke(button, &border, 1.);
+            let at = text_origin(button, ITEM_PADDING);
+            draw_text(ctx, label, text_color.clone(), at, false);
+        }
+        ctx.stroke(self.rect, &border, 1.);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{RemapSpec, Remapper};
+    use crate::test_fixtures::{word_columns, word_rows};
+    use druid::im::hashset;
+
+    #[test]
+    fn filters_hide_rows_and_count_values() {
+        let columns = word_columns();
+        let data = word_rows(&[("apple", 0.5), ("pear", 1.), ("apple", 1.), ("plum", 0.)]);
+        let mut spec = RemapSpec::default();
+        assert_eq!(
+            distinct_values(&columns, &data, &spec.filters, LogIdx(1)),
+            vec![
+                ("0".to_string(), 1),
+                ("0.5".to_string(), 1),
+                ("1".to_string(), 2)
+            ]
+        );
+
+        spec.set_filter(LogIdx(0), hashset!["apple".to_string()]);
+        let remap = columns.remap_items(&data, &spec);
+        assert_eq!(remap.max_vis_idx(data.len()), VisIdx(1));
+        assert_eq!(remap.get_log_idx(VisIdx(0)), Some(LogIdx(1)));
+        assert_eq!(remap.get_log_idx(VisIdx(1)), Some(LogIdx(3)));
+        // The values of a column ignore its own filter, but not the others
+        assert_eq!(
+            distinct_values(&columns, &data, &spec.filters, LogIdx(1)),
+            vec![("0".to_string(), 1), ("1".to_string(), 1)]
+        );
+        assert_eq!(
+            distinct_values(&columns, &data, &spec.filters, LogIdx(0)).len(),
+            3
+        );
+
+        spec.clear_filters();
+        assert!(spec.filters.is_empty());
+    }
+}
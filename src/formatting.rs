@@ -0,0 +1,345 @@
+use crate::vis::F64Range;
+use druid::{Color, Env, Key};
+
+// Set in the Env while a styled cell is painted. TextCell honours it over its own colour.
+pub const STYLE_TEXT_COLOR: Key<Color> = Key::new("druid-table.style.text-color");
+pub const STYLE_BOLD: Key<bool> = Key::new("druid-table.style.bold");
+pub const STYLE_STRIKETHROUGH: Key<bool> = Key::new("druid-table.style.strikethrough");
+
+// Whole-cell styling, painted by the table around the cell's own renderer
+#[derive(Clone, Debug, Default)]
+pub struct CellStyle {
+    pub background: Option<Color>,
+    pub text_color: Option<Color>,
+    pub bold: bool,
+    pub strikethrough: bool,
+}
+
+impl CellStyle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn background(mut self, color: impl Into<Color>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+
+    pub fn text_color(mut self, color: impl Into<Color>) -> Self {
+        self.text_color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    // Fields set on other take precedence
+    pub fn merge(self, other: &CellStyle) -> CellStyle {
+        CellStyle {
+            background: other.background.clone().or(self.background),
+            text_color: other.text_color.clone().or(self.text_color),
+            bold: self.bold || other.bold,
+            strikethrough: self.strikethrough || other.strikethrough,
+        }
+    }
+
+    pub(crate) fn merge_opt(
+        style: Option<CellStyle>,
+        other: Option<CellStyle>,
+    ) -> Option<CellStyle> {
+        match (style, other) {
+            (Some(style), Some(other)) => Some(style.merge(&other)),
+            (style, None) => style,
+            (None, other) => other,
+        }
+    }
+
+    pub(crate) fn changes_env(&self) -> bool {
+        self.text_color.is_some() || self.bold || self.strikethrough
+    }
+
+    pub(crate) fn apply_to_env(&self, env: &Env) -> Env {
+        let mut env = env.clone();
+        if let Some(text_color) = &self.text_color {
+            env = env.adding(STYLE_TEXT_COLOR, text_color.clone());
+        }
+        if self.bold {
+            env = env.adding(STYLE_BOLD, true);
+        }
+        if self.strikethrough {
+            env = env.adding(STYLE_STRIKETHROUGH, true);
+        }
+        env
+    }
+}
+
+// A gradient across the range of values in a column, for heatmaps
+#[derive(Clone, Debug)]
+pub struct ColorScale {
+    low: Color,
+    mid: Option<Color>,
+    high: Color,
+}
+
+impl ColorScale {
+    pub fn two(low: Color, high: Color) -> Self {
+        ColorScale {
+            low,
+            mid: None,
+            high,
+        }
+    }
+
+    pub fn three(low: Color, mid: Color, high: Color) -> Self {
+        ColorScale {
+            low,
+            mid: Some(mid),
+            high,
+        }
+    }
+
+    // fraction is the position of a value in the range, 0 at the lowest and 1 at the highest
+    pub fn color_at(&self, fraction: f64) -> Color {
+        let fraction = fraction.max(0.).min(1.);
+        match &self.mid {
+            Some(mid) if fraction < 0.5 => lerp_color(&self.low, mid, fraction * 2.),
+            Some(mid) => lerp_color(mid, &self.high, fraction * 2. - 1.),
+            None => lerp_color(&self.low, &self.high, fraction),
+        }
+    }
+}
+
+fn lerp_color(from: &Color, to: &Color, fraction: f64) -> Color {
+    let (r0, g0, b0, a0) = from.as_rgba();
+    let (r1, g1, b1, a1) = to.as_rgba();
+    let lerp = |a: f64, b: f64| a + (b - a) * fraction;
+    Color::rgba(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1), lerp(a0, a1))
+}
+
+enum RuleKind<T> {
+    When(Box<dyn Fn(&T) -> bool>, CellStyle),
+    Ranked {
+        value: Box<dyn Fn(&T) -> f64>,
+        percent: f64,
+        top: bool,
+        style: CellStyle,
+        observed: Vec<f64>,
+        threshold: Option<f64>,
+    },
+    Scale {
+        value: Box<dyn Fn(&T) -> f64>,
+        scale: ColorScale,
+        range: Option<F64Range>,
+    },
+}
+
+// A declarative conditional format for a column.
+// Rules that depend on the column's values (ranks and colour scales) are computed over the rows
+// currently shown, so they follow sorting and filtering.
+pub struct FormatRule<T> {
+    kind: RuleKind<T>,
+}
+
+impl<T> FormatRule<T> {
+    pub fn when(test: impl Fn(&T) -> bool + 'static, style: CellStyle) -> Self {
+        FormatRule {
+            kind: RuleKind::When(Box::new(test), style),
+        }
+    }
+
+    pub fn top_percent(
+        value: impl Fn(&T) -> f64 + 'static,
+        percent: f64,
+        style: CellStyle,
+    ) -> Self {
+        Self::ranked(value, percent, true, style)
+    }
+
+    pub fn bottom_percent(
+        value: impl Fn(&T) -> f64 + 'static,
+        percent: f64,
+        style: CellStyle,
+    ) -> Self {
+        Self::ranked(value, percent, false, style)
+    }
+
+    fn ranked(
+        value: impl Fn(&T) -> f64 + 'static,
+        percent: f64,
+        top: bool,
+        style: CellStyle,
+    ) -> Self {
+        FormatRule {
+            kind: RuleKind::Ranked {
+                value: Box::new(value),
+                percent,
+                top,
+                style,
+                observed: Vec::new(),
+                threshold: None,
+            },
+        }
+    }
+
+    pub fn color_scale(value: impl Fn(&T) -> f64 + 'static, scale: ColorScale) -> Self {
+        FormatRule {
+            kind: RuleKind::Scale {
+                value: Box::new(value),
+                scale,
+                range: None,
+            },
+        }
+    }
+
+    pub(crate) fn observes_rows(&self) -> bool {
+        !matches!(self.kind, RuleKind::When(..))
+    }
+
+    pub(crate) fn start_observe(&mut self) {
+        match &mut self.kind {
+            RuleKind::Ranked {
+                observed,
+                threshold,
+                ..
+            } => {
+                observed.clear();
+                *threshold = None;
+            }
+            RuleKind::Scale { range, .. } => *range = None,
+            RuleKind::When(..) => (),
+        }
+    }
+
+    pub(crate) fn observe(&mut self, data: &T) {
+        match &mut self.kind {
+            RuleKind::Ranked {
+                value, observed, ..
+            } => {
+                let v = value(data);
+                if v.is_finite() {
+                    observed.push(v)
+                }
+            }
+            RuleKind::Scale { value, range, .. } => {
+                let v = value(data);
+                if v.is_finite() {
+                    *range = Some(match *range {
+                        Some(F64Range(min, max)) => F64Range(min.min(v), max.max(v)),
+                        None => F64Range(v, v),
+                    })
+                }
+            }
+            RuleKind::When(..) => (),
+        }
+    }
+
+    pub(crate) fn end_observe(&mut self) {
+        if let RuleKind::Ranked {
+            percent,
+            top,
+            observed,
+            threshold,
+            ..
+        } = &mut self.kind
+        {
+            *threshold = rank_threshold(observed, *percent, *top);
+            // Only the threshold is needed until the next observation
+            *observed = Vec::new();
+        }
+    }
+
+    pub(crate) fn style(&self, data: &T) -> Option<CellStyle> {
+        match &self.kind {
+            RuleKind::When(test, style) => if_opt!(test(data), style.clone()),
+            RuleKind::Ranked {
+                value,
+                top,
+                style,
+                threshold: Some(threshold),
+                ..
+            } => {
+                let v = value(data);
+                let hit = if *top {
+                    v >= *threshold
+                } else {
+                    v <= *threshold
+                };
+                if_opt!(hit, style.clone())
+            }
+            RuleKind::Scale {
+                value,
+                scale,
+                range: Some(range),
+            } => {
+                let v = value(data);
+                if v.is_finite() {
+                    let fraction = if range.distance() == 0. {
+                        0.5
+                    } else {
+                        (v - range.0) / range.distance()
+                    };
+                    Some(CellStyle::new().background(scale.color_at(fraction)))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// The value a row must reach to be in the top (or bottom) percent of the observed values
+fn rank_threshold(observed: &mut Vec<f64>, percent: f64, top: bool) -> Option<f64> {
+    if observed.is_empty() {
+        return None;
+    }
+    observed.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    if top {
+        observed.reverse();
+    }
+    let count = ((observed.len() as f64) * percent / 100.).round() as usize;
+    observed.get(count.max(1) - 1).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn top_and_bottom_thresholds() {
+        let values = || (1..=20).map(|v| v as f64).collect::<Vec<f64>>();
+        assert_eq!(rank_threshold(&mut values(), 10., true), Some(19.));
+        assert_eq!(rank_threshold(&mut values(), 10., false), Some(2.));
+        // Always at least one row
+        assert_eq!(rank_threshold(&mut values(), 1., true), Some(20.));
+        assert_eq!(rank_threshold(&mut Vec::new(), 10., true), None);
+    }
+
+    #[test]
+    fn rules_follow_observed_rows() {
+        let mut rule = FormatRule::top_percent(|v: &f64| *v, 50., CellStyle::new());
+        rule.start_observe();
+        for v in &[1., 2., 3., 4.] {
+            rule.observe(v);
+        }
+        rule.end_observe();
+        assert!(rule.style(&3.).is_some());
+        assert!(rule.style(&2.).is_none());
+
+        let mut scale =
+            FormatRule::color_scale(|v: &f64| *v, ColorScale::two(Color::BLACK, Color::WHITE));
+        scale.start_observe();
+        scale.observe(&0.);
+        scale.observe(&10.);
+        scale.end_observe();
+        let bg = scale.style(&5.).and_then(|s| s.background).unwrap();
+        assert_eq!(bg.as_rgba8(), Color::rgb(0.5, 0.5, 0.5).as_rgba8());
+    }
+}
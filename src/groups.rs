@@ -0,0 +1,333 @@
+use crate::axis_measure::{LogIdx, VisIdx};
+use crate::data::{Remap, RemapSpec};
+use druid::im::HashSet;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub enum GroupMember {
+    Column(LogIdx),
+    Group(ColumnGroup),
+}
+
+// A band of header over some columns, eg "Q1" over "Jan", "Feb" and "Mar".
+// Groups can hold other groups, which are shown in bands below them.
+#[derive(Clone, Debug)]
+pub struct ColumnGroup {
+    name: String,
+    members: Vec<GroupMember>,
+}
+
+impl ColumnGroup {
+    pub fn new(name: impl Into<String>) -> Self {
+        ColumnGroup {
+            name: name.into(),
+            members: Vec::new(),
+        }
+    }
+
+    // Columns by their position in the data
+    pub fn columns(mut self, columns: impl IntoIterator<Item = usize>) -> Self {
+        self.members.extend(
+            columns
+                .into_iter()
+                .map(|col| GroupMember::Column(LogIdx(col))),
+        );
+        self
+    }
+
+    pub fn group(mut self, group: ColumnGroup) -> Self {
+        self.members.push(GroupMember::Group(group));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn leaves(&self) -> Vec<LogIdx> {
+        let mut leaves = Vec::new();
+        for member in &self.members {
+            match member {
+                GroupMember::Column(log_idx) => leaves.push(*log_idx),
+                GroupMember::Group(group) => leaves.extend(group.leaves()),
+            }
+        }
+        leaves
+    }
+
+    fn depth(&self) -> usize {
+        1 + self
+            .members
+            .iter()
+            .map(|member| match member {
+                GroupMember::Group(group) => group.depth(),
+                GroupMember::Column(_) => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn add_to(&self, level: usize, all: &mut Vec<(usize, &ColumnGroup)>) {
+        all.push((level, self));
+        for member in &self.members {
+            if let GroupMember::Group(group) = member {
+                group.add_to(level + 1, all)
+            }
+        }
+    }
+}
+
+// A group as it is shown, over the visible columns from start to end
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GroupBand {
+    pub(crate) id: usize,
+    pub(crate) level: usize,
+    pub(crate) name: String,
+    pub(crate) start: VisIdx,
+    pub(crate) end: VisIdx,
+    pub(crate) collapsed: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ColumnGroups {
+    groups: Vec<ColumnGroup>,
+}
+
+impl ColumnGroups {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with(mut self, group: ColumnGroup) -> Self {
+        self.add(group);
+        self
+    }
+
+    pub fn add(&mut self, group: ColumnGroup) {
+        self.groups.push(group)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // The number of bands shown above the column headers
+    pub fn levels(&self) -> usize {
+        self.groups.iter().map(|g| g.depth()).max().unwrap_or(0)
+    }
+
+    // Every group, outermost first. Their positions here are their ids.
+    fn all(&self) -> Vec<(usize, &ColumnGroup)> {
+        let mut all = Vec::new();
+        for group in &self.groups {
+            group.add_to(0, &mut all)
+        }
+        all
+    }
+
+    fn vis_of_log(remap: &Remap, last: VisIdx) -> HashMap<LogIdx, VisIdx> {
+        VisIdx::range_inc_iter(VisIdx(0), last)
+            .filter_map(|vis| remap.get_log_idx(vis).map(|log| (log, vis)))
+            .collect()
+    }
+
+    fn vis_range(vis_of: &HashMap<LogIdx, VisIdx>, leaves: &[LogIdx]) -> Option<(VisIdx, VisIdx)> {
+        let shown = leaves.iter().filter_map(|log| vis_of.get(log));
+        let start = *shown.clone().min()?;
+        let end = *shown.max()?;
+        Some((start, end))
+    }
+
+    pub(crate) fn bands(&self, remap: &Remap, spec: &RemapSpec, last: VisIdx) -> Vec<GroupBand> {
+        let vis_of = Self::vis_of_log(remap, last);
+        self.all()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(id, (level, group))| {
+                let leaves = group.leaves();
+                let (start, end) = Self::vis_range(&vis_of, &leaves)?;
+                Some(GroupBand {
+                    id,
+                    level,
+                    name: group.name.clone(),
+                    start,
+                    end,
+                    collapsed: spec.collapsed.contains(&id),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn toggle_collapsed(&self, id: usize, spec: &mut RemapSpec, max_log_idx: LogIdx) {
+        if spec.collapsed.remove(&id).is_none() {
+            spec.collapsed.insert(id);
+        }
+        self.fold(spec, max_log_idx);
+    }
+
+    // A collapsed group shows only its first column. Groups inside it keep their own state, for
+    // when it is opened again.
+    fn fold(&self, spec: &mut RemapSpec, max_log_idx: LogIdx) {
+        spec.folded.clear();
+        let remap = spec.remap_placements(max_log_idx);
+        let vis_of = Self::vis_of_log(&remap, remap.max_vis_idx(max_log_idx.0 + 1));
+        let mut folded = HashSet::new();
+        for (id, (_, group)) in self.all().into_iter().enumerate() {
+            if !spec.collapsed.contains(&id) {
+                continue;
+            }
+            let leaves = group.leaves();
+            let first = leaves
+                .iter()
+                .filter_map(|log| vis_of.get(log).map(|vis| (*vis, *log)))
+                .min();
+            if let Some((_, first)) = first {
+                folded.extend(leaves.into_iter().filter(|log| *log != first));
+            }
+        }
+        spec.folded = folded;
+    }
+
+    // The columns that headers can be moved within, so they stay inside the innermost group
+    // holding all of them. None if no group holds them all.
+    pub(crate) fn move_range(
+        &self,
+        moved: &[LogIdx],
+        remap: &Remap,
+        last: VisIdx,
+    ) -> Option<(VisIdx, VisIdx)> {
+        let innermost = self
+            .all()
+            .into_iter()
+            .filter(|(_, group)| {
+                let leaves = group.leaves();
+                moved.iter().all(|log| leaves.contains(log))
+            })
+            .max_by_key(|(level, _)| *level)?;
+        Self::vis_range(&Self::vis_of_log(remap, last), &innermost.1.leaves())
+    }
+
+    // Whether the visible columns of every group are next to each other
+    pub(crate) fn keeps_together(&self, remap: &Remap, last: VisIdx) -> bool {
+        let vis_of = Self::vis_of_log(remap, last);
+        self.all().iter().all(|(_, group)| {
+            let leaves = group.leaves();
+            let shown = leaves.iter().filter(|log| vis_of.contains_key(log)).count();
+            match Self::vis_range(&vis_of, &leaves) {
+                Some((start, end)) => end.0 - start.0 + 1 == shown,
+                None => true,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn quarters() -> ColumnGroups {
+        ColumnGroups::new().with(
+            ColumnGroup::new("2020")
+                .group(ColumnGroup::new("Q1").columns(1..=3))
+                .group(ColumnGroup::new("Q2").columns(4..=6)),
+        )
+    }
+
+    #[test]
+    fn bands_span_their_columns() {
+        let groups = quarters();
+        assert_eq!(groups.levels(), 2);
+        let bands = groups.bands(&Remap::Pristine, &RemapSpec::default(), VisIdx(7));
+        let ranges: Vec<_> = bands
+            .iter()
+            .map(|b| (b.name.as_str(), b.level, b.start.0, b.end.0))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![("2020", 0, 1, 6), ("Q1", 1, 1, 3), ("Q2", 1, 4, 6)]
+        );
+    }
+
+    #[test]
+    fn collapse_hides_all_but_first_column() {
+        let groups = quarters();
+        let mut spec = RemapSpec::default();
+        groups.toggle_collapsed(1, &mut spec, LogIdx(7));
+        let remap = spec.remap_placements(LogIdx(7));
+        assert_eq!(remap.max_vis_idx(8), VisIdx(5));
+        assert_eq!(remap.get_log_idx(VisIdx(2)), Some(LogIdx(4)));
+
+        let bands = groups.bands(&remap, &spec, VisIdx(5));
+        assert!(bands[1].collapsed);
+        assert_eq!((bands[1].start, bands[1].end), (VisIdx(1), VisIdx(1)));
+
+        groups.toggle_collapsed(1, &mut spec, LogIdx(7));
+        assert!(spec.folded.is_empty());
+    }
+
+    #[test]
+    fn nested_groups_collapse_on_their_own() {
+        let groups = quarters();
+        let mut spec = RemapSpec::default();
+        let folded = |spec: &RemapSpec| {
+            let mut folded: Vec<usize> = spec.folded.iter().map(|log| log.0).collect();
+            folded.sort();
+            folded
+        };
+        groups.toggle_collapsed(1, &mut spec, LogIdx(7));
+        let bands = groups.bands(&spec.remap_placements(LogIdx(7)), &spec, VisIdx(5));
+        assert!(!bands[0].collapsed);
+        assert!(bands[1].collapsed);
+
+        // The year hides both quarters, and opening it again leaves Q1 collapsed
+        groups.toggle_collapsed(0, &mut spec, LogIdx(7));
+        assert_eq!(folded(&spec), vec![2, 3, 4, 5, 6]);
+        groups.toggle_collapsed(0, &mut spec, LogIdx(7));
+        assert_eq!(folded(&spec), vec![2, 3]);
+        let bands = groups.bands(&spec.remap_placements(LogIdx(7)), &spec, VisIdx(5));
+        assert!(!bands[0].collapsed);
+        assert!(bands[1].collapsed);
+        assert!(!bands[2].collapsed);
+    }
+
+    #[test]
+    fn hiding_columns_leaves_groups_alone() {
+        let groups = quarters();
+        let mut spec = RemapSpec::default();
+        groups.toggle_collapsed(1, &mut spec, LogIdx(7));
+        spec.hidden.insert(LogIdx(5));
+        let remap = spec.remap_placements(LogIdx(7));
+        assert_eq!(remap.max_vis_idx(8), VisIdx(4));
+        let bands = groups.bands(&remap, &spec, VisIdx(4));
+        assert!(bands[1].collapsed);
+        assert!(!bands[2].collapsed);
+
+        // Showing the hidden columns doesn't open the collapsed group
+        spec.hidden.clear();
+        let bands = groups.bands(&spec.remap_placements(LogIdx(7)), &spec, VisIdx(5));
+        assert!(bands[1].collapsed);
+    }
+
+    #[test]
+    fn moves_stay_inside_the_group() {
+        let groups = quarters();
+        assert_eq!(
+            groups.move_range(&[LogIdx(2)], &Remap::Pristine, VisIdx(7)),
+            Some((VisIdx(1), VisIdx(3)))
+        );
+        assert_eq!(
+            groups.move_range(&[LogIdx(2), LogIdx(5)], &Remap::Pristine, VisIdx(7)),
+            Some((VisIdx(1), VisIdx(6)))
+        );
+        assert_eq!(
+            groups.move_range(&[LogIdx(0)], &Remap::Pristine, VisIdx(7)),
+            None
+        );
+
+        let mut spec = RemapSpec::default();
+        spec.place(LogIdx(3), VisIdx(1));
+        assert!(groups.keeps_together(&spec.remap_placements(LogIdx(7)), VisIdx(7)));
+        spec.place(LogIdx(7), VisIdx(2));
+        assert!(!groups.keeps_together(&spec.remap_placements(LogIdx(7)), VisIdx(7)));
+    }
+}
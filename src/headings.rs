@@ -1,23 +1,33 @@
 use std::marker::PhantomData;
 
+use druid::kurbo::{BezPath, Line};
+use druid::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
 use druid::widget::prelude::*;
 use druid::{
-    Affine, BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, Rect, Size, UpdateCtx, Widget,
+    theme, Affine, BoxConstraints, Command, Cursor, Data, Env, Event, EventCtx, KbKey, LayoutCtx,
+    LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, Size, UpdateCtx, Vec2, Widget,
+    WidgetId,
 };
 
-use crate::axis_measure::{AxisMeasure, LogIdx, TableAxis, VisIdx, VisOffset};
+use crate::axis_measure::{AxisMeasure, AxisPair, LogIdx, TableAxis, VisIdx, VisOffset};
+use crate::cells::SHOW_FILTER;
 use crate::columns::{CellCtx, CellRender};
 use crate::config::{ResolvedTableConfig, TableConfig};
-use crate::data::{IndexedItems, SortSpec};
+use crate::data::IndexedItems;
+use crate::groups::{ColumnGroups, GroupBand};
 use crate::headings::HeaderMovement::{Disallowed, Permitted};
+use crate::menus::{HeaderMenus, MenuHeader};
 use crate::numbers_table::LogIdxTable;
+use crate::pinned::{column_event, ColumnRegion, Regions};
 use crate::render_ext::RenderContextExt;
+use crate::row_moves::{RowMove, RowMoves};
+use crate::selection::{CellRange, SingleCell, SingleSlice, SliceRange};
 use crate::table::TableState;
-use crate::IndicesSelection;
-use std::collections::HashMap;
+use crate::{IndicesSelection, TableSelection};
 use druid_bindings::{BindableAccess, bindable_self_body};
 
+const FILTER_BUTTON_SIZE: f64 = 12.;
+
 pub trait HeadersFromData {
     type TableData: Data;
     type Header: Data;
@@ -90,7 +100,14 @@ impl<TableData: IndexedItems + Data> HeadersFromData for HeadersFromIndices<Tabl
 enum HeaderMovement {
     Disallowed,
     Permitted,
-    Moving(VisIdx),
+    Moving(HeaderDrag),
+}
+
+// The selected headers being dragged, from where they were pressed to where the pointer is now
+struct HeaderDrag {
+    pressed: f64,
+    pointer: f64,
+    drop: Option<VisIdx>,
 }
 
 pub struct Headings<HeadersSource, Render>
@@ -107,6 +124,15 @@ where
     header_movement: HeaderMovement,
     resize_dragging: Option<VisIdx>,
     selection_dragging: bool,
+    groups: ColumnGroups,
+    // The cells that filters and menu actions are sent to
+    cells: Option<WidgetId>,
+    filters: bool,
+    menus: Option<HeaderMenus>,
+    // The columns these headers show, when some are pinned outside the horizontal scroll
+    region: ColumnRegion,
+    regions: Option<Regions>,
+    row_moves: Option<RowMoves<HeadersSource::TableData>>,
 }
 
 impl<HeadersSource, Render> Headings<HeadersSource, Render>
@@ -131,9 +157,425 @@ where
             header_movement: if allow_moves { Permitted } else { Disallowed },
             resize_dragging: None,
             selection_dragging: false,
+            groups: ColumnGroups::new(),
+            cells: None,
+            filters: false,
+            menus: None,
+            region: ColumnRegion::Scrolling,
+            regions: None,
+            row_moves: None,
+        }
+    }
+
+    // Dragged rows are moved in the data, rather than only where they are shown
+    pub fn row_moves(mut self, row_moves: RowMoves<HeadersSource::TableData>) -> Self {
+        self.row_moves = Some(row_moves);
+        self
+    }
+
+    pub(crate) fn in_region(mut self, region: ColumnRegion, regions: Regions) -> Self {
+        self.region = region;
+        self.regions = Some(regions);
+        self
+    }
+
+    // The pixels along the axis these headers show. Only columns are pinned.
+    fn region_pixels(&self, data: &TableState<HeadersSource::TableData>) -> (f64, f64) {
+        match self.axis {
+            TableAxis::Columns => data.region_pixels(self.region),
+            TableAxis::Rows => (0., data.measures[self.axis].total_pixel_length()),
         }
     }
 
+    pub fn cells(mut self, cells: WidgetId) -> Self {
+        self.cells = Some(cells);
+        self
+    }
+
+    // A button on each header that opens its filter in the cells
+    pub fn filters(mut self, filters: bool) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    // A menu of actions on the header when it is right clicked
+    pub fn menus(mut self, menus: HeaderMenus) -> Self {
+        self.menus = Some(menus);
+        self
+    }
+
+    // Selects the header if it isn't already, so the menu's actions are seen to apply to it
+    fn show_menu(
+        &self,
+        ctx: &mut EventCtx,
+        data: &mut TableState<HeadersSource::TableData>,
+        me: &MouseEvent,
+    ) -> Option<()> {
+        let (menus, cells) = (self.menus.as_ref()?, self.cells?);
+        let rtc = self.resolved_config.as_ref()?;
+        if self.axis.cross_axis().main_pixel_from_point(&me.pos) < self.bands_length(rtc) {
+            return None;
+        }
+        let pix_main = self.axis.main_pixel_from_point(&me.pos);
+        let vis_idx = data.measures[self.axis].vis_idx_from_pixel(pix_main)?;
+        let log_idx = data.remaps[self.axis].get_log_idx(vis_idx)?;
+        if !data.selection.fully_selects_heading(self.axis, vis_idx) {
+            data.selection
+                .select_in_axis(self.axis, vis_idx, &data.remaps);
+        }
+        let header = MenuHeader::new(self.axis, log_idx, &data.remap_specs);
+        menus.show(ctx, &header, cells, me.window_pos);
+        ctx.set_handled();
+        Some(())
+    }
+
+    fn header_rect(
+        &self,
+        rtc: &ResolvedTableConfig,
+        measure: &AxisMeasure,
+        vis_idx: VisIdx,
+    ) -> Option<Rect> {
+        Some(Rect::from_origin_size(
+            self.axis.cell_origin(
+                measure.first_pixel_from_vis(vis_idx)?,
+                self.bands_length(rtc),
+            ),
+            self.axis.size(
+                measure.pixels_length_for_vis(vis_idx)?,
+                rtc.cross_axis_length(&self.axis),
+            ),
+        ))
+    }
+
+    // At the end of the header, taken away from the space for its content
+    fn filter_button_rect(&self, rtc: &ResolvedTableConfig, header: &Rect) -> Option<Rect> {
+        if !self.filters || self.cells.is_none() {
+            return None;
+        }
+        let center = header.center();
+        let x = header.x1 - rtc.cell_padding - FILTER_BUTTON_SIZE;
+        Some(Rect::from_origin_size(
+            (x, center.y - FILTER_BUTTON_SIZE / 2.),
+            (FILTER_BUTTON_SIZE, FILTER_BUTTON_SIZE),
+        ))
+    }
+
+    fn filter_button_at(
+        &self,
+        data: &TableState<HeadersSource::TableData>,
+        pos: Point,
+    ) -> Option<LogIdx> {
+        let rtc = self.resolved_config.as_ref()?;
+        let measure = &data.measures[self.axis];
+        let vis_idx = measure.vis_idx_from_pixel(self.axis.main_pixel_from_point(&pos))?;
+        let header = self.header_rect(rtc, measure, vis_idx)?;
+        let button = self.filter_button_rect(rtc, &header)?;
+        if button.inflate(2., 2.).contains(pos) {
+            data.remaps[self.axis].get_log_idx(vis_idx)
+        } else {
+            None
+        }
+    }
+
+    // A funnel when the column is filtered, otherwise an arrow to open the filter
+    fn paint_filter_button(
+        ctx: &mut PaintCtx,
+        rect: Rect,
+        filtered: bool,
+        rtc: &ResolvedTableConfig,
+        env: &Env,
+    ) {
+        let mut path = BezPath::new();
+        if filtered {
+            let mid = rect.center().x;
+            let neck = rect.y0 + rect.height() * 0.45;
+            path.move_to((rect.x0, rect.y0 + 1.));
+            path.line_to((rect.x1, rect.y0 + 1.));
+            path.line_to((mid + 1.5, neck));
+            path.line_to((mid + 1.5, rect.y1 - 1.));
+            path.line_to((mid - 1.5, rect.y1 - 2.5));
+            path.line_to((mid - 1.5, neck));
+            path.close_path();
+            ctx.fill(path, &rtc.focus_color);
+        } else {
+            let third = rect.height() / 3.;
+            path.move_to((rect.x0 + 2., rect.y0 + third));
+            path.line_to((rect.x1 - 2., rect.y0 + third));
+            path.line_to((rect.center().x, rect.y1 - third));
+            path.close_path();
+            ctx.fill(path, &env.get(theme::PLACEHOLDER_COLOR));
+        }
+    }
+
+    // Bands of group headers above the headers
+    pub fn groups(mut self, groups: ColumnGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    // The space taken by the group bands across the axis
+    fn bands_length(&self, rtc: &ResolvedTableConfig) -> f64 {
+        self.groups.levels() as f64 * rtc.cross_axis_length(&self.axis)
+    }
+
+    fn last_vis(&self, data: &TableState<HeadersSource::TableData>) -> VisIdx {
+        let len = self.headers.as_ref().map_or(0, |h| h.idx_len());
+        data.remaps[self.axis].max_vis_idx(len)
+    }
+
+    fn band_at(
+        &self,
+        data: &TableState<HeadersSource::TableData>,
+        pix_main: f64,
+        pix_cross: f64,
+    ) -> Option<(GroupBand, Rect)> {
+        let rtc = self.resolved_config.as_ref()?;
+        if pix_cross >= self.bands_length(rtc) {
+            return None;
+        }
+        let level = (pix_cross / rtc.cross_axis_length(&self.axis)) as usize;
+        let measure = &data.measures[self.axis];
+        let vis_idx = measure.vis_idx_from_pixel(pix_main)?;
+        let band = self
+            .groups
+            .bands(
+                &data.remaps[self.axis],
+                &data.remap_specs[self.axis],
+                self.last_vis(data),
+            )
+            .into_iter()
+            .find(|band| band.level == level && band.start <= vis_idx && vis_idx <= band.end)?;
+        let rect = self.band_rect(rtc, measure, &band)?;
+        Some((band, rect))
+    }
+
+    fn band_rect(
+        &self,
+        rtc: &ResolvedTableConfig,
+        measure: &AxisMeasure,
+        band: &GroupBand,
+    ) -> Option<Rect> {
+        let cross = rtc.cross_axis_length(&self.axis);
+        let start = measure.first_pixel_from_vis(band.start)?;
+        let end =
+            measure.first_pixel_from_vis(band.end)? + measure.pixels_length_for_vis(band.end)?;
+        Some(Rect::from_origin_size(
+            self.axis.cell_origin(start, band.level as f64 * cross),
+            self.axis.size(end - start, cross),
+        ))
+    }
+
+    // The arrow at the start of the band collapses or expands the group, elsewhere its columns
+    // are selected
+    fn band_clicked(
+        &self,
+        data: &mut TableState<HeadersSource::TableData>,
+        pix_main: f64,
+        pix_cross: f64,
+    ) -> bool {
+        let (band, rect) = match self.band_at(data, pix_main, pix_cross) {
+            Some(found) => found,
+            None => return false,
+        };
+        let (start, _) = self.axis.pixels_from_rect(&rect);
+        let arrow_length = self.axis.cross_axis().length_from_size(&rect.size());
+        if pix_main - start < arrow_length {
+            let len = self.headers.as_ref().map_or(0, |h| h.idx_len());
+            self.groups.toggle_collapsed(
+                band.id,
+                &mut data.remap_specs[self.axis],
+                LogIdx(len.saturating_sub(1)),
+            );
+        } else {
+            data.selection
+                .select_in_axis(self.axis, band.start, &data.remaps);
+            data.selection
+                .extend_in_axis(self.axis, band.end, &data.remaps);
+        }
+        true
+    }
+
+    fn moved_headers(&self, data: &TableState<HeadersSource::TableData>) -> Vec<VisIdx> {
+        data.selection
+            .fully_selected_on_axis(self.axis)
+            .unwrap_or_default()
+    }
+
+    // Where the selected headers go when dropped on a header, kept inside their group and
+    // before the end of the axis
+    fn drop_target(&self, data: &TableState<HeadersSource::TableData>, pointed: VisIdx) -> VisIdx {
+        let remap = &data.remaps[self.axis];
+        let last = self.last_vis(data);
+        let moved: Vec<LogIdx> = self
+            .moved_headers(data)
+            .into_iter()
+            .filter_map(|vis| remap.get_log_idx(vis))
+            .collect();
+        let (start, end) = self
+            .groups
+            .move_range(&moved, remap, last)
+            .unwrap_or((VisIdx(0), last));
+        let latest = VisIdx((end.0 + 1).saturating_sub(moved.len()));
+        pointed.min(latest).max(start)
+    }
+
+    // Where the line showing the drop goes: before the header the moved ones will be put in front
+    // of, or after the one they will follow
+    fn drop_pixel(&self, data: &TableState<HeadersSource::TableData>, drop: VisIdx) -> Option<f64> {
+        let moved = self.moved_headers(data);
+        let measure = &data.measures[self.axis];
+        if drop <= *moved.first()? {
+            measure.first_pixel_from_vis(drop)
+        } else {
+            let follows = drop + VisOffset(moved.len() as isize - 1);
+            Some(measure.first_pixel_from_vis(follows)? + measure.pixels_length_for_vis(follows)?)
+        }
+    }
+
+    // The ghost follows the pointer, and the headers will drop where it is
+    fn drag_headers(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &TableState<HeadersSource::TableData>,
+        pix_main: f64,
+    ) {
+        let measure = &data.measures[self.axis];
+        let last_pixel = (measure.total_pixel_length() - 1.).max(0.);
+        let drop = measure
+            .vis_idx_from_pixel(pix_main.max(0.).min(last_pixel))
+            .map(|pointed| self.drop_target(data, pointed));
+        if let HeaderMovement::Moving(drag) = &mut self.header_movement {
+            drag.pointer = pix_main;
+            drag.drop = drop;
+        }
+        ctx.set_cursor(&Cursor::OpenHand);
+        ctx.request_paint();
+    }
+
+    // Whether the mouse is over a header that can be dragged to move it
+    fn can_drag(&self, data: &TableState<HeadersSource::TableData>, pos: Point) -> bool {
+        let rtc = match (&self.header_movement, &self.resolved_config) {
+            (Permitted, Some(rtc)) => rtc,
+            _ => return false,
+        };
+        if self.axis.cross_axis().main_pixel_from_point(&pos) < self.bands_length(rtc) {
+            return false;
+        }
+        // Rows are moved in the data, so only while they are shown in data order. Sorted rows
+        // would not move, and filtered or hidden ones would not line up with what is shown.
+        if self.row_moves.is_some() && !data.remaps[self.axis].is_pristine() {
+            return false;
+        }
+        data.measures[self.axis]
+            .vis_idx_from_pixel(self.axis.main_pixel_from_point(&pos))
+            .map_or(false, |idx| {
+                data.selection.fully_selects_heading(self.axis, idx)
+            })
+    }
+
+    // Dropped or cancelled, the keyboard goes back to the cells
+    fn end_drag(&mut self, ctx: &mut EventCtx) {
+        self.header_movement = Permitted;
+        ctx.set_active(false);
+        if let Some(cells) = self.cells {
+            ctx.set_focus(cells);
+        }
+        ctx.request_paint();
+    }
+
+    // The rows go in front of the one shown where they are dropped, once they are taken out
+    fn move_rows(
+        &self,
+        data: &mut TableState<HeadersSource::TableData>,
+        drop: VisIdx,
+        row_moves: &RowMoves<HeadersSource::TableData>,
+    ) -> Option<()> {
+        // Rows shown in data order are where their logical index says, which the selection below
+        // relies on
+        if !data.remaps[self.axis].is_pristine() {
+            return None;
+        }
+        let moved = self.moved_headers(data);
+        let remap = &data.remaps[self.axis];
+        let row_move = RowMove {
+            rows: moved
+                .iter()
+                .filter_map(|vis| remap.get_log_idx(*vis))
+                .collect(),
+            before: VisIdx::range_inc_iter(VisIdx(0), self.last_vis(data))
+                .filter(|vis| !moved.contains(vis))
+                .nth(drop.0)
+                .and_then(|vis| remap.get_log_idx(vis)),
+        };
+        if !row_moves.make(&mut data.data, &row_move) {
+            return None;
+        }
+
+        // The rows stay selected where they now are
+        let len = self.headers.as_ref().map_or(0, |h| h.idx_len());
+        let first = row_move.destination(len);
+        let col = data.remaps[TableAxis::Columns].get_log_idx(VisIdx(0))?;
+        let cell = |offset: usize| {
+            SingleCell::new(
+                AxisPair::new(VisIdx(first + offset), VisIdx(0)),
+                AxisPair::new(LogIdx(first + offset), col),
+            )
+        };
+        data.selection = match row_move.rows.len() {
+            0 => return None,
+            1 => TableSelection::SingleSlice(SingleSlice::new(self.axis, cell(0))),
+            count => TableSelection::SliceRange(SliceRange {
+                axis: self.axis,
+                range: CellRange::new(cell(0), cell(count - 1)),
+            }),
+        };
+        Some(())
+    }
+
+    // Moves the selected headers. A move that would split up a group is ignored.
+    fn move_headers(&self, data: &mut TableState<HeadersSource::TableData>, moved_to_idx: VisIdx) {
+        if self.groups.is_empty() {
+            data.explicit_header_move(self.axis, moved_to_idx);
+            return;
+        }
+
+        let before = data.remap_specs[self.axis].clone();
+        data.explicit_header_move(self.axis, moved_to_idx);
+        let len = self.headers.as_ref().map_or(0, |h| h.idx_len());
+        let remap = data.remap_specs[self.axis].remap_placements(LogIdx(len.saturating_sub(1)));
+        if !self.groups.keeps_together(&remap, remap.max_vis_idx(len)) {
+            data.remap_specs[self.axis] = before;
+        }
+    }
+
+    fn paint_band(
+        &self,
+        ctx: &mut PaintCtx,
+        env: &Env,
+        measure: &AxisMeasure,
+        band: &GroupBand,
+    ) -> Option<()> {
+        let rtc = self.resolved_config.as_ref()?;
+        let rect = self.band_rect(rtc, measure, band)?;
+        let arrow = if band.collapsed { "▸" } else { "▾" };
+        let layout = ctx
+            .text()
+            .new_text_layout(format!("{} {}", arrow, band.name))
+            .font(FontFamily::SYSTEM_UI, env.get(theme::TEXT_SIZE_NORMAL))
+            .text_color(env.get(theme::LABEL_COLOR))
+            .build()
+            .ok()?;
+
+        let padded_rect = rect.inset(-rtc.cell_padding);
+        ctx.with_save(|ctx| {
+            ctx.clip(padded_rect);
+            let y = ((padded_rect.height() - layout.size().height) / 2.).max(0.);
+            ctx.draw_text(&layout, (padded_rect.x0, padded_rect.y0 + y));
+        });
+        ctx.stroke_bottom_left_border(&rect, &rtc.cells_border, rtc.cell_border_thickness);
+        Some(())
+    }
+
     fn set_pix_length_for_axis(
         &mut self,
         measure: &mut AxisMeasure,
@@ -146,6 +588,71 @@ where
         ctx.request_layout();
     }
 
+    fn paint_content(
+        ctx: &mut PaintCtx,
+        header_render: &Render,
+        cell: &CellCtx,
+        header: &HeadersSource::Header,
+        padded_rect: Rect,
+        env: &Env,
+    ) {
+        ctx.with_save(|ctx| {
+            let layout_origin = padded_rect.origin().to_vec2();
+            ctx.clip(padded_rect);
+            ctx.transform(Affine::translate(layout_origin));
+            ctx.with_child_ctx(padded_rect, |ctxt| {
+                header_render.paint(ctxt, cell, header, env);
+            });
+        });
+    }
+
+    // A see-through copy of the moved headers under the pointer, and a line where they will drop
+    fn paint_drag(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &TableState<HeadersSource::TableData>,
+        env: &Env,
+    ) -> Option<()> {
+        let drag = match &self.header_movement {
+            HeaderMovement::Moving(drag) => drag,
+            _ => return None,
+        };
+        let rtc = self.resolved_config.as_ref()?;
+        let headers = self.headers.as_ref()?;
+        let measure = &data.measures[self.axis];
+        let offset: Vec2 = self
+            .axis
+            .cell_origin(drag.pointer - drag.pressed, 0.)
+            .to_vec2();
+
+        for vis_idx in self.moved_headers(data) {
+            let rect = self.header_rect(rtc, measure, vis_idx)? + offset;
+            ctx.fill(rect, &rtc.selection_color.clone().with_alpha(0.4));
+            ctx.stroke(rect, &rtc.selection_color, rtc.cell_border_thickness);
+            if let Some(log_idx) = data.remaps[self.axis].get_log_idx(vis_idx) {
+                let cell = CellCtx::Header(
+                    &self.axis,
+                    log_idx,
+                    &data.remap_specs[self.axis.cross_axis()],
+                );
+                let padded_rect = rect.inset(-rtc.cell_padding);
+                headers.with(log_idx, |header| {
+                    Self::paint_content(ctx, &self.header_render, &cell, header, padded_rect, env)
+                });
+            }
+        }
+
+        let pixel = self.drop_pixel(data, drag.drop?)?;
+        let bands = self.bands_length(rtc);
+        let line = Line::new(
+            self.axis.cell_origin(pixel, bands),
+            self.axis
+                .cell_origin(pixel, bands + rtc.cross_axis_length(&self.axis)),
+        );
+        ctx.stroke(line, &rtc.focus_color, 2.);
+        Some(())
+    }
+
     fn paint_header(
         &mut self,
         ctx: &mut PaintCtx,
@@ -153,41 +660,38 @@ where
         env: &Env,
         measure: &AxisMeasure,
         indices_selection: &IndicesSelection,
-        sort_dirs: &HashMap<LogIdx, SortSpec>,
         vis_main_idx: VisIdx,
     ) -> Option<()> {
         let rtc = self.resolved_config.as_ref()?;
         let headers = self.headers.as_ref()?;
         let axis = self.axis;
+        let cell_rect = self.header_rect(rtc, measure, vis_main_idx)?;
+        let filter_button = self.filter_button_rect(rtc, &cell_rect);
         let header_render = &mut self.header_render;
 
-        let cell_rect = Rect::from_origin_size(
-            axis.cell_origin(measure.first_pixel_from_vis(vis_main_idx)?, 0.),
-            axis.size(
-                measure.pixels_length_for_vis(vis_main_idx)?,
-                rtc.cross_axis_length(&axis),
-            ),
-        );
-
         if indices_selection.vis_index_selected(vis_main_idx) {
             ctx.fill(cell_rect, &rtc.header_selected_background);
         }
 
-        let padded_rect = cell_rect.inset(-rtc.cell_padding);
+        let mut padded_rect = cell_rect.inset(-rtc.cell_padding);
+        if let Some(button) = &filter_button {
+            padded_rect.x1 = (button.x0 - rtc.cell_padding).max(padded_rect.x0);
+        }
         if let Some(log_main_idx) = data.remaps[self.axis].get_log_idx(vis_main_idx) {
-            let cell = CellCtx::Header(&axis, log_main_idx, sort_dirs.get(&log_main_idx));
+            let cross_spec = &data.remap_specs[axis.cross_axis()];
+            let cell = CellCtx::Header(&axis, log_main_idx, cross_spec);
 
             headers.with(log_main_idx, |col_name| {
-                ctx.with_save(|ctx| {
-                    let layout_origin = padded_rect.origin().to_vec2();
-                    ctx.clip(padded_rect);
-                    ctx.transform(Affine::translate(layout_origin));
-                    ctx.with_child_ctx(padded_rect, |ctxt| {
-                        header_render.paint(ctxt, &cell, col_name, env);
-                    });
-                });
+                Self::paint_content(ctx, header_render, &cell, col_name, padded_rect, env)
             });
 
+            if let Some(button) = filter_button {
+                let filtered = data.remap_specs[axis.cross_axis()]
+                    .filters
+                    .contains_key(&log_main_idx);
+                Self::paint_filter_button(ctx, button, filtered, rtc, env);
+            }
+
             ctx.stroke_bottom_left_border(&cell_rect, &rtc.cells_border, rtc.cell_border_thickness);
         }
         Some(())
@@ -207,10 +711,28 @@ where
         data: &mut TableState<HeadersSource::TableData>,
         _env: &Env,
     ) {
-        let measure = &mut data.measures[self.axis];
+        let event = &match self.axis {
+            TableAxis::Columns => column_event(data, self.region, self.regions.as_ref(), event),
+            TableAxis::Rows => event.clone(),
+        };
         match event {
+            Event::MouseDown(me) if me.button.is_right() => {
+                self.show_menu(ctx, data, me);
+            }
             Event::MouseDown(me) => {
                 let pix_main = self.axis.main_pixel_from_point(&me.pos);
+                let pix_cross = self.axis.cross_axis().main_pixel_from_point(&me.pos);
+                if let (Some(col), Some(cells)) = (self.filter_button_at(data, me.pos), self.cells)
+                {
+                    ctx.submit_command(Command::new(SHOW_FILTER, col, cells));
+                    ctx.set_handled();
+                    return;
+                }
+                if me.count == 1 && self.band_clicked(data, pix_main, pix_cross) {
+                    ctx.set_handled();
+                    return;
+                }
+                let measure = &mut data.measures[self.axis];
                 if me.count == 2 {
                     let extend = me.mods.ctrl() || me.mods.meta();
                     if let Some(vis_idx) = measure.vis_idx_from_pixel(pix_main) {
@@ -229,15 +751,24 @@ where
                             ctx.set_handled()
                         }
                     } else if let Some(idx) = measure.vis_idx_from_pixel(pix_main) {
-                        let sel = &mut data.selection;
                         // Already selected so move headings:
-                        if sel.fully_selects_heading(self.axis, idx) {
-                            self.header_movement = HeaderMovement::Moving(idx);
+                        if self.can_drag(data, me.pos) {
+                            self.header_movement = HeaderMovement::Moving(HeaderDrag {
+                                pressed: pix_main,
+                                pointer: pix_main,
+                                drop: None,
+                            });
                             ctx.set_active(true);
+                            // So Escape can cancel the move
+                            ctx.request_focus();
+                            ctx.set_cursor(&Cursor::OpenHand);
                         } else {
+                            let sel = &mut data.selection;
                             // Change the selection
                             if me.mods.shift() {
                                 sel.extend_in_axis(self.axis, idx, &data.remaps);
+                            } else if me.mods.ctrl() || me.mods.meta() {
+                                sel.add_in_axis(self.axis, idx, &data.remaps);
                             } else {
                                 sel.select_in_axis(self.axis, idx, &data.remaps);
                             }
@@ -248,7 +779,13 @@ where
                     }
                 }
             }
+            Event::MouseMove(me) if matches!(self.header_movement, HeaderMovement::Moving(_)) => {
+                let pix_main = self.axis.main_pixel_from_point(&me.pos);
+                self.drag_headers(ctx, data, pix_main);
+                ctx.set_handled()
+            }
             Event::MouseMove(me) => {
+                let measure = &mut data.measures[self.axis];
                 let pix_main = self.axis.main_pixel_from_point(&me.pos);
                 if let Some(idx) = self.resize_dragging {
                     self.set_pix_length_for_axis(measure, ctx, idx, pix_main);
@@ -259,10 +796,6 @@ where
                         ctx.set_cursor(self.axis.resize_cursor());
                     }
                     ctx.set_handled()
-                } else if let HeaderMovement::Moving(_idx) = self.header_movement {
-                    // Show visual indicator
-                    if let Some(idx) = measure.vis_idx_from_pixel(pix_main) {}
-                    ctx.set_handled()
                 } else if self.selection_dragging {
                     if let Some(idx) = measure.vis_idx_from_pixel(pix_main) {
                         data.selection.extend_in_axis(self.axis, idx, &data.remaps);
@@ -277,20 +810,27 @@ where
                         ctx.set_cursor(cursor);
                         ctx.set_handled();
                     }
-                } // TODO grabber for when header can move (ie selected)
+                } else if self.can_drag(data, me.pos) {
+                    ctx.set_cursor(&Cursor::OpenHand);
+                }
             }
             Event::MouseUp(me) => {
+                let measure = &mut data.measures[self.axis];
                 let pix_main = self.axis.main_pixel_from_point(&me.pos);
                 if let Some(idx) = self.resize_dragging {
                     self.set_pix_length_for_axis(measure, ctx, idx, pix_main);
                     self.resize_dragging = None;
                     ctx.set_active(false);
                     ctx.set_handled();
-                } else if let HeaderMovement::Moving(moved_idx) = self.header_movement {
-                    if let Some(moved_to_idx) = measure.vis_idx_from_pixel(pix_main) {
-                        data.explicit_header_move(self.axis, moved_to_idx)
+                } else if let HeaderMovement::Moving(drag) = &self.header_movement {
+                    match (drag.drop, &self.row_moves) {
+                        (Some(drop), Some(row_moves)) if self.axis == TableAxis::Rows => {
+                            self.move_rows(data, drop, row_moves);
+                        }
+                        (Some(drop), _) => self.move_headers(data, drop),
+                        _ => (),
                     }
-                    ctx.set_active(false);
+                    self.end_drag(ctx);
                     ctx.set_handled()
                 } else if self.selection_dragging {
                     self.selection_dragging = false;
@@ -298,6 +838,12 @@ where
                     ctx.set_handled()
                 }
             }
+            Event::KeyDown(ke) if ke.key == KbKey::Escape => {
+                if let HeaderMovement::Moving(_) = self.header_movement {
+                    self.end_drag(ctx);
+                    ctx.set_handled();
+                }
+            }
             _ => (),
         }
     }
@@ -338,18 +884,17 @@ where
     ) -> Size {
         bc.debug_check("ColumnHeadings");
         let cross_axis_length = if let Some(rc) = &self.resolved_config {
-            match self.axis {
+            let header_length = match self.axis {
                 TableAxis::Columns => rc.col_header_height,
                 TableAxis::Rows => rc.row_header_width,
-            }
+            };
+            header_length + self.bands_length(rc)
         } else {
             self.axis.default_header_cross()
         };
 
-        bc.constrain(self.axis.size(
-            data.measures[self.axis].total_pixel_length(),
-            cross_axis_length,
-        ))
+        let (start, end) = self.region_pixels(data);
+        bc.constrain(self.axis.size(end - start, cross_axis_length))
     }
 
     fn paint(
@@ -361,36 +906,37 @@ where
         let measure = &data.measures[self.axis];
         let indices_selection = data.selection.to_axis_selection(self.axis, &data.remaps);
 
-        // TODO build on change of spec
-        let cross_rem = &data.remap_specs[self.axis.cross_axis()];
-        let sort_dirs: HashMap<LogIdx, SortSpec> = cross_rem
-            .sort_by
-            .iter()
-            .enumerate()
-            .map(|(ord, x)| (LogIdx(x.idx), SortSpec::new(ord, x.direction)))
-            .collect();
-
         if let Some(rtc) = &self.resolved_config {
             self.header_render.init(ctx, env);
-            let rect = ctx.region().bounding_box();
+            // Painted along the whole axis, moved back to where this region starts
+            let (start, end) = self.region_pixels(data);
+            let shift = self.axis.cell_origin(start, 0.).to_vec2();
+            let rect = ctx.region().bounding_box() + shift;
+            let (p0, p1) = self.axis.pixels_from_rect(&rect);
+            let (p0, p1) = (p0.max(start), p1.min(end));
+            ctx.transform(Affine::translate(-shift));
 
             ctx.fill(rect, &rtc.header_background);
 
-            let (p0, p1) = self.axis.pixels_from_rect(&rect);
             let (start_main, end_main) = measure.vis_range_from_pixels(p0, p1);
 
             for vis_main_idx in VisIdx::range_inc_iter(start_main, end_main) {
                 // TODO: excessive unwrapping
-                self.paint_header(
-                    ctx,
-                    data,
-                    env,
-                    measure,
-                    &indices_selection,
-                    &sort_dirs,
-                    vis_main_idx,
-                );
+                self.paint_header(ctx, data, env, measure, &indices_selection, vis_main_idx);
+            }
+
+            let bands = self.groups.bands(
+                &data.remaps[self.axis],
+                &data.remap_specs[self.axis],
+                self.last_vis(data),
+            );
+            for band in bands
+                .iter()
+                .filter(|band| band.end >= start_main && band.start <= end_main)
+            {
+                self.paint_band(ctx, env, measure, band);
             }
+            self.paint_drag(ctx, data, env);
         }
     }
 }
@@ -4,34 +4,74 @@ mod macros;
 mod axis_measure;
 mod builder;
 mod cells;
+mod chart_cells;
+mod choices;
+mod click_cells;
+mod clipboard;
 mod columns;
 mod config;
 mod data;
+mod date_time;
+mod editors;
+mod fill;
+mod filters;
+mod formatting;
+mod groups;
 mod headings;
 mod interp;
+mod menus;
 pub mod numbers_table;
+mod pinned;
 mod render_ext;
+mod row_moves;
 mod selection;
+mod spans;
+mod stats;
 mod table;
+#[cfg(test)]
+mod test_fixtures;
 mod vis;
 
 pub use axis_measure::{
     AxisMeasure, AxisPair, FixedAxisMeasure, LogIdx, StoredAxisMeasure, TableAxis, VisIdx,
 };
 pub use builder::{AxisMeasurementType, DefaultTableArgs, ShowHeadings, TableBuilder};
-pub use cells::{Cells, CellsDelegate};
+pub use cells::{
+    Cells, CellsDelegate, EditStart, ScrollTo, AUTO_SIZE, CLEAR_FILTERS, COMMIT_EDIT, HIDE_HEADER,
+    PASTE_FAILED, PIN_COLUMN, RESET_LAYOUT, SCROLL_TO, SHOW_FILTER, SHOW_HIDDEN, SORT_COLUMN,
+    START_EDIT,
+};
+pub use chart_cells::{DataBarCell, ProgressBarCell, SparklineCell};
+pub use choices::ChoiceCell;
+pub use click_cells::{ButtonCell, LinkCell};
+pub use clipboard::{
+    copy_selection, parse_tsv, paste_into_selection, CopiedCells, PasteFailure, PasteReport,
+};
 pub use columns::{
-    column, CellCtx, CellRender, CellRenderExt, DataCompare, EditorFactory, TextCell,
+    column, CellCtx, CellRender, CellRenderExt, DataCompare, EditorFactory, HorizontalAlignment,
+    TextCell, TextOptions, TextOverflow, VerticalAlignment,
 };
 pub use config::TableConfig;
-pub use data::{IndexedData, IndexedItems, Remap, RemapSpec, Remapper, SortDirection};
+pub use data::{IndexedData, IndexedItems, Pin, Remap, RemapSpec, Remapper, SortDirection};
+pub use date_time::{DateTimeCell, DateTimeValue};
+pub use editors::{ParseEditor, TextEditor, EDITOR_ERROR};
+pub use fill::{fill_selection, numeric_series, FillMode};
+pub use filters::distinct_values;
+pub use formatting::{
+    CellStyle, ColorScale, FormatRule, STYLE_BOLD, STYLE_STRIKETHROUGH, STYLE_TEXT_COLOR,
+};
+pub use groups::{ColumnGroup, ColumnGroups, GroupMember};
 pub use headings::{HeadersFromIndices, Headings, SuppliedHeaders};
+pub use menus::HeaderMenus;
+pub use row_moves::{RowMove, RowMoves};
 pub use selection::{IndicesSelection, TableSelection};
+pub use spans::CellSpans;
+pub use stats::{selection_stats, SelectionStats};
 pub use table::{HeaderBuild, Table, TableArgs};
 pub use vis::{
-    AxisName, BandScale, BandScaleFactory, DatumId, DrawableAxis, F64Range, LinearScale, Mark,
-    MarkId, MarkOverrides, MarkProps, MarkShape, OffsetSource, SeriesId, StateName, TextMark, Vis,
-    VisEvent, VisMarks, VisMarksInterp, Visualization,
+    AxisName, BandScale, BandScaleFactory, DatumId, DrawableAxis, F64Range, LinearScale,
+    LinearValue, Mark, MarkId, MarkOverrides, MarkProps, MarkShape, OffsetSource, SeriesId,
+    StateName, TextMark, Vis, VisEvent, VisMarks, VisMarksInterp, Visualization,
 };
 
 #[macro_use]
@@ -0,0 +1,125 @@
+use crate::axis_measure::{AxisPair, LogIdx, TableAxis};
+use crate::cells::{AUTO_SIZE, HIDE_HEADER, PIN_COLUMN, RESET_LAYOUT, SHOW_HIDDEN, SORT_COLUMN};
+use crate::data::{Pin, RemapSpec, SortDirection};
+use druid::menu::{Menu, MenuItem};
+use druid::{Command, Data, EventCtx, Point, Selector, WidgetId};
+use std::any::Any;
+use std::rc::Rc;
+
+// The header a menu was opened on, and the state of it the menu depends on
+#[derive(Clone, Debug)]
+pub(crate) struct MenuHeader {
+    axis: TableAxis,
+    log_idx: LogIdx,
+    sort: Option<SortDirection>,
+    sort_fixed: bool,
+    any_hidden: bool,
+    pinned: Option<Pin>,
+}
+
+impl MenuHeader {
+    pub(crate) fn new(axis: TableAxis, log_idx: LogIdx, specs: &AxisPair<RemapSpec>) -> Self {
+        // Sorting by a column reorders the rows
+        let sorts = &specs[axis.cross_axis()];
+        MenuHeader {
+            axis,
+            log_idx,
+            sort: sorts.sort_of(log_idx).map(|(_, direction)| direction),
+            sort_fixed: sorts.is_sort_fixed(log_idx),
+            any_hidden: !specs[axis].hidden.is_empty(),
+            pinned: specs[axis].pinned(log_idx),
+        }
+    }
+}
+
+fn item<T: Data, P: Any>(
+    label: &'static str,
+    selector: Selector<P>,
+    payload: P,
+    cells: WidgetId,
+) -> MenuItem<T> {
+    MenuItem::new(label).command(Command::new(selector, payload, cells))
+}
+
+fn header_menu<T: Data>(header: &MenuHeader, cells: WidgetId) -> Menu<T> {
+    let MenuHeader {
+        axis,
+        log_idx,
+        sort,
+        sort_fixed,
+        any_hidden,
+        pinned,
+    } = header.clone();
+    let mut menu = Menu::empty();
+    if axis == TableAxis::Columns {
+        let ascending = Some(SortDirection::Ascending);
+        let descending = Some(SortDirection::Descending);
+        menu = menu
+            .entry(
+                item("Sort ascending", SORT_COLUMN, (log_idx, ascending), cells)
+                    .enabled(!sort_fixed && sort != ascending),
+            )
+            .entry(
+                item("Sort descending", SORT_COLUMN, (log_idx, descending), cells)
+                    .enabled(!sort_fixed && sort != descending),
+            )
+            .entry(
+                item("Clear sort", SORT_COLUMN, (log_idx, None), cells)
+                    .enabled(!sort_fixed && sort.is_some()),
+            )
+            .separator();
+    }
+
+    let (hide, show_hidden, auto_size) = match axis {
+        TableAxis::Columns => ("Hide column", "Show hidden columns", "Auto-size column"),
+        TableAxis::Rows => ("Hide row", "Show hidden rows", "Auto-size row"),
+    };
+    menu = menu
+        .entry(item(hide, HIDE_HEADER, (axis, log_idx), cells))
+        .entry(item(show_hidden, SHOW_HIDDEN, axis, cells).enabled(any_hidden))
+        .separator();
+
+    if axis == TableAxis::Columns {
+        menu = menu
+            .entry(
+                item("Pin left", PIN_COLUMN, (log_idx, Some(Pin::Left)), cells)
+                    .enabled(pinned != Some(Pin::Left)),
+            )
+            .entry(
+                item("Pin right", PIN_COLUMN, (log_idx, Some(Pin::Right)), cells)
+                    .enabled(pinned != Some(Pin::Right)),
+            )
+            .entry(item("Unpin", PIN_COLUMN, (log_idx, None), cells).enabled(pinned.is_some()))
+            .separator();
+    }
+
+    menu.entry(item(auto_size, AUTO_SIZE, (axis, log_idx), cells))
+        .entry(item("Reset layout", RESET_LAYOUT, (), cells))
+}
+
+// Context menus on the headers, with their actions sent as commands to the cells.
+// Menus belong to the window, so they are built for the data of the whole app.
+#[derive(Clone)]
+pub struct HeaderMenus {
+    show: Rc<dyn Fn(&mut EventCtx, &MenuHeader, WidgetId, Point)>,
+}
+
+impl HeaderMenus {
+    pub fn new<AppData: Data>() -> Self {
+        HeaderMenus {
+            show: Rc::new(|ctx, header, cells, window_pos| {
+                ctx.show_context_menu(header_menu::<AppData>(header, cells), window_pos)
+            }),
+        }
+    }
+
+    pub(crate) fn show(
+        &self,
+        ctx: &mut EventCtx,
+        header: &MenuHeader,
+        cells: WidgetId,
+        window_pos: Point,
+    ) {
+        (self.show)(ctx, header, cells, window_pos)
+    }
+}
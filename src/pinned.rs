@@ -0,0 +1,462 @@
+use crate::axis_measure::{LogIdx, TableAxis};
+use crate::cells::CellsDelegate;
+use crate::columns::{CellCtx, CellRender, CommitResult, EditorFactory, TextOptions};
+use crate::data::{IndexedData, Remap, RemapSpec, Remapper};
+use crate::formatting::CellStyle;
+use crate::headings::HeadersFromData;
+use crate::table::TableState;
+use druid::piet::PietText;
+use druid::widget::prelude::*;
+use druid::{Data, MouseEvent, Point, Rect, WidgetPod};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// The part of the table's columns a widget shows. Columns pinned to a side are shown in their own
+// region outside the horizontal scroll, and the rest scroll between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ColumnRegion {
+    Left,
+    Scrolling,
+    Right,
+}
+
+// The cells of each region, and how much room the scrolling one has, so a drag or the keyboard
+// focus can move from one region to another
+#[derive(Clone)]
+pub(crate) struct Regions {
+    left: WidgetId,
+    scrolling: WidgetId,
+    right: WidgetId,
+    scrolling_width: Rc<Cell<f64>>,
+    view_height: Rc<Cell<f64>>,
+}
+
+impl Regions {
+    pub(crate) fn new(scrolling: WidgetId) -> Self {
+        Regions {
+            left: WidgetId::next(),
+            scrolling,
+            right: WidgetId::next(),
+            scrolling_width: Default::default(),
+            view_height: Default::default(),
+        }
+    }
+
+    pub(crate) fn id(&self, region: ColumnRegion) -> WidgetId {
+        match region {
+            ColumnRegion::Left => self.left,
+            ColumnRegion::Scrolling => self.scrolling,
+            ColumnRegion::Right => self.right,
+        }
+    }
+
+    pub(crate) fn scrolling_width(&self) -> f64 {
+        self.scrolling_width.get()
+    }
+
+    // How much of the cells is in view, which is how far a page moves
+    pub(crate) fn view_height(&self) -> f64 {
+        self.view_height.get()
+    }
+}
+
+// Lays out a row of the table's regions side by side, the pinned ones as wide as their columns
+// and the scrolling one in the space between them
+pub(crate) struct PinnedColumns<TableData: Data> {
+    left: WidgetPod<TableState<TableData>, Box<dyn Widget<TableState<TableData>>>>,
+    scrolling: WidgetPod<TableState<TableData>, Box<dyn Widget<TableState<TableData>>>>,
+    right: WidgetPod<TableState<TableData>, Box<dyn Widget<TableState<TableData>>>>,
+    regions: Regions,
+    holds_cells: bool,
+}
+
+impl<TableData: Data> PinnedColumns<TableData> {
+    pub(crate) fn new(
+        left: impl Widget<TableState<TableData>> + 'static,
+        scrolling: impl Widget<TableState<TableData>> + 'static,
+        right: impl Widget<TableState<TableData>> + 'static,
+        regions: Regions,
+    ) -> Self {
+        PinnedColumns {
+            left: WidgetPod::new(Box::new(left)),
+            scrolling: WidgetPod::new(Box::new(scrolling)),
+            right: WidgetPod::new(Box::new(right)),
+            regions,
+            holds_cells: false,
+        }
+    }
+
+    // The row of cells, rather than headers, so its height is the view of the cells
+    pub(crate) fn holds_cells(mut self) -> Self {
+        self.holds_cells = true;
+        self
+    }
+
+    fn pods(
+        &mut self,
+    ) -> [&mut WidgetPod<TableState<TableData>, Box<dyn Widget<TableState<TableData>>>>; 3] {
+        [&mut self.left, &mut self.scrolling, &mut self.right]
+    }
+}
+
+impl<TableData: Data> Widget<TableState<TableData>> for PinnedColumns<TableData> {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut TableState<TableData>,
+        env: &Env,
+    ) {
+        for pod in self.pods().iter_mut() {
+            pod.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &TableState<TableData>,
+        env: &Env,
+    ) {
+        for pod in self.pods().iter_mut() {
+            pod.lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &TableState<TableData>,
+        data: &TableState<TableData>,
+        env: &Env,
+    ) {
+        for pod in self.pods().iter_mut() {
+            pod.update(ctx, data, env);
+        }
+        // Pinning, moving or hiding columns changes the widths of the regions
+        if !old_data.remaps.same(&data.remaps) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &TableState<TableData>,
+        env: &Env,
+    ) -> Size {
+        let (left_width, right_width) = data.pinned_widths();
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            data.measures[TableAxis::Columns].total_pixel_length()
+        };
+        let scrolling_width = (width - left_width - right_width).max(0.);
+        self.regions.scrolling_width.set(scrolling_width);
+
+        let mut height: f64 = 0.;
+        let mut x = 0.;
+        for (pod, width) in self
+            .pods()
+            .iter_mut()
+            .zip(&[left_width, scrolling_width, right_width])
+        {
+            let child_bc = BoxConstraints::new(
+                Size::new(*width, bc.min().height),
+                Size::new(*width, bc.max().height),
+            );
+            let size = pod.layout(ctx, &child_bc, data, env);
+            pod.set_layout_rect(ctx, data, env, Rect::from_origin_size((x, 0.), size));
+            height = height.max(size.height);
+            x += width;
+        }
+        let size = bc.constrain(Size::new(width, height));
+        if self.holds_cells {
+            self.regions.view_height.set(size.height);
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &TableState<TableData>, env: &Env) {
+        for pod in self.pods().iter_mut() {
+            let rect = pod.layout_rect();
+            ctx.with_save(|ctx| {
+                ctx.clip(rect);
+                pod.paint(ctx, data, env);
+            });
+        }
+    }
+}
+
+// A renderer, cells delegate or header source used by the widgets of every region at once
+pub(crate) struct Shared<T>(Rc<RefCell<T>>);
+
+impl<T> Shared<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Shared(Rc::new(RefCell::new(inner)))
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T, CR: CellRender<T>> CellRender<T> for Shared<CR> {
+    fn init(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.0.borrow_mut().init(ctx, env)
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, cell: &CellCtx, data: &T, env: &Env) {
+        self.0.borrow().paint(ctx, cell, data, env)
+    }
+
+    fn observes_rows(&self) -> bool {
+        self.0.borrow().observes_rows()
+    }
+
+    fn start_observe(&mut self) {
+        self.0.borrow_mut().start_observe()
+    }
+
+    fn observe(&mut self, data: &T) {
+        self.0.borrow_mut().observe(data)
+    }
+
+    fn end_observe(&mut self) {
+        self.0.borrow_mut().end_observe()
+    }
+
+    fn cell_style(&self, cell: &CellCtx, data: &T) -> Option<CellStyle> {
+        self.0.borrow().cell_style(cell, data)
+    }
+
+    fn event(
+        &self,
+        ctx: &mut EventCtx,
+        cell: &CellCtx,
+        event: &Event,
+        data: &T,
+        env: &Env,
+    ) -> bool {
+        self.0.borrow().event(ctx, cell, event, data, env)
+    }
+
+    fn update_text_options(&mut self, update: &dyn Fn(&mut TextOptions)) {
+        self.0.borrow_mut().update_text_options(update)
+    }
+
+    fn spills(&self, cell: &CellCtx, data: &T) -> bool {
+        self.0.borrow().spills(cell, data)
+    }
+
+    fn is_blank(&self, cell: &CellCtx, data: &T) -> bool {
+        self.0.borrow().is_blank(cell, data)
+    }
+
+    fn cell_tooltip(
+        &self,
+        text: &mut PietText,
+        size: Size,
+        cell: &CellCtx,
+        data: &T,
+        env: &Env,
+    ) -> Option<String> {
+        self.0.borrow().cell_tooltip(text, size, cell, data, env)
+    }
+
+    fn cell_text(&self, cell: &CellCtx, data: &T) -> Option<String> {
+        self.0.borrow().cell_text(cell, data)
+    }
+}
+
+impl<RowData, EF: EditorFactory<RowData>> EditorFactory<RowData> for Shared<EF> {
+    fn make_editor(&mut self, ctx: &CellCtx) -> Option<Box<dyn Widget<RowData>>> {
+        self.0.borrow_mut().make_editor(ctx)
+    }
+
+    fn can_commit(&self, ctx: &CellCtx, original: &RowData, edited: &RowData) -> CommitResult {
+        self.0.borrow().can_commit(ctx, original, edited)
+    }
+
+    fn validate(&self, ctx: &CellCtx, data: &RowData) -> CommitResult {
+        self.0.borrow().validate(ctx, data)
+    }
+
+    fn paste_text(&self, ctx: &CellCtx, data: &mut RowData, text: &str) -> CommitResult {
+        self.0.borrow().paste_text(ctx, data, text)
+    }
+
+    fn fill_series(&self, ctx: &CellCtx, source: &[String], count: usize) -> Option<Vec<String>> {
+        self.0.borrow().fill_series(ctx, source, count)
+    }
+}
+
+impl<TableData: IndexedData, R: Remapper<TableData>> Remapper<TableData> for Shared<R>
+where
+    TableData::Item: Data,
+{
+    fn sort_fixed(&self, idx: usize) -> bool {
+        self.0.borrow().sort_fixed(idx)
+    }
+
+    fn initial_spec(&self) -> RemapSpec {
+        self.0.borrow().initial_spec()
+    }
+
+    fn remap_items(&self, table_data: &TableData, remap_spec: &RemapSpec) -> Remap {
+        self.0.borrow().remap_items(table_data, remap_spec)
+    }
+}
+
+impl<TableData: IndexedData, CD: CellsDelegate<TableData>> CellsDelegate<TableData> for Shared<CD>
+where
+    TableData::Item: Data,
+{
+    fn number_of_columns_in_data(&self, data: &TableData) -> usize {
+        self.0.borrow().number_of_columns_in_data(data)
+    }
+
+    fn row_style(&self, data: &TableData::Item) -> Option<CellStyle> {
+        self.0.borrow().row_style(data)
+    }
+
+    fn column_header(&self, col: LogIdx) -> Option<String> {
+        self.0.borrow().column_header(col)
+    }
+
+    fn merges_cells(&self) -> bool {
+        self.0.borrow().merges_cells()
+    }
+
+    fn merges_repeats(&self, col: LogIdx) -> bool {
+        self.0.borrow().merges_repeats(col)
+    }
+
+    fn column_span(&self, col: LogIdx, data: &TableData::Item) -> usize {
+        self.0.borrow().column_span(col, data)
+    }
+}
+
+impl<HS: HeadersFromData> HeadersFromData for Shared<HS> {
+    type TableData = HS::TableData;
+    type Header = HS::Header;
+    type Headers = HS::Headers;
+
+    fn get_headers(&self, table_data: &Self::TableData) -> Self::Headers {
+        self.0.borrow().get_headers(table_data)
+    }
+}
+
+// Where a point over one region is along all the columns, as if the regions were laid end to end
+// as they are shown. A drag started in one region then carries on into the others.
+pub(crate) fn column_point<TableData: Data>(
+    data: &TableState<TableData>,
+    region: ColumnRegion,
+    scrolling_width: f64,
+    pos: Point,
+) -> Point {
+    let (left_width, right_width) = data.pinned_widths();
+    let total = data.measures[TableAxis::Columns].total_pixel_length();
+    // Across the view of the table, from its left edge
+    let view_x = match region {
+        ColumnRegion::Left => pos.x,
+        ColumnRegion::Scrolling => pos.x - data.scroll_x + left_width,
+        ColumnRegion::Right => pos.x + left_width + scrolling_width,
+    };
+    let x = if view_x < left_width {
+        view_x
+    } else if right_width > 0. && view_x >= left_width + scrolling_width {
+        total - right_width + view_x - left_width - scrolling_width
+    } else {
+        view_x + data.scroll_x
+    };
+    Point::new(x, pos.y)
+}
+
+// A mouse event over a region, moved to where it is along all the columns
+pub(crate) fn column_event<TableData: Data>(
+    data: &TableState<TableData>,
+    region: ColumnRegion,
+    regions: Option<&Regions>,
+    event: &Event,
+) -> Event {
+    // Without the other regions, the scrolling one is all there is
+    let scrolling_width = regions.map_or(f64::INFINITY, |regions| regions.scrolling_width());
+    let moved = |me: &MouseEvent| {
+        let mut me = me.clone();
+        me.pos = column_point(data, region, scrolling_width, me.pos);
+        me
+    };
+    match event {
+        Event::MouseDown(me) => Event::MouseDown(moved(me)),
+        Event::MouseUp(me) => Event::MouseUp(moved(me)),
+        Event::MouseMove(me) => Event::MouseMove(moved(me)),
+        Event::Wheel(me) => Event::Wheel(moved(me)),
+        _ => event.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::axis_measure::{AxisMeasure, AxisPair, VisIdx};
+    use crate::data::Pin;
+    use crate::AxisMeasurementType;
+    use druid::im::Vector;
+
+    // Ten columns of 100 pixels, with one pinned each side and the rest scrolled by 250
+    fn pinned_table() -> TableState<Vector<u32>> {
+        let measures = AxisPair::new(
+            AxisMeasure::new(AxisMeasurementType::Uniform, 20.),
+            AxisMeasure::new(AxisMeasurementType::Uniform, 100.),
+        );
+        let mut data = TableState::new(Vector::<u32>::new(), measures);
+        let mut spec = RemapSpec::default();
+        spec.pin(LogIdx(3), Some(Pin::Left));
+        spec.pin(LogIdx(0), Some(Pin::Right));
+        data.remaps[TableAxis::Columns] = spec.remap_placements(LogIdx(9));
+        data.remap_specs[TableAxis::Columns] = spec;
+        data.measures[TableAxis::Columns].set_axis_properties(
+            0.,
+            10,
+            &data.remaps[TableAxis::Columns],
+        );
+        data.measures[TableAxis::Rows].set_axis_properties(0., 100, &Remap::Pristine);
+        data.scroll_x = 250.;
+        data
+    }
+
+    #[test]
+    fn points_carry_on_across_regions() {
+        let data = pinned_table();
+        assert_eq!(data.pinned_widths(), (100., 100.));
+        assert_eq!(data.region_of_column(VisIdx(0)), ColumnRegion::Left);
+        assert_eq!(data.region_of_column(VisIdx(5)), ColumnRegion::Scrolling);
+        assert_eq!(data.region_of_column(VisIdx(9)), ColumnRegion::Right);
+
+        // With 400 pixels between the pinned columns
+        let at = |region, x| column_point(&data, region, 400., Point::new(x, 0.)).x;
+        assert_eq!(at(ColumnRegion::Left, 50.), 50.);
+        assert_eq!(at(ColumnRegion::Scrolling, 260.), 360.);
+        assert_eq!(at(ColumnRegion::Right, 30.), 930.);
+        // Dragged out of the scrolling region into the pinned ones
+        assert_eq!(at(ColumnRegion::Scrolling, 240.), 90.);
+        assert_eq!(at(ColumnRegion::Scrolling, 660.), 910.);
+    }
+
+    #[test]
+    fn scrolls_just_enough_to_show_columns_and_rows() {
+        let data = pinned_table();
+        // With 400 pixels between the pinned columns, showing 350 to 750
+        let show = |axis, vis| data.scroll_to_show(axis, VisIdx(vis), 400.);
+        assert_eq!(show(TableAxis::Columns, 1), Some(0.));
+        assert_eq!(show(TableAxis::Columns, 5), Some(250.));
+        assert_eq!(show(TableAxis::Columns, 8), Some(400.));
+        assert_eq!(show(TableAxis::Columns, 0), None);
+        assert_eq!(show(TableAxis::Rows, 50), Some(620.));
+    }
+}
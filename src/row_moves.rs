@@ -0,0 +1,122 @@
+use crate::axis_measure::LogIdx;
+use druid::im::Vector;
+use std::rc::Rc;
+
+// Rows dragged from where they are in the data to just before another row, or to the end
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowMove {
+    pub rows: Vec<LogIdx>, // In the order they are shown
+    pub before: Option<LogIdx>,
+}
+
+impl RowMove {
+    // Where the first of the rows is in the data once they have moved
+    pub fn destination(&self, len: usize) -> usize {
+        match self.before {
+            Some(before) => before.0 - self.rows.iter().filter(|row| **row < before).count(),
+            None => len.saturating_sub(self.rows.len()),
+        }
+    }
+
+    pub fn apply_to<T: Clone>(&self, items: &mut Vector<T>) {
+        let moving: Vec<T> = self
+            .rows
+            .iter()
+            .filter_map(|row| items.get(row.0).cloned())
+            .collect();
+        let mut kept: Vector<T> = items
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.rows.contains(&LogIdx(*idx)))
+            .map(|(_, item)| item.clone())
+            .collect();
+        let at = self.destination(items.len()).min(kept.len());
+        for (offset, item) in moving.into_iter().enumerate() {
+            kept.insert(at + offset, item);
+        }
+        *items = kept;
+    }
+}
+
+// Dragging row headers moves the rows in the data itself, for lists whose order matters like
+// queues of work. The app can refuse a move, or make it in its own way.
+pub struct RowMoves<TableData> {
+    allow: Option<Rc<dyn Fn(&TableData, &RowMove) -> bool>>,
+    apply: Rc<dyn Fn(&mut TableData, &RowMove)>,
+}
+
+impl<TableData> Clone for RowMoves<TableData> {
+    fn clone(&self) -> Self {
+        RowMoves {
+            allow: self.allow.clone(),
+            apply: self.apply.clone(),
+        }
+    }
+}
+
+impl<TableData> RowMoves<TableData> {
+    pub fn new(apply: impl Fn(&mut TableData, &RowMove) + 'static) -> Self {
+        RowMoves {
+            allow: None,
+            apply: Rc::new(apply),
+        }
+    }
+
+    // Only moves this returns true for are made
+    pub fn allow(mut self, allow: impl Fn(&TableData, &RowMove) -> bool + 'static) -> Self {
+        self.allow = Some(Rc::new(allow));
+        self
+    }
+
+    // Returns whether the move was made
+    pub(crate) fn make(&self, data: &mut TableData, row_move: &RowMove) -> bool {
+        if self
+            .allow
+            .as_ref()
+            .map_or(true, |allow| allow(data, row_move))
+        {
+            (self.apply)(data, row_move);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<T: Clone + 'static> RowMoves<Vector<T>> {
+    pub fn vector() -> Self {
+        RowMoves::new(|items: &mut Vector<T>, row_move| row_move.apply_to(items))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use druid::im::vector;
+
+    #[test]
+    fn rows_move_before_another_or_to_the_end() {
+        let moves = RowMoves::vector();
+        let mut items = vector!['a', 'b', 'c', 'd', 'e'];
+        let down = RowMove {
+            rows: vec![LogIdx(0), LogIdx(1)],
+            before: Some(LogIdx(4)),
+        };
+        assert_eq!(down.destination(5), 2);
+        assert!(moves.make(&mut items, &down));
+        assert_eq!(items, vector!['c', 'd', 'a', 'b', 'e']);
+
+        let to_end = RowMove {
+            rows: vec![LogIdx(1)],
+            before: None,
+        };
+        assert_eq!(to_end.destination(5), 4);
+        assert!(moves.make(&mut items, &to_end));
+        assert_eq!(items, vector!['c', 'a', 'b', 'e', 'd']);
+
+        // A move the app refuses leaves the rows alone
+        let moves = moves.allow(|_, row_move| row_move.before.is_some());
+        assert!(!moves.make(&mut items, &to_end));
+        assert_eq!(items, vector!['c', 'a', 'b', 'e', 'd']);
+    }
+}
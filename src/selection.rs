@@ -1,6 +1,8 @@
 use crate::axis_measure::{AxisPair, LogIdx, TableAxis, VisIdx, VisOffset};
 use crate::AxisMeasure;
+use druid::im::{vector, Vector};
 use druid::kurbo::{Point, Rect, Size};
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::iter::Map;
 use std::ops::{Add, Index, IndexMut, RangeInclusive};
@@ -60,7 +62,7 @@ impl AxisPair<f64> {
 }
 
 // For now a rect only makes sense in VisIdx - In LogIdx any list of points is possible due to remapping
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CellRect {
     pub start_row: VisIdx,
     pub end_row: VisIdx,
@@ -94,12 +96,26 @@ impl CellRect {
         VisIdx::range_inc_iter(self.start_col, self.end_col)
     }
 
-    fn contains_cell(&self, cell_addr: &AxisPair<VisIdx>) -> bool {
+    // The smallest rect containing both
+    pub fn union(&self, other: &CellRect) -> CellRect {
+        CellRect::new(
+            (
+                self.start_row.min(other.start_row),
+                self.end_row.max(other.end_row),
+            ),
+            (
+                self.start_col.min(other.start_col),
+                self.end_col.max(other.end_col),
+            ),
+        )
+    }
+
+    pub(crate) fn contains_cell(&self, cell_addr: &AxisPair<VisIdx>) -> bool {
         self.contains_idx(TableAxis::Columns, cell_addr.col)
             && self.contains_idx(TableAxis::Rows, cell_addr.row)
     }
 
-    fn range(&self, axis: TableAxis) -> (VisIdx, VisIdx) {
+    pub(crate) fn range(&self, axis: TableAxis) -> (VisIdx, VisIdx) {
         match axis {
             TableAxis::Rows => (self.start_row, self.end_row),
             TableAxis::Columns => (self.start_col, self.end_col),
@@ -128,6 +144,21 @@ impl CellRect {
     }
 }
 
+// Moves from a cell, jumping over the merged block it is in as a whole
+fn move_past_span(
+    vis: &AxisPair<VisIdx>,
+    axis: TableAxis,
+    amount: VisOffset,
+    cell_demap: &impl CellDemap,
+) -> AxisPair<VisIdx> {
+    let mut from = vis.clone();
+    if let Some(span) = cell_demap.span_of(vis) {
+        let (start, end) = span.range(axis);
+        from[axis] = if amount.0 > 0 { end } else { start };
+    }
+    from.move_by(axis, amount)
+}
+
 trait AxisPairMove<O> {
     fn move_by(&self, axis: TableAxis, amount: O) -> Self;
 }
@@ -214,6 +245,19 @@ impl CellRange {
         let (a, b) = VisIdx::ascending(self.focus.vis[axis], self.extent.vis[axis]);
         a <= vis_idx && vis_idx <= b
     }
+
+    pub fn to_cell_rect(&self) -> CellRect {
+        CellRect::new(
+            VisIdx::ascending(
+                self.focus.vis[TableAxis::Rows],
+                self.extent.vis[TableAxis::Rows],
+            ),
+            VisIdx::ascending(
+                self.focus.vis[TableAxis::Columns],
+                self.extent.vis[TableAxis::Columns],
+            ),
+        )
+    }
 }
 
 impl SingleSlice {
@@ -237,7 +281,8 @@ pub enum IndicesSelection {
     NoSelection,
     Single(VisIdx),
     Range { focus: VisIdx, extent: VisIdx },
-    //Range(from, to)
+    // From selections added with Ctrl
+    Many(Vec<IndicesSelection>),
 }
 
 impl IndicesSelection {
@@ -248,6 +293,9 @@ impl IndicesSelection {
                 let (min, max) = VisIdx::ascending(*focus, *extent);
                 vis_idx >= min && vis_idx <= max
             }
+            IndicesSelection::Many(selections) => selections
+                .iter()
+                .any(|selection| selection.vis_index_selected(vis_idx)),
             _ => false,
         }
     }
@@ -259,7 +307,10 @@ pub enum TableSelection {
     SingleCell(SingleCell),
     SingleSlice(SingleSlice),
     CellRange(CellRange),
-    SliceRange(SliceRange), //  Discontiguous
+    SliceRange(SliceRange),
+    // Discontiguous selections, added to with Ctrl. The last layer is the current one, which
+    // has the focus and is the one extended. No layer is itself layered.
+    Layers(Vector<TableSelection>),
 }
 
 impl Default for TableSelection {
@@ -271,6 +322,11 @@ impl Default for TableSelection {
 pub trait CellDemap {
     fn get_log_idx(&self, axis: TableAxis, vis: &VisIdx) -> Option<LogIdx>;
 
+    // The merged block of cells this one is part of
+    fn span_of(&self, _cell: &AxisPair<VisIdx>) -> Option<CellRect> {
+        None
+    }
+
     fn get_log_cell(&self, vis: &AxisPair<VisIdx>) -> Option<AxisPair<LogIdx>> {
         self.get_log_idx(TableAxis::Rows, &vis.row)
             .map(|row| {
@@ -318,7 +374,7 @@ impl TableSelection {
                     .map(|log| Self::SingleCell(SingleCell::new(vis_origin, log)))
             }
             Self::SingleCell(SingleCell { vis, .. }) => {
-                let new_vis = vis.move_by(axis, amount); // Should check upper bounds
+                let new_vis = move_past_span(vis, axis, amount, cell_demap); // Should check upper bounds
                 cell_demap
                     .get_log_cell(&new_vis)
                     .map(|log| Self::SingleCell(SingleCell::new(new_vis, log)))
@@ -330,7 +386,7 @@ impl TableSelection {
                 })
             }
             Self::CellRange(CellRange { focus, .. }) => {
-                let new_vis = focus.vis.move_by(axis, amount);
+                let new_vis = move_past_span(&focus.vis, axis, amount, cell_demap);
                 cell_demap
                     .get_log_cell(&new_vis)
                     .map(|log| Self::SingleCell(SingleCell::new(new_vis, log)))
@@ -344,10 +400,18 @@ impl TableSelection {
                     ))
                 })
             }
+            // Moving on leaves the other layers behind
+            Self::Layers(_) => self.current().move_focus(axis, amount, cell_demap),
         }
     }
 
     pub fn move_extent(&self, sel: TableSelection) -> Option<TableSelection> {
+        if let Self::Layers(layers) = self {
+            let mut layers = layers.clone();
+            let current = layers.pop_back()?.move_extent(sel)?;
+            layers.push_back(current);
+            return Some(Self::Layers(layers));
+        }
         let res = match (self, &sel) {
             (Self::SingleCell(cur), Self::SingleCell(ext)) => {
                 Some(Self::CellRange(CellRange::new(cur.clone(), ext.clone())))
@@ -355,6 +419,18 @@ impl TableSelection {
             (Self::CellRange(CellRange { focus, .. }), Self::SingleCell(ext)) => {
                 Some(Self::CellRange(CellRange::new(focus.clone(), ext.clone())))
             }
+            // Whole rows or columns stay whole, extended along their axis
+            (Self::SingleSlice(SingleSlice { axis, focus }), Self::SingleCell(ext))
+            | (
+                Self::SliceRange(SliceRange {
+                    axis,
+                    range: CellRange { focus, .. },
+                }),
+                Self::SingleCell(ext),
+            ) => Some(Self::SliceRange(SliceRange {
+                axis: *axis,
+                range: CellRange::new(focus.clone(), ext.clone()),
+            })),
             _ => None,
         };
         //log::info!("Move extent: \ncur :\n{:?}  \nextent:\n{:?} \nresult:\n{:?}", self, sel, res);
@@ -362,7 +438,11 @@ impl TableSelection {
     }
 
     pub fn extend_in_axis(&mut self, axis: TableAxis, vis: VisIdx, cell_demap: &impl CellDemap) {
-        if let Some(focus) = self.focus() {
+        if let Self::Layers(layers) = self {
+            if let Some(current) = layers.back_mut() {
+                current.extend_in_axis(axis, vis, cell_demap);
+            }
+        } else if let Some(focus) = self.focus() {
             let vis_addr = AxisPair::new_for_axis(axis, vis, Default::default());
 
             if let Some(log_addr) = cell_demap.get_log_cell(&vis_addr) {
@@ -404,9 +484,90 @@ impl TableSelection {
             .flatten()
     }
 
+    // Selects the cells of the rect, keeping the focus if it is in one of its corners
+    pub fn select_rect(
+        &self,
+        rect: &CellRect,
+        cell_demap: &impl CellDemap,
+    ) -> Option<TableSelection> {
+        let opposite = |axis: TableAxis, at: VisIdx| {
+            let (start, end) = rect.range(axis);
+            if at == start {
+                Some(end)
+            } else if at == end {
+                Some(start)
+            } else {
+                None
+            }
+        };
+        let (focus, extent) = self
+            .vis_focus()
+            .and_then(|focus| {
+                let extent = AxisPair::new(
+                    opposite(TableAxis::Rows, focus.row)?,
+                    opposite(TableAxis::Columns, focus.col)?,
+                );
+                Some((focus.clone(), extent))
+            })
+            .unwrap_or_else(|| {
+                (
+                    AxisPair::new(rect.start_row, rect.start_col),
+                    AxisPair::new(rect.end_row, rect.end_col),
+                )
+            });
+        let cell = |vis: AxisPair<VisIdx>| {
+            cell_demap
+                .get_log_cell(&vis)
+                .map(|log| SingleCell::new(vis, log))
+        };
+        Some(TableSelection::CellRange(CellRange::new(
+            cell(focus)?,
+            cell(extent)?,
+        )))
+    }
+
     pub fn add_selection(&self, sel: TableSelection) -> Option<TableSelection> {
-        // Todo selection layers
-        Some(sel)
+        let mut layers = match self {
+            Self::NoSelection => return Some(sel),
+            Self::Layers(layers) => layers.clone(),
+            _ => vector![self.clone()],
+        };
+        match sel {
+            Self::NoSelection => (),
+            Self::Layers(added) => layers.append(added),
+            _ => layers.push_back(sel),
+        }
+        Some(Self::Layers(layers))
+    }
+
+    // Adds a whole row or column, as Ctrl clicking a header does
+    pub fn add_in_axis(&mut self, axis: TableAxis, vis: VisIdx, cell_demap: &impl CellDemap) {
+        let mut added = Self::NoSelection;
+        added.select_in_axis(axis, vis, cell_demap);
+        if let Some(sel) = self.add_selection(added) {
+            *self = sel
+        }
+    }
+
+    // The layer the focus is in, which is the whole selection unless some were added with Ctrl
+    pub fn current(&self) -> &TableSelection {
+        match self {
+            Self::Layers(layers) => layers.back().unwrap_or(self),
+            _ => self,
+        }
+    }
+
+    // Swaps the current layer for another, keeping the ones added before it
+    pub fn replace_current(&self, sel: TableSelection) -> TableSelection {
+        match self {
+            Self::Layers(layers) if !layers.is_empty() => {
+                let mut layers = layers.clone();
+                layers.pop_back();
+                layers.push_back(sel);
+                Self::Layers(layers)
+            }
+            _ => sel,
+        }
     }
 
     pub fn has_focus(&self) -> bool {
@@ -427,6 +588,7 @@ impl TableSelection {
                 range: CellRange { focus, .. },
                 ..
             }) => Some(focus),
+            Self::Layers(layers) => layers.back()?.focus(),
         }
     }
 
@@ -434,10 +596,23 @@ impl TableSelection {
         self.focus().map(|x| &x.vis)
     }
 
+    // The corner of the selection away from the focus, which is moved to extend it
+    pub fn vis_extent(&self) -> Option<&AxisPair<VisIdx>> {
+        match self {
+            Self::CellRange(CellRange { extent, .. })
+            | Self::SliceRange(SliceRange {
+                range: CellRange { extent, .. },
+                ..
+            }) => Some(&extent.vis),
+            Self::Layers(layers) => layers.back()?.vis_extent(),
+            _ => self.vis_focus(),
+        }
+    }
+
     pub fn to_axis_selection(
         &self,
         for_axis: TableAxis,
-        _cell_demap: &impl CellDemap,
+        cell_demap: &impl CellDemap,
     ) -> IndicesSelection {
         match self {
             Self::NoSelection => IndicesSelection::NoSelection,
@@ -466,6 +641,12 @@ impl TableSelection {
                     IndicesSelection::NoSelection
                 }
             }
+            Self::Layers(layers) => IndicesSelection::Many(
+                layers
+                    .iter()
+                    .map(|layer| layer.to_axis_selection(for_axis, cell_demap))
+                    .collect(),
+            ),
         }
     }
 
@@ -482,18 +663,9 @@ impl TableSelection {
                     vec![sl.to_cell_rect(bounding.range(sl.axis.cross_axis()))],
                 )
             }
-            TableSelection::CellRange(CellRange { focus, extent }) => {
-                let row =
-                    VisIdx::ascending(focus.vis[TableAxis::Rows], extent.vis[TableAxis::Rows]);
-                let col = VisIdx::ascending(
-                    focus.vis[TableAxis::Columns],
-                    extent.vis[TableAxis::Columns],
-                );
-
-                let cell_rect = CellRect::new(row, col);
-
+            TableSelection::CellRange(range) => {
                 //TODO: Intersection with bounding box
-                DrawableSelections::new(Some(focus.vis), vec![cell_rect])
+                DrawableSelections::new(Some(range.focus.vis.clone()), vec![range.to_cell_rect()])
             }
             TableSelection::SliceRange(sr)
                 if bounding.contains_idx(sr.axis, sr.range.focus.vis[sr.axis])
@@ -504,10 +676,65 @@ impl TableSelection {
                     vec![sr.to_cell_rect(bounding.range(sr.axis.cross_axis()))],
                 )
             }
+            // Every layer is shaded, even single cells, with the focus drawn for the current one
+            TableSelection::Layers(layers) => {
+                let current = self.current().get_drawable_selections(bounding);
+                let mut ranges: Vec<CellRect> = layers
+                    .iter()
+                    .take(layers.len().saturating_sub(1))
+                    .flat_map(|layer| layer.selected_rects(bounding))
+                    .collect();
+                ranges.extend(current.ranges);
+                DrawableSelections::new(current.focus, ranges)
+            }
             _ => DrawableSelections::new(None, Default::default()),
         }
     }
 
+    // The rectangles of selected cells within the bounds. A single cell is its own rectangle.
+    pub fn selected_rects(&self, bounding: &CellRect) -> Vec<CellRect> {
+        if let Self::Layers(layers) = self {
+            return layers
+                .iter()
+                .flat_map(|layer| layer.selected_rects(bounding))
+                .collect();
+        }
+        let drawable = self.get_drawable_selections(bounding);
+        if drawable.ranges.is_empty() {
+            drawable
+                .focus
+                .map(|focus| CellRect::point(focus.row, focus.col))
+                .into_iter()
+                .collect()
+        } else {
+            drawable.ranges
+        }
+    }
+
+    // The smallest rectangle holding everything selected within the bounds
+    pub fn bounding_rect(&self, bounding: &CellRect) -> Option<CellRect> {
+        let mut rects = self.selected_rects(bounding);
+        let last = rects.pop()?;
+        Some(rects.iter().fold(last, |acc, rect| acc.union(rect)))
+    }
+
+    // Every selected cell within the bounds once, by row then column, however the layers overlap
+    pub fn selected_cells(&self, bounding: &CellRect) -> Vec<AxisPair<VisIdx>> {
+        let cells: BTreeSet<(VisIdx, VisIdx)> = self
+            .selected_rects(bounding)
+            .iter()
+            .flat_map(|rect| {
+                rect.rows()
+                    .flat_map(move |row| rect.cols().map(move |col| (row, col)))
+            })
+            .filter(|(row, col)| bounding.contains_cell(&AxisPair::new(*row, *col)))
+            .collect();
+        cells
+            .into_iter()
+            .map(|(row, col)| AxisPair::new(row, col))
+            .collect()
+    }
+
     pub fn fully_selects_heading(&self, in_axis: TableAxis, idx: VisIdx) -> bool {
         match self {
             TableSelection::SingleSlice(SingleSlice { axis, focus })
@@ -520,6 +747,9 @@ impl TableSelection {
             {
                 true
             }
+            TableSelection::Layers(layers) => layers
+                .iter()
+                .any(|layer| layer.fully_selects_heading(in_axis, idx)),
             _ => false,
         }
     }
@@ -533,6 +763,17 @@ impl TableSelection {
                 let (a, b) = VisIdx::ascending(range.focus.vis[in_axis], range.extent.vis[in_axis]);
                 Some(VisIdx::range_inc_iter(a, b).collect())
             }
+            // Only when every layer is whole rows or columns
+            TableSelection::Layers(layers) => {
+                let selected: BTreeSet<VisIdx> = layers
+                    .iter()
+                    .map(|layer| layer.fully_selected_on_axis(in_axis))
+                    .collect::<Option<Vec<_>>>()?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                Some(selected.into_iter().collect())
+            }
             _ => None,
         }
     }
@@ -0,0 +1,256 @@
+use crate::axis_measure::{AxisPair, LogIdx, TableAxis, VisIdx, VisOffset};
+use crate::cells::CellsDelegate;
+use crate::columns::CellCtx;
+use crate::data::{IndexedData, Remap};
+use crate::selection::{CellDemap, CellRect, SingleCell};
+use druid::Data;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Runs of a repeated value down each column that merges them, as first and last visual rows.
+// Finding them walks whole columns, so they are kept until the data or its order changes.
+#[derive(Debug, Default)]
+pub(crate) struct RepeatRuns {
+    runs: HashMap<LogIdx, Vec<(VisIdx, VisIdx)>>,
+}
+
+impl RepeatRuns {
+    pub(crate) fn find<TableData, CD>(
+        delegate: &CD,
+        data: &TableData,
+        remaps: &AxisPair<Remap>,
+    ) -> RepeatRuns
+    where
+        TableData: IndexedData<Idx = LogIdx>,
+        TableData::Item: Data,
+        CD: CellsDelegate<TableData>,
+    {
+        let mut runs = HashMap::new();
+        if !delegate.merges_cells() {
+            return RepeatRuns { runs };
+        }
+        let spans = CellSpans::with_runs(delegate, data, remaps, Default::default());
+        for col in VisIdx::range_inc_iter(VisIdx(0), spans.last.col) {
+            let log_col = match remaps[TableAxis::Columns].get_log_idx(col) {
+                Some(log_col) if delegate.merges_repeats(log_col) => log_col,
+                _ => continue,
+            };
+            let mut col_runs = Vec::new();
+            let mut start = VisIdx(0);
+            let mut prev = None;
+            for row in VisIdx::range_inc_iter(VisIdx(0), spans.last.row) {
+                let text = spans.text(AxisPair::new(row, col));
+                if text != prev {
+                    if prev.is_some() && row > start + VisOffset(1) {
+                        col_runs.push((start, row + VisOffset(-1)));
+                    }
+                    start = row;
+                }
+                prev = text;
+            }
+            if prev.is_some() && spans.last.row > start {
+                col_runs.push((start, spans.last.row));
+            }
+            runs.insert(log_col, col_runs);
+        }
+        RepeatRuns { runs }
+    }
+
+    // The runs in the column that overlap the rows from start to end
+    fn overlapping(&self, col: LogIdx, start: VisIdx, end: VisIdx) -> &[(VisIdx, VisIdx)] {
+        let runs = self.runs.get(&col).map_or(&[][..], |runs| &runs[..]);
+        let first = runs.partition_point(|(_, run_end)| *run_end < start);
+        let last = runs.partition_point(|(run_start, _)| *run_start <= end);
+        &runs[first..last.max(first)]
+    }
+}
+
+// Finds merged cells in the table as it is shown. Spans follow the order of the rows and
+// columns on screen. Spans across columns are worked out when needed, while runs down columns
+// are looked up from those found beforehand.
+pub struct CellSpans<'a, TableData, CD> {
+    delegate: &'a CD,
+    data: &'a TableData,
+    remaps: &'a AxisPair<Remap>,
+    last: AxisPair<VisIdx>,
+    runs: Rc<RepeatRuns>,
+}
+
+impl<'a, TableData, CD> CellSpans<'a, TableData, CD>
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    pub fn new(delegate: &'a CD, data: &'a TableData, remaps: &'a AxisPair<Remap>) -> Self {
+        let runs = Rc::new(RepeatRuns::find(delegate, data, remaps));
+        Self::with_runs(delegate, data, remaps, runs)
+    }
+
+    pub(crate) fn with_runs(
+        delegate: &'a CD,
+        data: &'a TableData,
+        remaps: &'a AxisPair<Remap>,
+        runs: Rc<RepeatRuns>,
+    ) -> Self {
+        let last = AxisPair::new(
+            remaps[TableAxis::Rows].max_vis_idx(data.idx_len()),
+            remaps[TableAxis::Columns].max_vis_idx(delegate.number_of_columns_in_data(data)),
+        );
+        CellSpans {
+            delegate,
+            data,
+            remaps,
+            last,
+            runs,
+        }
+    }
+
+    fn cell(&self, vis: AxisPair<VisIdx>) -> Option<SingleCell> {
+        if vis.row > self.last.row || vis.col > self.last.col {
+            return None;
+        }
+        let log = self.remaps.get_log_cell(&vis)?;
+        Some(SingleCell::new(vis, log))
+    }
+
+    fn column_span(&self, vis: AxisPair<VisIdx>) -> usize {
+        self.cell(vis)
+            .and_then(|cell| {
+                self.data.with(cell.log.row, |row| {
+                    self.delegate.column_span(cell.log.col, row)
+                })
+            })
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    // Blank cells are never merged
+    fn text(&self, vis: AxisPair<VisIdx>) -> Option<String> {
+        let cell = self.cell(vis)?;
+        self.data
+            .with(cell.log.row, |row| {
+                self.delegate.cell_text(&CellCtx::Cell(&cell), row)
+            })
+            .flatten()
+            .filter(|text| !text.is_empty())
+    }
+
+    // The merged blocks with any of their cells in the rect
+    pub fn spans_in(&self, rect: &CellRect) -> Vec<CellRect> {
+        let mut spans = Vec::new();
+        if !self.delegate.merges_cells() {
+            return spans;
+        }
+
+        // Across columns, including from cells to the left of the rect that reach into it
+        for row in rect.rows() {
+            let mut col = VisIdx(0);
+            while col <= rect.end_col && col <= self.last.col {
+                let span = self.column_span(AxisPair::new(row, col));
+                let end = (col + VisOffset(span as isize - 1)).min(self.last.col);
+                if end > col && end >= rect.start_col {
+                    spans.push(CellRect::new((row, row), (col, end)));
+                }
+                col = end + VisOffset(1);
+            }
+        }
+
+        // Down columns that merge repeated values, including runs starting above the rect
+        for col in rect.cols() {
+            if let Some(log_col) = self.remaps[TableAxis::Columns].get_log_idx(col) {
+                for (start, end) in self.runs.overlapping(log_col, rect.start_row, rect.end_row) {
+                    spans.push(CellRect::new((*start, *end), (col, col)));
+                }
+            }
+        }
+        spans
+    }
+}
+
+impl<'a, TableData, CD> CellDemap for CellSpans<'a, TableData, CD>
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    fn get_log_idx(&self, axis: TableAxis, vis: &VisIdx) -> Option<LogIdx> {
+        self.remaps.get_log_idx(axis, vis)
+    }
+
+    fn span_of(&self, cell: &AxisPair<VisIdx>) -> Option<CellRect> {
+        self.spans_in(&CellRect::point(cell.row, cell.col))
+            .into_iter()
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::{column, CellRenderExt, ProvidedColumns, TextCell};
+    use crate::selection::TableSelection;
+    use crate::test_fixtures::{at, cell, pristine_remaps};
+    use druid::im::{vector, Vector};
+
+    #[test]
+    fn spans_merge_repeats_and_columns() {
+        type Row = (String, String, String);
+        let columns: ProvidedColumns<Vector<Row>, _> = ProvidedColumns::new(vec![
+            column("Group", TextCell::new().lens(lens!(Row, 0))).merge_repeats(),
+            column("Name", TextCell::new().lens(lens!(Row, 1))).span_columns(|row: &Row| {
+                if row.2.is_empty() {
+                    2
+                } else {
+                    1
+                }
+            }),
+            column("Note", TextCell::new().lens(lens!(Row, 2))),
+        ]);
+        let data: Vector<Row> = vector![
+            ("a".into(), "one".into(), "x".into()),
+            ("a".into(), "two".into(), "x".into()),
+            ("a".into(), "Section".into(), "".into()),
+            ("b".into(), "three".into(), "x".into())
+        ];
+        let remaps = pristine_remaps();
+        let spans = CellSpans::new(&columns, &data, &remaps);
+
+        assert_eq!(
+            spans.span_of(&at(1, 0)),
+            Some(CellRect::new(
+                (VisIdx(0), VisIdx(2)),
+                (VisIdx(0), VisIdx(0))
+            ))
+        );
+        assert_eq!(spans.span_of(&at(3, 0)), None);
+        assert_eq!(
+            spans.span_of(&at(2, 2)),
+            Some(CellRect::new(
+                (VisIdx(2), VisIdx(2)),
+                (VisIdx(1), VisIdx(2))
+            ))
+        );
+        assert_eq!(spans.span_of(&at(1, 2)), None);
+
+        // Moving down from the merged group jumps past it
+        let moved = TableSelection::SingleCell(cell(0, 0)).move_focus(
+            TableAxis::Rows,
+            VisOffset(1),
+            &spans,
+        );
+        assert_eq!(
+            moved.and_then(|sel| sel.vis_focus().cloned()),
+            Some(at(3, 0))
+        );
+        let moved = TableSelection::SingleCell(cell(2, 0)).move_focus(
+            TableAxis::Columns,
+            VisOffset(1),
+            &spans,
+        );
+        assert_eq!(
+            moved.and_then(|sel| sel.vis_focus().cloned()),
+            Some(at(2, 1))
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::axis_measure::{AxisPair, LogIdx};
+use crate::cells::CellsDelegate;
+use crate::clipboard::shown_bounds;
+use crate::columns::CellCtx;
+use crate::data::{IndexedData, Remap};
+use crate::selection::{CellDemap, SingleCell, TableSelection};
+use druid::Data;
+
+// Figures for the selected cells, as a status bar shows them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectionStats {
+    pub count: usize,         // Cells with any text
+    pub numeric_count: usize, // Cells whose text is a number
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl SelectionStats {
+    pub fn average(&self) -> Option<f64> {
+        if_opt!(self.numeric_count > 0, self.sum / self.numeric_count as f64)
+    }
+
+    fn add(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        self.count += 1;
+        if let Ok(value) = text.parse::<f64>() {
+            self.numeric_count += 1;
+            self.sum += value;
+            self.min = Some(self.min.map_or(value, |min| min.min(value)));
+            self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        }
+    }
+}
+
+// Sums up the text of the selected cells. Each cell counts once, however the ranges added with
+// Ctrl overlap.
+pub fn selection_stats<TableData, CD>(
+    delegate: &CD,
+    data: &TableData,
+    remaps: &AxisPair<Remap>,
+    selection: &TableSelection,
+) -> SelectionStats
+where
+    TableData: IndexedData<Idx = LogIdx>,
+    TableData::Item: Data,
+    CD: CellsDelegate<TableData>,
+{
+    let bounds = shown_bounds(delegate, data, remaps);
+    let mut stats = SelectionStats::default();
+    for vis in selection.selected_cells(&bounds) {
+        let text = remaps.get_log_cell(&vis).and_then(|log| {
+            let row = log.row;
+            let cell = SingleCell::new(vis, log);
+            data.with(row, |row| delegate.cell_text(&CellCtx::Cell(&cell), row))
+                .flatten()
+        });
+        if let Some(text) = text {
+            stats.add(&text);
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_fixtures::{cell, pristine_remaps, word_columns, word_rows};
+
+    #[test]
+    fn stats_count_overlapping_ranges_once() {
+        let columns = word_columns();
+        let data = word_rows(&[("one", 0.25), ("", 0.5), ("three", 1.)]);
+        let remaps = pristine_remaps();
+        // The whole second column, and the bottom row overlapping it
+        let selection = TableSelection::SingleCell(cell(0, 1))
+            .move_extent(TableSelection::SingleCell(cell(2, 1)))
+            .and_then(|sel| sel.add_selection(TableSelection::SingleCell(cell(2, 0))))
+            .and_then(|sel| sel.move_extent(TableSelection::SingleCell(cell(2, 1))))
+            .unwrap();
+
+        let stats = selection_stats(&columns, &data, &remaps, &selection);
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.numeric_count, 3);
+        assert_eq!(stats.sum, 1.75);
+        assert_eq!(stats.min, Some(0.25));
+        assert_eq!(stats.max, Some(1.));
+        assert_eq!(stats.average(), Some(1.75 / 3.));
+
+        let stats = selection_stats(&columns, &data, &remaps, &TableSelection::NoSelection);
+        assert_eq!(stats, SelectionStats::default());
+        assert_eq!(stats.average(), None);
+    }
+}
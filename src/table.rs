@@ -1,6 +1,11 @@
 use crate::axis_measure::{AxisMeasure, AxisPair, TableAxis, VisOffset};
 use crate::cells::CellsDelegate;
+use crate::data::Pin;
+use crate::groups::ColumnGroups;
 use crate::headings::HeadersFromData;
+use crate::menus::HeaderMenus;
+use crate::pinned::{ColumnRegion, PinnedColumns, Regions, Shared};
+use crate::row_moves::RowMoves;
 use crate::selection::CellDemap;
 use crate::{
     CellRender, Cells, Headings, IndexedData, IndexedItems, LogIdx, Remap, RemapSpec, TableConfig,
@@ -12,7 +17,7 @@ use druid::widget::{
 };
 use druid::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, Lens, LifeCycle, LifeCycleCtx, PaintCtx,
-    Point, Rect, Size, UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod,
+    Point, Rect, Size, Target, UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod,
 };
 use druid_bindings::*;
 
@@ -74,6 +79,9 @@ pub struct TableArgs<
     row_h: Option<RowH>,
     col_h: Option<ColH>,
     table_config: TableConfig,
+    column_groups: ColumnGroups,
+    header_menus: Option<HeaderMenus>,
+    row_moves: Option<RowMoves<TableData>>,
 }
 
 impl<
@@ -95,8 +103,29 @@ impl<
             row_h,
             col_h,
             table_config,
+            column_groups: ColumnGroups::new(),
+            header_menus: None,
+            row_moves: None,
         }
     }
+
+    // Bands of group headers above the column headers
+    pub fn column_groups(mut self, column_groups: ColumnGroups) -> Self {
+        self.column_groups = column_groups;
+        self
+    }
+
+    // Context menus when headers are right clicked
+    pub fn header_menus(mut self, header_menus: Option<HeaderMenus>) -> Self {
+        self.header_menus = header_menus;
+        self
+    }
+
+    // Dragging row headers moves the rows in the data
+    pub fn row_moves(mut self, row_moves: Option<RowMoves<TableData>>) -> Self {
+        self.row_moves = row_moves;
+        self
+    }
 }
 
 // This trait exists to move type parameters to associated types
@@ -132,8 +161,8 @@ where
 
 #[derive(Data, Clone, Debug, Lens)]
 pub(crate) struct TableState<TableData: Data> {
-    scroll_x: f64,
-    scroll_y: f64,
+    pub(crate) scroll_x: f64,
+    pub(crate) scroll_y: f64,
     pub(crate) data: TableData,
     pub(crate) remap_specs: AxisPair<RemapSpec>,
     pub(crate) remaps: AxisPair<Remap>,
@@ -176,6 +205,79 @@ impl<TableData: Data> TableState<TableData> {
             }
         }
     }
+
+    // How wide the columns pinned to the left and right are
+    pub(crate) fn pinned_widths(&self) -> (f64, f64) {
+        let spec = &self.remap_specs[TableAxis::Columns];
+        let measure = &self.measures[TableAxis::Columns];
+        let total = measure.total_pixel_length();
+        let start_of = |vis: usize| measure.first_pixel_from_vis(VisIdx(vis)).unwrap_or(total);
+        let (left, right) = (spec.pinned_count(Pin::Left), spec.pinned_count(Pin::Right));
+        let left_width = if left > 0 { start_of(left) } else { 0. };
+        let right_width = match measure.vis_len().checked_sub(right) {
+            Some(first) if right > 0 => total - start_of(first),
+            _ => 0.,
+        };
+        (left_width, right_width)
+    }
+
+    // The pixels along the columns that a region shows, from start to end
+    pub(crate) fn region_pixels(&self, region: ColumnRegion) -> (f64, f64) {
+        let (left_width, right_width) = self.pinned_widths();
+        let total = self.measures[TableAxis::Columns].total_pixel_length();
+        match region {
+            ColumnRegion::Left => (0., left_width),
+            ColumnRegion::Scrolling => (left_width, (total - right_width).max(left_width)),
+            ColumnRegion::Right => (total - right_width, total),
+        }
+    }
+
+    // The horizontal scroll is across the columns between the pinned ones
+    pub(crate) fn scroll(&self, axis: TableAxis) -> f64 {
+        match axis {
+            TableAxis::Rows => self.scroll_y,
+            TableAxis::Columns => self.scroll_x,
+        }
+    }
+
+    pub(crate) fn set_scroll(&mut self, axis: TableAxis, scroll: f64) {
+        match axis {
+            TableAxis::Rows => self.scroll_y = scroll,
+            TableAxis::Columns => self.scroll_x = scroll,
+        }
+    }
+
+    // The scroll that shows the whole of a row or column in a view of this length, moving as
+    // little as possible. Pinned columns are always in view.
+    pub(crate) fn scroll_to_show(&self, axis: TableAxis, vis: VisIdx, view: f64) -> Option<f64> {
+        if view <= 0. {
+            return None;
+        }
+        let offset = match axis {
+            TableAxis::Rows => 0.,
+            TableAxis::Columns if self.region_of_column(vis) == ColumnRegion::Scrolling => {
+                self.pinned_widths().0
+            }
+            TableAxis::Columns => return None,
+        };
+        let measure = &self.measures[axis];
+        let start = measure.first_pixel_from_vis(vis)? - offset;
+        let end = measure.far_pixel_from_vis(vis)? - offset;
+        let scroll = self.scroll(axis).max(end - view);
+        Some(scroll.min(start).max(0.))
+    }
+
+    pub(crate) fn region_of_column(&self, vis: VisIdx) -> ColumnRegion {
+        let spec = &self.remap_specs[TableAxis::Columns];
+        let len = self.measures[TableAxis::Columns].vis_len();
+        if vis.0 < spec.pinned_count(Pin::Left) {
+            ColumnRegion::Left
+        } else if vis.0 + spec.pinned_count(Pin::Right) >= len {
+            ColumnRegion::Right
+        } else {
+            ColumnRegion::Scrolling
+        }
+    }
 }
 
 impl CellDemap for AxisPair<Remap> {
@@ -220,6 +322,7 @@ impl AxisIds {
 
 struct Ids {
     cells: WidgetId,
+    regions: Regions,
     rows: Option<AxisIds>,
     columns: Option<AxisIds>,
 }
@@ -228,6 +331,7 @@ impl Ids {
     pub fn new(cells: WidgetId, rows: Option<AxisIds>, columns: Option<AxisIds>) -> Self {
         Ids {
             cells,
+            regions: Regions::new(cells),
             rows,
             columns,
         }
@@ -266,12 +370,17 @@ impl<Args: TableArgsT + 'static> Table<Args> {
             if_opt!(col_headings, AxisIds::new()),
         );
 
-        let cells_delegate = args.cells_delegate;
-        let cells = Cells::new(table_config.clone(), cells_delegate);
+        // Each region of columns has its own cells, all drawing on the one delegate
+        let cells_delegate = Shared::new(args.cells_delegate);
+        let regions = ids.regions.clone();
+        let cells = |region: ColumnRegion| {
+            Cells::new(table_config.clone(), cells_delegate.clone())
+                .in_region(region, regions.clone())
+        };
 
         // These have to be added before we move Cells into scroll
 
-        let cells_scroll = Scroll::new(cells.with_id(ids.cells)).binding(
+        let cells_scroll = Scroll::new(cells(ColumnRegion::Scrolling).with_id(ids.cells)).binding(
             TableState::<Args::TableData>::scroll_x
                 .bind(ScrollToProperty::new(Axis::Horizontal))
                 .and(
@@ -279,56 +388,127 @@ impl<Args: TableArgsT + 'static> Table<Args> {
                         .bind(ScrollToProperty::new(Axis::Vertical)),
                 ),
         );
-
-        Self::add_headings(args.col_h, args.row_h, table_config, ids, cells_scroll)
+        // Pinned columns only scroll up and down, along with the rest
+        let pinned_scroll = |region: ColumnRegion| {
+            Scroll::new(cells(region).with_id(regions.id(region)))
+                .vertical()
+                .disable_scrollbars()
+                .binding(
+                    TableState::<Args::TableData>::scroll_y
+                        .bind(ScrollToProperty::new(Axis::Vertical)),
+                )
+        };
+        let cells_row = PinnedColumns::new(
+            pinned_scroll(ColumnRegion::Left),
+            cells_scroll,
+            pinned_scroll(ColumnRegion::Right),
+            regions.clone(),
+        )
+        .holds_cells();
+
+        Self::add_headings(
+            args.col_h,
+            args.row_h,
+            table_config,
+            args.column_groups,
+            args.header_menus,
+            args.row_moves,
+            ids,
+            cells_row,
+        )
     }
 
     fn add_headings(
         col_h: Option<Args::ColH>,
         row_h: Option<Args::RowH>,
         table_config: TableConfig,
+        column_groups: ColumnGroups,
+        header_menus: Option<HeaderMenus>,
+        row_moves: Option<RowMoves<Args::TableData>>,
         ids: Ids,
         widget: impl Widget<TableState<Args::TableData>> + 'static,
     ) -> TableChild<Args::TableData> {
         if let (Some(AxisIds { headers, scroll }), Some(col_h)) = (ids.columns, col_h) {
             let (source, render) = col_h.content();
-
-            let col_headings = Headings::new(
-                TableAxis::Columns,
-                table_config.clone(),
-                source,
-                render,
-                true,
-            );
-            let ch_scroll = Scroll::new(col_headings.with_id(headers))
+            let (source, render) = (Shared::new(source), Shared::new(render));
+            let header_rows = column_groups.levels() + 1;
+
+            let col_headings = |region: ColumnRegion| {
+                let mut col_headings = Headings::new(
+                    TableAxis::Columns,
+                    table_config.clone(),
+                    source.clone(),
+                    render.clone(),
+                    true,
+                )
+                .groups(column_groups.clone())
+                .cells(ids.cells)
+                .filters(table_config.filters)
+                .in_region(region, ids.regions.clone());
+                if let Some(menus) = header_menus.clone() {
+                    col_headings = col_headings.menus(menus);
+                }
+                col_headings
+            };
+            let ch_scroll = Scroll::new(col_headings(ColumnRegion::Scrolling).with_id(headers))
                 .disable_scrollbars()
                 .with_id(scroll)
                 .binding(
                     TableState::<Args::TableData>::scroll_x
                         .bind(ScrollToProperty::new(Axis::Horizontal)),
                 );
+            let header_row = PinnedColumns::new(
+                col_headings(ColumnRegion::Left),
+                ch_scroll,
+                col_headings(ColumnRegion::Right),
+                ids.regions.clone(),
+            );
 
             let cells_column = Flex::column()
                 .cross_axis_alignment(CrossAxisAlignment::Start)
-                .with_child(ch_scroll)
+                .with_child(header_row)
                 .with_flex_child(widget, 1.);
-            Self::add_row_headings(table_config, true, row_h, ids, cells_column)
+            Self::add_row_headings(
+                table_config,
+                header_rows,
+                header_menus,
+                row_moves,
+                row_h,
+                ids,
+                cells_column,
+            )
         } else {
-            Self::add_row_headings(table_config, false, row_h, ids, widget)
+            Self::add_row_headings(table_config, 0, header_menus, row_moves, row_h, ids, widget)
         }
     }
 
     fn add_row_headings(
         table_config: TableConfig,
-        corner_needed: bool,
+        corner_rows: usize,
+        header_menus: Option<HeaderMenus>,
+        row_moves: Option<RowMoves<Args::TableData>>,
         row_h: Option<Args::RowH>,
         ids: Ids,
         widget: impl Widget<TableState<Args::TableData>> + 'static,
     ) -> TableChild<Args::TableData> {
         if let (Some(AxisIds { headers, scroll }), Some(row_h)) = (ids.rows, row_h) {
             let (source, render) = row_h.content();
-            let row_headings =
-                Headings::new(TableAxis::Rows, table_config.clone(), source, render, false);
+            // Rows are only moved by dragging when that moves them in the data
+            let allow_moves = row_moves.is_some();
+            let mut row_headings = Headings::new(
+                TableAxis::Rows,
+                table_config.clone(),
+                source,
+                render,
+                allow_moves,
+            )
+            .cells(ids.cells);
+            if let Some(menus) = header_menus {
+                row_headings = row_headings.menus(menus);
+            }
+            if let Some(row_moves) = row_moves {
+                row_headings = row_headings.row_moves(row_moves);
+            }
 
             let row_scroll = Scroll::new(row_headings.with_id(headers))
                 .disable_scrollbars()
@@ -339,8 +519,8 @@ impl<Args: TableArgsT + 'static> Table<Args> {
                 );
 
             let mut rh_col = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
-            if corner_needed {
-                rh_col.add_spacer(table_config.col_header_height)
+            for _ in 0..corner_rows {
+                rh_col.add_spacer(table_config.col_header_height.clone())
             }
             rh_col.add_flex_child(row_scroll, 1.);
 
@@ -366,6 +546,13 @@ impl<Args: TableArgsT + 'static> Widget<TableState<Args::TableData>> for Table<A
         env: &Env,
     ) {
         if let Some(child) = self.child.as_mut() {
+            // Commands for the table are handled by its cells
+            if let Event::Command(cmd) = event {
+                if cmd.target() == Target::Widget(ctx.widget_id()) {
+                    ctx.submit_command(cmd.clone().to(child.ids.cells));
+                    return;
+                }
+            }
             child.pod.event(ctx, event, data, env);
         }
     }
@@ -0,0 +1,36 @@
+// Tables shared by the tests that read and write cells through their columns
+use crate::axis_measure::{AxisPair, LogIdx, VisIdx};
+use crate::chart_cells::ProgressBarCell;
+use crate::columns::{column, CellDelegate, CellRenderExt, ProvidedColumns, TextCell};
+use crate::data::Remap;
+use crate::selection::SingleCell;
+use druid::im::Vector;
+
+pub(crate) type Row = (String, f64);
+
+// A word, and a progress that fails validation outside 0 to 1
+pub(crate) fn word_columns() -> ProvidedColumns<Vector<Row>, Box<dyn CellDelegate<Row>>> {
+    ProvidedColumns::new(vec![
+        column("Word", TextCell::new().lens(lens!(Row, 0))),
+        column("Done", ProgressBarCell::new().lens(lens!(Row, 1))),
+    ])
+}
+
+pub(crate) fn word_rows(rows: &[(&str, f64)]) -> Vector<Row> {
+    rows.iter()
+        .map(|(word, done)| (word.to_string(), *done))
+        .collect()
+}
+
+pub(crate) fn pristine_remaps() -> AxisPair<Remap> {
+    AxisPair::new(Remap::Pristine, Remap::Pristine)
+}
+
+pub(crate) fn at(row: usize, col: usize) -> AxisPair<VisIdx> {
+    AxisPair::new(VisIdx(row), VisIdx(col))
+}
+
+// A cell of a table that isn't remapped, so it is shown where it is in the data
+pub(crate) fn cell(row: usize, col: usize) -> SingleCell {
+    SingleCell::new(at(row, col), AxisPair::new(LogIdx(row), LogIdx(col)))
+}
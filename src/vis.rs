@@ -1035,7 +1035,7 @@ impl<T: Clone + Ord + Hash + Display> BandScale<T> {
     }
 }
 
-pub trait LinearValue: Clone + Ord + Display + Default {
+pub trait LinearValue: Clone + PartialOrd + Display + Default {
     fn as_f64(&self) -> f64;
     fn from_f64(val: f64) -> Self;
 }
@@ -1050,6 +1050,16 @@ impl LinearValue for u32 {
     }
 }
 
+impl LinearValue for f64 {
+    fn as_f64(&self) -> f64 {
+        *self
+    }
+
+    fn from_f64(val: f64) -> Self {
+        val
+    }
+}
+
 #[derive(Copy, Clone, Debug, Data)]
 pub struct F64Range(pub f64, pub f64);
 